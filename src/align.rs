@@ -0,0 +1,116 @@
+//! Alignment of text in a rectangle.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::Rectangle,
+    text::{renderer::TextRenderer, Baseline},
+};
+
+/// Horizontal position of text in a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HorizontalAlign {
+    /// Against the left edge (default).
+    #[default]
+    Left,
+    /// Centered between the edges.
+    Center,
+    /// Against the right edge.
+    Right,
+}
+
+/// Vertical position of text in a rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalAlign {
+    /// Against the top edge (default).
+    #[default]
+    Top,
+    /// Centered between the edges.
+    Middle,
+    /// Against the bottom edge.
+    Bottom,
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw `text` aligned in `area`.
+    ///
+    /// The box of `measure_string` is aligned, text larger than the area starts at its top left
+    /// corner and overflows it. Returns the position following the last character.
+    pub fn draw_aligned<D>(
+        &self,
+        text: &str,
+        area: Rectangle,
+        horizontal: HorizontalAlign,
+        vertical: VerticalAlign,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let bounding_box = self
+            .measure_string(text, Point::zero(), Baseline::Top)
+            .bounding_box;
+        let free = area.size.saturating_sub(bounding_box.size);
+        let x = match horizontal {
+            HorizontalAlign::Left => 0,
+            HorizontalAlign::Center => free.width / 2,
+            HorizontalAlign::Right => free.width,
+        };
+        let y = match vertical {
+            VerticalAlign::Top => 0,
+            VerticalAlign::Middle => free.height / 2,
+            VerticalAlign::Bottom => free.height,
+        };
+        let aligned = area.top_left + Point::new(x as i32, y as i32);
+        self.draw_string(text, aligned - bounding_box.top_left, Baseline::Top, target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+    use rusttype::Font;
+
+    #[test]
+    fn text_is_aligned_in_the_area() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .build();
+        let area = Rectangle::new(Point::new(2, 4), Size::new(60, 40));
+        let size = style
+            .measure_string("Hi", Point::zero(), Baseline::Top)
+            .bounding_box
+            .size;
+
+        let drawn = |horizontal, vertical| {
+            let mut display = MockDisplay::new();
+            style
+                .draw_aligned("Hi", area, horizontal, vertical, &mut display)
+                .unwrap();
+            display.affected_area()
+        };
+        let top_left = |x, y| Rectangle::new(Point::new(x, y), size);
+
+        assert_eq!(
+            drawn(HorizontalAlign::Left, VerticalAlign::Top),
+            top_left(2, 4)
+        );
+        assert_eq!(
+            drawn(HorizontalAlign::Center, VerticalAlign::Middle),
+            top_left(2 + (60 - size.width as i32) / 2, 4 + (40 - 16) / 2)
+        );
+        assert_eq!(
+            drawn(HorizontalAlign::Right, VerticalAlign::Bottom),
+            top_left(62 - size.width as i32, 28)
+        );
+    }
+}
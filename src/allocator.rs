@@ -0,0 +1,65 @@
+//! Allocation of the rendering buffers.
+//!
+//! With the `allocator-api2` feature, the pixel buffers of a style and the coverage stored in a
+//! [`GlyphCache`](crate::GlyphCache) can come from a user provided allocator, like a dedicated
+//! memory pool, instead of the global heap. Without it, they are regular `Vec`s and the allocator
+//! handle is a zero sized placeholder.
+
+#[cfg(feature = "allocator-api2")]
+use allocator_api2::alloc::{Allocator, Global};
+
+/// Handle to the allocator used for rendering buffers.
+#[cfg(feature = "allocator-api2")]
+pub type AllocatorRef = &'static (dyn Allocator + Sync);
+
+/// Allocator handle stored by styles and caches.
+#[cfg(feature = "allocator-api2")]
+#[derive(Clone, Copy)]
+pub(crate) struct Alloc(pub(crate) AllocatorRef);
+
+#[cfg(feature = "allocator-api2")]
+impl crate::stdlib::fmt::Debug for Alloc {
+    fn fmt(&self, f: &mut crate::stdlib::fmt::Formatter<'_>) -> crate::stdlib::fmt::Result {
+        f.write_str("Alloc")
+    }
+}
+
+/// Allocator handle stored by styles and caches, buffers use the global heap.
+#[cfg(not(feature = "allocator-api2"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Alloc;
+
+/// Growable buffer allocated with the rendering allocator.
+#[cfg(feature = "allocator-api2")]
+pub(crate) type Buffer<T> = allocator_api2::vec::Vec<T, AllocatorRef>;
+
+/// Growable buffer allocated with the rendering allocator.
+#[cfg(not(feature = "allocator-api2"))]
+pub(crate) type Buffer<T> = crate::stdlib::vec::Vec<T>;
+
+/// The global heap.
+#[cfg(feature = "allocator-api2")]
+pub(crate) const GLOBAL: Alloc = Alloc(&Global);
+
+/// The global heap.
+#[cfg(not(feature = "allocator-api2"))]
+pub(crate) const GLOBAL: Alloc = Alloc;
+
+/// Create an empty buffer in `allocator`.
+#[cfg(feature = "allocator-api2")]
+pub(crate) fn buffer<T>(allocator: Alloc) -> Buffer<T> {
+    Buffer::new_in(allocator.0)
+}
+
+/// Create an empty buffer in `allocator`.
+#[cfg(not(feature = "allocator-api2"))]
+pub(crate) fn buffer<T>(_allocator: Alloc) -> Buffer<T> {
+    Buffer::new()
+}
+
+/// Create a buffer of `len` zeroes in `allocator`.
+pub(crate) fn zeroed(len: usize, allocator: Alloc) -> Buffer<u8> {
+    let mut buffer = self::buffer(allocator);
+    buffer.resize(len, 0);
+    buffer
+}
@@ -0,0 +1,123 @@
+//! Text along a circle.
+
+use crate::{
+    allocator,
+    stdlib::fmt::Debug,
+    transform::{transformed_coverage, AffineTransform},
+    ColorBlend, FontTextStyle,
+};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::Float;
+
+use embedded_graphics::{geometry::Angle, prelude::*, primitives::Rectangle};
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw `text` along a circle of `radius` pixels around `center`.
+    ///
+    /// The baseline follows the circle clockwise from `start_angle`, with angles measured like
+    /// embedded-graphics arcs: 0° points right and angles grow clockwise. Each glyph is rotated
+    /// to stand on the circle, its top pointing away from the center, so text starting at -90°
+    /// reads across the top of a dial. The text is laid out as a single line, without background
+    /// or decorations.
+    ///
+    /// Returns the angle following the last glyph.
+    pub fn draw_on_arc<D>(
+        &self,
+        text: &str,
+        center: Point,
+        radius: u32,
+        start_angle: Angle,
+        target: &mut D,
+    ) -> Result<Angle, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let layout = self.layout(text);
+        let radius = radius.max(1) as f32;
+        let baseline = self.v_metrics().ascent;
+        let start = start_angle.to_radians();
+        let center = (center.x as f32, center.y as f32);
+
+        if self.colors().0.is_none() && self.fill.is_none() {
+            return Ok(Angle::from_radians(start + layout.advance / radius));
+        }
+        // fills span the circle and the glyphs standing on it
+        let outer = radius.round() as i32 + self.pixel_size() as i32;
+        let area = Rectangle::with_center(
+            Point::new(center.0 as i32, center.1 as i32),
+            Size::new_equal(2 * outer as u32 + 1),
+        );
+        let mut pixels = allocator::buffer(self.allocator);
+        let glyphs = layout.glyphs.iter().zip(layout.glyph_sources.iter());
+        for (i, (glyph, (_, advance))) in glyphs.enumerate() {
+            let position = glyph.position();
+            // glyphs are placed by the middle of their advance
+            let middle = position.x + advance / 2.0;
+            let angle = start + middle / radius;
+            let (sin, cos) = (angle.sin(), angle.cos());
+            let transform = AffineTransform::translation(-advance / 2.0, position.y - baseline)
+                .then(&AffineTransform::rotation(Angle::from_radians(
+                    angle + core::f32::consts::FRAC_PI_2,
+                )))
+                .then(&AffineTransform::translation(
+                    center.0 + radius * cos,
+                    center.1 + radius * sin,
+                ));
+            transformed_coverage(glyph.unpositioned(), &transform, |x, y, coverage| {
+                let point = Point::new(x, y);
+                let blended = self
+                    .glyph_color(point, i, &area)
+                    .and_then(|text_color| self.blend(text_color, coverage, point));
+                if let Some(color) = blended {
+                    pixels.push(Pixel(point, color));
+                }
+            });
+        }
+        self.draw_pixels(pixels, &mut self.clipped_target(target))?;
+        Ok(Angle::from_radians(start + layout.advance / radius))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+    use rusttype::Font;
+
+    #[test]
+    fn text_follows_the_circle() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(12)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let center = Point::new(32, 32);
+
+        let mut display = MockDisplay::new();
+        let end = style
+            .draw_on_arc("IIII", center, 20, Angle::from_degrees(-90.0), &mut display)
+            .unwrap();
+        let advance = style.layout("IIII").advance;
+        assert!((end.to_radians() + core::f32::consts::FRAC_PI_2 - advance / 20.0).abs() < 1e-5);
+
+        // glyphs stand outside the circle, within a font size of it
+        let drawn = display.affected_area();
+        assert!(!drawn.is_zero_sized());
+        for point in drawn.points() {
+            if display.get_pixel(point).is_some() {
+                let offset = point - center;
+                let distance = ((offset.x * offset.x + offset.y * offset.y) as f32).sqrt();
+                assert!((19.0..=33.0).contains(&distance), "{:?}", point);
+            }
+        }
+        // text starting at the top is drawn to the right of it
+        assert!(drawn.top_left.x >= center.x - 1);
+        assert!(drawn.top_left.y < center.y - 20);
+    }
+}
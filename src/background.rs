@@ -0,0 +1,193 @@
+//! Background behind the text.
+//!
+//! The background fills the box of each line, from the top of the ascent to the bottom of the
+//! descent and from the origin to the advance. A [`Padding`] grows it on each side, and its
+//! corners can be rounded, for labels and badges. With [`BackgroundArea::Glyphs`], it only fills
+//! the box of each glyph instead.
+
+use crate::FontTextStyle;
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle, RoundedRectangle},
+};
+
+/// Area filled by the background of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackgroundArea {
+    /// The line box, from the ascent to the descent and from the origin to the advance
+    /// (default).
+    #[default]
+    Line,
+    /// The bounding box of each glyph, leaving what is under the spaces between glyphs and
+    /// around them visible. Whitespace drawn separately has no background.
+    Glyphs,
+}
+
+/// Space in pixels added around the text by the background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Padding {
+    /// Space above the text.
+    pub top: u32,
+    /// Space after the text.
+    pub right: u32,
+    /// Space below the text.
+    pub bottom: u32,
+    /// Space before the text.
+    pub left: u32,
+}
+
+impl Padding {
+    /// Create a padding, the sides are in the order of CSS.
+    pub fn new(top: u32, right: u32, bottom: u32, left: u32) -> Self {
+        Padding {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// `area` grown by the padding.
+    pub(crate) fn around(&self, area: &Rectangle) -> Rectangle {
+        Rectangle::new(
+            area.top_left - Point::new(self.left as i32, self.top as i32),
+            area.size + Size::new(self.left + self.right, self.top + self.bottom),
+        )
+    }
+
+    /// `area` grown by the top and bottom padding only, for whitespace within a line.
+    pub(crate) fn above_and_below(&self, area: &Rectangle) -> Rectangle {
+        Padding::new(self.top, 0, self.bottom, 0).around(area)
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Fill the background `area` of a line with `color`, with rounded corners if set.
+    pub(crate) fn fill_background<D>(
+        &self,
+        area: &Rectangle,
+        color: C,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        match self.background_corner_radius {
+            0 => target.fill_solid(area, color),
+            radius => RoundedRectangle::with_equal_corners(*area, Size::new_equal(radius))
+                .into_styled(PrimitiveStyle::with_fill(color))
+                .draw(target),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn padding_grows_the_background() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let builder = || {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(16)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLUE)
+        };
+        let draw = |style: &crate::FontTextStyle<Rgb888>| {
+            let mut display = MockDisplay::new();
+            let next = style
+                .draw_string("Hi", Point::new(8, 6), Baseline::Top, &mut display)
+                .unwrap();
+            (display, next)
+        };
+
+        let plain = builder().build();
+        let padded = builder().background_padding(2, 3, 4, 5).build();
+        let (plain_display, plain_next) = draw(&plain);
+        let (padded_display, padded_next) = draw(&padded);
+        assert_eq!(padded_next, plain_next);
+
+        let area = Padding::new(2, 3, 4, 5).around(&plain_display.affected_area());
+        assert_eq!(padded_display.affected_area(), area);
+        let measured = padded.measure_string("Hi", Point::new(8, 6), Baseline::Top);
+        assert_eq!(measured.bounding_box, area);
+        // the text is in place, over the background
+        for point in plain_display.affected_area().points() {
+            assert_eq!(
+                padded_display.get_pixel(point),
+                plain_display.get_pixel(point)
+            );
+        }
+        assert_eq!(padded_display.get_pixel(area.top_left), Some(Rgb888::BLUE));
+    }
+
+    #[test]
+    fn corners_are_rounded() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLUE)
+            .background_padding(2, 6, 2, 6)
+            .background_corner_radius(6)
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        style
+            .draw_string("Hi", Point::new(8, 6), Baseline::Top, &mut display)
+            .unwrap();
+
+        let area = style
+            .measure_string("Hi", Point::new(8, 6), Baseline::Top)
+            .bounding_box;
+        assert_eq!(display.affected_area(), area);
+        let bottom_right = area.bottom_right().unwrap();
+        let center = area.center();
+        for corner in [area.top_left, bottom_right] {
+            assert_eq!(display.get_pixel(corner), None);
+        }
+        for edge in [
+            Point::new(center.x, area.top_left.y),
+            Point::new(bottom_right.x, center.y),
+        ] {
+            assert_eq!(display.get_pixel(edge), Some(Rgb888::BLUE));
+        }
+    }
+
+    #[test]
+    fn background_can_fill_glyph_boxes_only() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLUE)
+            .background_area(BackgroundArea::Glyphs)
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        style
+            .draw_string("H  i", Point::new(8, 6), Baseline::Top, &mut display)
+            .unwrap();
+
+        // nothing above the glyphs nor between them
+        let ink = style.ink_bounding_box("H  i", Point::new(8, 6));
+        assert_eq!(display.affected_area(), ink);
+        let line = style
+            .measure_string("H  i", Point::new(8, 6), Baseline::Top)
+            .bounding_box;
+        assert!(ink.size.height < line.size.height);
+        let gap = (ink.top_left.x..ink.top_left.x + ink.size.width as i32)
+            .filter(|x| display.get_pixel(Point::new(*x, ink.center().y)).is_none())
+            .count();
+        assert!(gap > 0);
+    }
+}
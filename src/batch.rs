@@ -0,0 +1,105 @@
+//! Drawing of many strings at once.
+
+use crate::{
+    allocator, layout::LineLayout, stats::Counter, stdlib::fmt::Debug, ColorBlend, FontTextStyle,
+};
+
+use crate::stdlib::vec::Vec;
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw several strings, each at its top left position, in a single batch.
+    ///
+    /// Strings appearing several times are laid out once. The glyph pixels of all strings are
+    /// sorted in row major order and sent to the target in a single `draw_iter` call, or as
+    /// spans with contiguous rows, which suits displays addressed line by line. Backgrounds are
+    /// filled before, and decorations drawn after, all the glyphs.
+    pub fn draw_strings<D>(&self, strings: &[(&str, Point)], target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let target = &mut self.clipped_target(target);
+        let mut layouts: Vec<(&str, LineLayout, Rectangle)> = Vec::new();
+        let mut lines = Vec::with_capacity(strings.len());
+        for (text, position) in strings {
+            let index = match layouts.iter().position(|(t, _, _)| t == text) {
+                Some(index) => index,
+                None => {
+                    let layout = self.layout(text);
+                    let line = self.line_box(&layout);
+                    layouts.push((text, layout, line));
+                    layouts.len() - 1
+                }
+            };
+            lines.push((index, *position));
+        }
+
+        let mut pixels = allocator::buffer(self.allocator);
+        for (index, position) in lines.iter().copied() {
+            let (_, layout, line) = &layouts[index];
+            let line = line.translate(position);
+            self.draw_line_background(&line, &layout.glyphs, position, target)?;
+            pixels.extend(self.shadow_pixels(&layout.glyphs, position));
+            pixels.extend(self.glow_pixels(&layout.glyphs, position));
+            pixels.extend(self.outline_pixels(&layout.glyphs, position));
+            pixels.extend(
+                self.glyph_pixels(&layout.glyphs, 0, position, Some(line), |_| None)
+                    .0,
+            );
+            pixels.extend(self.notdef_pixels(&layout.notdef_boxes, position));
+        }
+        // the sort is stable, effects stay behind the glyphs
+        pixels.sort_by_key(|Pixel(point, _)| (point.y, point.x));
+        self.count(Counter::DrawCalls, 1);
+        self.count(Counter::PixelsEmitted, pixels.len() as u32);
+        self.draw_pixels(pixels, target)?;
+
+        for (index, position) in lines {
+            self.draw_decorations(layouts[index].2.translate(position), target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        prelude::*,
+        text::{Baseline, Text},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn batch_matches_separate_draws() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(12)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLUE)
+            .underline()
+            .build();
+        let strings = [
+            ("12", Point::new(0, 0)),
+            ("34", Point::new(30, 20)),
+            ("12", Point::new(5, 40)),
+        ];
+
+        let mut separate = MockDisplay::new();
+        separate.set_allow_overdraw(true);
+        for (text, position) in strings {
+            Text::with_baseline(text, position, style.clone(), Baseline::Top)
+                .draw(&mut separate)
+                .unwrap();
+        }
+        let mut batched = MockDisplay::new();
+        batched.set_allow_overdraw(true);
+        style.draw_strings(&strings, &mut batched).unwrap();
+        batched.assert_eq(&separate);
+    }
+}
@@ -0,0 +1,102 @@
+//! Synthetic bold.
+//!
+//! Without a bold font, glyphs are emboldened by spreading their coverage a few pixels to the
+//! right, which thickens the vertical strokes the most, like the fake bold of most renderers.
+
+use crate::{allocator, FontTextStyle};
+
+use embedded_graphics::pixelcolor::PixelColor;
+use rusttype::PositionedGlyph;
+
+/// Spread the coverage of a row `weight` pixels to the right, in place.
+///
+/// Pixels take the highest coverage of themselves and the `weight` pixels on their left.
+pub(crate) fn embolden(row: &mut [u8], weight: usize) {
+    if weight == 0 {
+        return;
+    }
+    for x in (0..row.len()).rev() {
+        let start = x.saturating_sub(weight);
+        row[x] = row[start..=x].iter().copied().max().unwrap_or(0);
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Call `f(x, y, coverage)` for each pixel of a glyph emboldened by `faux_bold`, relative to
+    /// the layout origin.
+    pub(crate) fn bold_coverage(
+        &self,
+        glyph: &PositionedGlyph<'_>,
+        mut f: impl FnMut(i32, i32, u8),
+    ) {
+        let bounds = match self.glyph_bounds(glyph) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let (left, top) = (bounds.min.x, bounds.min.y);
+        let width = bounds.width() as usize;
+        let height = bounds.height() as usize;
+        let mut data = allocator::zeroed(width * height, self.allocator);
+        self.plain_coverage(glyph, |x, y, v| {
+            let (x, y) = ((x - left) as usize, (y - top) as usize);
+            if x < width && y < height {
+                data[y * width + x] = v;
+            }
+        });
+
+        for (y, row) in data.chunks_mut(width).enumerate() {
+            embolden(row, self.faux_bold as usize);
+            for (x, coverage) in row.iter().enumerate() {
+                if *coverage > 0 {
+                    f(left + x as i32, top + y as i32, *coverage);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        prelude::*,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn rows_spread_to_the_right() {
+        let mut row = [0, 255, 0, 0, 100, 0, 0];
+        embolden(&mut row, 2);
+        assert_eq!(row, [0, 255, 255, 255, 100, 100, 100]);
+    }
+
+    #[test]
+    fn bold_glyphs_are_wider() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let draw = |weight| {
+            let style = FontTextStyleBuilder::new(font.clone())
+                .font_size(16)
+                .text_color(Rgb888::WHITE)
+                .anti_aliasing_color(Rgb888::BLACK)
+                .faux_bold(weight)
+                .build();
+            let mut display = MockDisplay::new();
+            let next = style
+                .draw_string("l", Point::new(2, 2), Baseline::Top, &mut display)
+                .unwrap();
+            let ink = style.ink_bounding_box("l", Point::new(2, 2));
+            (display.affected_area(), next.x, ink)
+        };
+
+        let (regular, regular_next, _) = draw(0);
+        let (bold, bold_next, ink) = draw(2);
+        assert_eq!(bold.top_left, regular.top_left);
+        assert_eq!(bold.size, regular.size + Size::new(2, 0));
+        assert_eq!(bold_next, regular_next + 2);
+        assert_eq!(ink, bold);
+    }
+}
@@ -0,0 +1,842 @@
+//! Glyph coverage cache.
+//!
+//! Rasterizing a glyph is by far the most expensive part of drawing text. A [`GlyphCache`] keeps
+//! the coverage of recently drawn glyphs so that they can be blended again without going through
+//! rusttype.
+//!
+//! [`TextRenderer::draw_string`](embedded_graphics::text::renderer::TextRenderer::draw_string)
+//! only gets `&self`, so the cache is updated through interior mutability. The cell used depends
+//! on the enabled features:
+//! * `critical-section`: a `critical_section::Mutex`, with `std` the cache can then be shared
+//!   between threads and interrupt handlers. Without `std`, [`SharedGlyphCache`] is an `Rc`, which
+//!   can't be sent to an interrupt handler,
+//! * `std`: a `std::sync::Mutex`, the cache can be shared between threads,
+//! * otherwise: a `RefCell`, for single threaded use.
+
+use crate::{
+    allocator::{self, Alloc, Buffer},
+    stdlib::{fmt, vec::Vec},
+};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+#[cfg(all(not(feature = "critical-section"), not(feature = "std")))]
+use crate::stdlib::cell::RefCell;
+
+use rusttype::{point, Font, PositionedGlyph};
+
+/// Number of horizontal and vertical subpixel positions a glyph is rasterized at when cached.
+const SUBPIXEL_STEPS: f32 = 4.0;
+
+/// Shared handle to a [`GlyphCache`], as stored in a [`FontTextStyle`](crate::FontTextStyle).
+#[cfg(feature = "std")]
+pub type SharedGlyphCache = std::sync::Arc<GlyphCache>;
+
+/// Shared handle to a [`GlyphCache`], as stored in a [`FontTextStyle`](crate::FontTextStyle).
+#[cfg(not(feature = "std"))]
+pub type SharedGlyphCache = alloc::rc::Rc<GlyphCache>;
+
+/// Number of bits stored per pixel of cached glyph coverage.
+///
+/// Lower depths divide the memory used by cached glyphs by 2 or 4, with fewer antialiasing
+/// levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoverageDepth {
+    /// 256 coverage levels (default).
+    #[default]
+    Bits8,
+    /// 16 coverage levels.
+    Bits4,
+    /// 4 coverage levels.
+    Bits2,
+}
+
+/// Expansion of 4 bit coverage to 8 bits.
+const LEVELS_4: [u8; 16] = [
+    0, 17, 34, 51, 68, 85, 102, 119, 136, 153, 170, 187, 204, 221, 238, 255,
+];
+
+/// Expansion of 2 bit coverage to 8 bits.
+const LEVELS_2: [u8; 4] = [0, 85, 170, 255];
+
+impl CoverageDepth {
+    fn bits(self) -> u32 {
+        match self {
+            CoverageDepth::Bits8 => 8,
+            CoverageDepth::Bits4 => 4,
+            CoverageDepth::Bits2 => 2,
+        }
+    }
+
+    /// Quantize an 8 bit coverage to this depth, rounding to the nearest level.
+    fn quantize(self, v: u8) -> u8 {
+        let max = (1u32 << self.bits()) - 1;
+        ((v as u32 * max + 127) / 255) as u8
+    }
+
+    /// Expand a quantized coverage back to 8 bits.
+    fn expand(self, v: u8) -> u8 {
+        match self {
+            CoverageDepth::Bits8 => v,
+            CoverageDepth::Bits4 => LEVELS_4[v as usize],
+            CoverageDepth::Bits2 => LEVELS_2[v as usize],
+        }
+    }
+}
+
+/// How glyphs are chosen for eviction when the cache is full.
+///
+/// Pinned glyphs (see [`GlyphCache::pin`]) are never evicted, whatever the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EvictionPolicy {
+    /// Evict the least recently used glyph (default).
+    #[default]
+    LeastRecentlyUsed,
+    /// Evict the least frequently used glyph, the least recently used one on ties.
+    LeastFrequentlyUsed,
+    /// Evict the least recently used glyph among those large enough to make room for the new one,
+    /// so that a large glyph doesn't evict many small ones. Falls back to least recently used.
+    SizeClass,
+}
+
+/// Identifies a rasterized glyph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct GlyphKey {
+    font: usize,
+    id: u16,
+    scale_x: u32,
+    scale_y: u32,
+    subpixel_x: u8,
+    subpixel_y: u8,
+}
+
+/// Rasterized coverage of a single glyph.
+///
+/// Offsets are relative to the integer part of the glyph position. Pixels are packed in rows,
+/// most significant bits first, at the cache depth.
+#[derive(Debug, Clone)]
+pub(crate) struct Coverage {
+    left: i32,
+    top: i32,
+    width: u32,
+    height: u32,
+    depth: CoverageDepth,
+    data: Buffer<u8>,
+}
+
+impl Coverage {
+    /// Rasterize a glyph, `None` if it has no outline (like a space).
+    fn rasterize(
+        glyph: &PositionedGlyph<'_>,
+        depth: CoverageDepth,
+        allocator: Alloc,
+    ) -> Option<Self> {
+        let bb = glyph.pixel_bounding_box()?;
+        let width = bb.width() as u32;
+        let bits = depth.bits();
+        let pixels = width as usize * bb.height() as usize;
+        let mut data = allocator::zeroed((pixels * bits as usize).div_ceil(8), allocator);
+        glyph.draw(|x, y, v| {
+            let v = depth.quantize((v * 255.0).min(255.0) as u8);
+            let bit = (y * width + x) * bits;
+            data[(bit / 8) as usize] |= v << (8 - bits - bit % 8);
+        });
+        Some(Coverage {
+            left: bb.min.x,
+            top: bb.min.y,
+            width,
+            height: bb.height() as u32,
+            depth,
+            data,
+        })
+    }
+
+    /// Call `f(x, y, coverage)` for each pixel, offset by `(x, y)`.
+    fn for_each(&self, x: i32, y: i32, mut f: impl FnMut(i32, i32, u8)) {
+        let bits = self.depth.bits();
+        let mask = ((1u32 << bits) - 1) as u8;
+        for i in 0..self.width * self.height {
+            let bit = i * bits;
+            let v = (self.data[(bit / 8) as usize] >> (8 - bits - bit % 8)) & mask;
+            f(
+                x + self.left + (i % self.width) as i32,
+                y + self.top + (i / self.width) as i32,
+                self.depth.expand(v),
+            );
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.data.len()
+    }
+}
+
+struct CacheEntry {
+    key: GlyphKey,
+    /// Font size of the style that drew the glyph, selects its partition.
+    font_size: u32,
+    coverage: Coverage,
+    last_use: u32,
+    uses: u32,
+    pinned: bool,
+}
+
+struct CacheStore {
+    /// Sorted by key.
+    entries: Vec<CacheEntry>,
+    capacity: usize,
+    depth: CoverageDepth,
+    allocator: Alloc,
+    policy: EvictionPolicy,
+    /// `(font, glyph id)` of pinned glyphs, at any size.
+    pinned: Vec<(usize, u16)>,
+    /// Font sizes with their own partition.
+    budgets: Vec<SizeBudget>,
+    used: usize,
+    tick: u32,
+}
+
+/// Partition of the cache reserved to a font size.
+struct SizeBudget {
+    font_size: u32,
+    budget: usize,
+    used: usize,
+}
+
+impl CacheStore {
+    fn get(&mut self, key: &GlyphKey) -> Option<&Coverage> {
+        self.tick = self.tick.wrapping_add(1);
+        let tick = self.tick;
+        let i = self.entries.binary_search_by(|e| e.key.cmp(key)).ok()?;
+        let e = &mut self.entries[i];
+        e.last_use = tick;
+        e.uses = e.uses.saturating_add(1);
+        Some(&e.coverage)
+    }
+
+    /// Partition of a glyph drawn at `font_size`: the font size if it has a budget, `None` for
+    /// the shared partition.
+    fn partition(&self, font_size: u32) -> Option<u32> {
+        self.budgets
+            .iter()
+            .any(|b| b.font_size == font_size)
+            .then_some(font_size)
+    }
+
+    /// Capacity and bytes used of a partition.
+    fn partition_usage(&self, partition: Option<u32>) -> (usize, usize) {
+        let (capacity, used) = match partition {
+            Some(font_size) => self
+                .budgets
+                .iter()
+                .find(|b| b.font_size == font_size)
+                .map_or((0, 0), |b| (b.budget, b.used)),
+            None => (
+                self.capacity
+                    .saturating_sub(self.budgets.iter().map(|b| b.budget).sum()),
+                self.used - self.budgets.iter().map(|b| b.used).sum::<usize>(),
+            ),
+        };
+        (capacity.min(self.capacity), used)
+    }
+
+    /// Count `size` more bytes in the partition of `font_size`, or fewer if `added` is false.
+    fn account(&mut self, font_size: u32, size: usize, added: bool) {
+        let update = |used: &mut usize| match added {
+            true => *used += size,
+            false => *used -= size,
+        };
+        update(&mut self.used);
+        if let Some(b) = self.budgets.iter_mut().find(|b| b.font_size == font_size) {
+            update(&mut b.used);
+        }
+    }
+
+    /// Count again the bytes used by each partition, after entries were removed in bulk or
+    /// budgets changed.
+    fn recount(&mut self) {
+        self.used = 0;
+        for b in self.budgets.iter_mut() {
+            b.used = 0;
+        }
+        for i in 0..self.entries.len() {
+            let (font_size, size) = (self.entries[i].font_size, self.entries[i].coverage.size());
+            self.account(font_size, size, true);
+        }
+    }
+
+    /// Index of the next entry of a partition to evict to make room for `needed` more bytes.
+    fn victim(&self, needed: usize, partition: Option<u32>) -> Option<usize> {
+        let tick = self.tick;
+        let age = |e: &CacheEntry| tick.wrapping_sub(e.last_use);
+        let mut candidates = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.pinned && self.partition(e.font_size) == partition);
+        let lru = |candidates: &mut dyn Iterator<Item = (usize, &CacheEntry)>| {
+            candidates.max_by_key(|(_, e)| age(e)).map(|(i, _)| i)
+        };
+        match self.policy {
+            EvictionPolicy::LeastRecentlyUsed => lru(&mut candidates),
+            EvictionPolicy::LeastFrequentlyUsed => candidates
+                .min_by_key(|(_, e)| (e.uses, u32::MAX - age(e)))
+                .map(|(i, _)| i),
+            EvictionPolicy::SizeClass => {
+                let mut large_enough = candidates
+                    .clone()
+                    .filter(|(_, e)| e.coverage.size() >= needed);
+                lru(&mut large_enough).or_else(|| lru(&mut candidates))
+            }
+        }
+    }
+
+    fn insert(&mut self, key: GlyphKey, font_size: u32, coverage: Coverage) {
+        let size = coverage.size();
+        let partition = self.partition(font_size);
+        let (capacity, mut used) = self.partition_usage(partition);
+        // the glyph may have been rasterized and cached by another user of the cache meanwhile
+        if size > capacity || self.entries.binary_search_by(|e| e.key.cmp(&key)).is_ok() {
+            return;
+        }
+        while used + size > capacity {
+            match self.victim(used + size - capacity, partition) {
+                Some(i) => {
+                    let entry = self.entries.remove(i);
+                    trace!(
+                        "glyph cache: evicted glyph {} ({} bytes)",
+                        entry.key.id,
+                        entry.coverage.size()
+                    );
+                    self.account(entry.font_size, entry.coverage.size(), false);
+                    used -= entry.coverage.size();
+                }
+                None => {
+                    debug!(
+                        "glyph cache: only pinned glyphs left, glyph {} not cached",
+                        key.id
+                    );
+                    return;
+                }
+            }
+        }
+        self.account(font_size, size, true);
+        let pinned = self.pinned.contains(&(key.font, key.id));
+        let i = self
+            .entries
+            .binary_search_by(|e| e.key.cmp(&key))
+            .unwrap_or_else(|i| i);
+        self.entries.insert(
+            i,
+            CacheEntry {
+                key,
+                font_size,
+                coverage,
+                last_use: self.tick,
+                uses: 1,
+                pinned,
+            },
+        );
+    }
+
+    fn set_pinned(&mut self, font: usize, id: u16, pinned: bool) {
+        if pinned && !self.pinned.contains(&(font, id)) {
+            self.pinned.push((font, id));
+        }
+        for entry in self.entries.iter_mut() {
+            if entry.key.font == font && entry.key.id == id {
+                entry.pinned = pinned;
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recount();
+    }
+
+    fn remove_font(&mut self, font: usize) {
+        self.entries.retain(|e| e.key.font != font);
+        self.pinned.retain(|(f, _)| *f != font);
+        self.recount();
+    }
+}
+
+#[cfg(feature = "critical-section")]
+type CacheCell<T> = critical_section::Mutex<core::cell::RefCell<T>>;
+
+#[cfg(all(not(feature = "critical-section"), feature = "std"))]
+type CacheCell<T> = std::sync::Mutex<T>;
+
+#[cfg(all(not(feature = "critical-section"), not(feature = "std")))]
+type CacheCell<T> = RefCell<T>;
+
+#[cfg(feature = "critical-section")]
+const fn new_cell<T>(value: T) -> CacheCell<T> {
+    critical_section::Mutex::new(core::cell::RefCell::new(value))
+}
+
+#[cfg(not(feature = "critical-section"))]
+const fn new_cell<T>(value: T) -> CacheCell<T> {
+    CacheCell::new(value)
+}
+
+/// Cache of rasterized glyphs, shared by any number of styles.
+///
+/// The capacity is the number of coverage bytes kept, one byte per pixel of each glyph bounding
+/// box, or less with a lower [`CoverageDepth`]. When the cache is full, the least recently used
+/// glyphs are evicted.
+///
+/// Cached glyphs are rasterized at a quarter pixel precision, which may differ very slightly from
+/// uncached rendering.
+///
+/// ```
+/// # use embedded_ttf::{FontTextStyleBuilder, GlyphCache, SharedGlyphCache};
+/// # use embedded_graphics::pixelcolor::Rgb565;
+/// # use rusttype::Font;
+/// let cache = SharedGlyphCache::new(GlyphCache::new(8 * 1024));
+/// let style = FontTextStyleBuilder::<Rgb565>::new(
+///     Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap())
+///     .glyph_cache(cache.clone())
+///     .build();
+/// ```
+pub struct GlyphCache {
+    store: CacheCell<CacheStore>,
+}
+
+impl GlyphCache {
+    /// Create an empty cache holding at most `capacity` bytes of glyph coverage.
+    pub const fn new(capacity: usize) -> Self {
+        Self::with_depth(capacity, CoverageDepth::Bits8)
+    }
+
+    /// Create an empty cache holding at most `capacity` bytes of glyph coverage stored at the
+    /// given depth.
+    pub const fn with_depth(capacity: usize, depth: CoverageDepth) -> Self {
+        Self::with_allocator(capacity, depth, allocator::GLOBAL)
+    }
+
+    /// Create an empty cache holding at most `capacity` bytes of glyph coverage stored at the
+    /// given depth, the coverage is allocated in `allocator`.
+    #[cfg(feature = "allocator-api2")]
+    pub const fn new_in(
+        capacity: usize,
+        depth: CoverageDepth,
+        allocator: allocator::AllocatorRef,
+    ) -> Self {
+        Self::with_allocator(capacity, depth, Alloc(allocator))
+    }
+
+    const fn with_allocator(capacity: usize, depth: CoverageDepth, allocator: Alloc) -> Self {
+        let store = CacheStore {
+            entries: Vec::new(),
+            capacity,
+            depth,
+            allocator,
+            policy: EvictionPolicy::LeastRecentlyUsed,
+            pinned: Vec::new(),
+            budgets: Vec::new(),
+            used: 0,
+            tick: 0,
+        };
+        GlyphCache {
+            store: new_cell(store),
+        }
+    }
+
+    /// Number of coverage bytes currently cached.
+    pub fn used(&self) -> usize {
+        self.with_store(|store| store.used)
+    }
+
+    /// Maximum number of coverage bytes cached.
+    pub fn capacity(&self) -> usize {
+        self.with_store(|store| store.capacity)
+    }
+
+    /// Depth of the cached coverage.
+    pub fn depth(&self) -> CoverageDepth {
+        self.with_store(|store| store.depth)
+    }
+
+    /// Select how glyphs are evicted when the cache is full.
+    pub fn set_eviction_policy(&self, policy: EvictionPolicy) {
+        self.with_store(|store| store.policy = policy)
+    }
+
+    /// Current eviction policy.
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.with_store(|store| store.policy)
+    }
+
+    /// Never evict the glyphs of `font` for the characters of `text`, at any size.
+    ///
+    /// Pinned glyphs are still rasterized the first time they are drawn, they count in the cache
+    /// capacity. Pin a small set, like the digits of a clock, or new glyphs won't be cached.
+    pub fn pin(&self, font: &Font<'_>, text: &str) {
+        let key = font_key(font);
+        self.with_store(|store| {
+            for c in text.chars() {
+                store.set_pinned(key, font.glyph(c).id().0, true);
+            }
+        })
+    }
+
+    /// Give glyphs drawn by styles of `font_size` pixels their own partition of `budget` bytes.
+    ///
+    /// Glyphs of a partitioned size only evict glyphs of the same size, and sizes without a
+    /// budget share the capacity left by the partitions. This keeps a rarely used large style
+    /// from evicting the glyphs of the body text.
+    ///
+    /// Glyphs belong to the font size of the style that draws them, whatever their rasterized
+    /// scale: superscripts, subscripts and fallback glyphs of a 40 pixel style use the budget of
+    /// 40 pixels.
+    pub fn set_size_budget(&self, font_size: f32, budget: usize) {
+        let font_size = font_size.to_bits();
+        self.with_store(|store| {
+            match store.budgets.iter_mut().find(|b| b.font_size == font_size) {
+                Some(b) => b.budget = budget,
+                None => store.budgets.push(SizeBudget {
+                    font_size,
+                    budget,
+                    used: 0,
+                }),
+            }
+            store.recount();
+        })
+    }
+
+    /// Return glyphs of `font_size` pixels to the shared partition.
+    pub fn remove_size_budget(&self, font_size: f32) {
+        let font_size = font_size.to_bits();
+        self.with_store(|store| {
+            store.budgets.retain(|b| b.font_size != font_size);
+            store.recount();
+        })
+    }
+
+    /// Remove the glyphs of `font` from the cache, and unpin them.
+    ///
+    /// Fonts are identified by the address of their data, call this before dropping a font that
+    /// may be replaced by another one, so that its glyphs are never drawn with the new font.
+    pub fn forget_font(&self, font: &Font<'_>) {
+        let key = font_key(font);
+        self.with_store(|store| store.remove_font(key))
+    }
+
+    /// Allow all glyphs to be evicted again.
+    pub fn unpin_all(&self) {
+        self.with_store(|store| {
+            store.pinned.clear();
+            for entry in store.entries.iter_mut() {
+                entry.pinned = false;
+            }
+        })
+    }
+
+    /// Number of glyphs currently cached.
+    pub fn len(&self) -> usize {
+        self.with_store(|store| store.entries.len())
+    }
+
+    /// Returns true if no glyph is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all cached glyphs.
+    pub fn clear(&self) {
+        self.with_store(CacheStore::clear)
+    }
+
+    /// Call `f(x, y, coverage)` for each pixel of the glyph, rasterizing it only if it is not
+    /// cached yet. Coordinates are relative to the layout origin, `font_size` is the size of the
+    /// style drawing the glyph.
+    ///
+    /// Returns true if the glyph was found in the cache.
+    pub(crate) fn with_coverage(
+        &self,
+        glyph: &PositionedGlyph<'_>,
+        font_size: f32,
+        mut f: impl FnMut(i32, i32, u8),
+    ) -> bool {
+        let position = glyph.position();
+        let (x, fract_x) = split_position(position.x);
+        let (y, fract_y) = split_position(position.y);
+        let scale = glyph.scale();
+        let key = GlyphKey {
+            font: font_key(glyph.font()),
+            id: glyph.id().0,
+            scale_x: scale.x.to_bits(),
+            scale_y: scale.y.to_bits(),
+            subpixel_x: fract_x,
+            subpixel_y: fract_y,
+        };
+
+        // `f` runs outside of the store lock, it blends pixels and may allocate
+        let cached = self.with_store(|store| match store.get(&key) {
+            Some(coverage) => Ok(coverage.clone()),
+            None => Err((store.depth, store.allocator)),
+        });
+        let (depth, allocator) = match cached {
+            Ok(coverage) => {
+                coverage.for_each(x, y, &mut f);
+                return true;
+            }
+            Err(settings) => settings,
+        };
+
+        // rasterize outside of the store lock, this can be long
+        let glyph = glyph.unpositioned().clone().positioned(point(
+            fract_x as f32 / SUBPIXEL_STEPS,
+            fract_y as f32 / SUBPIXEL_STEPS,
+        ));
+        if let Some(coverage) = Coverage::rasterize(&glyph, depth, allocator) {
+            coverage.for_each(x, y, &mut f);
+            self.with_store(|store| store.insert(key, font_size.to_bits(), coverage));
+        }
+        false
+    }
+
+    #[cfg(feature = "critical-section")]
+    fn with_store<R>(&self, f: impl FnOnce(&mut CacheStore) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.store.borrow_ref_mut(cs)))
+    }
+
+    #[cfg(all(not(feature = "critical-section"), feature = "std"))]
+    fn with_store<R>(&self, f: impl FnOnce(&mut CacheStore) -> R) -> R {
+        f(&mut self.store.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    #[cfg(all(not(feature = "critical-section"), not(feature = "std")))]
+    fn with_store<R>(&self, f: impl FnOnce(&mut CacheStore) -> R) -> R {
+        f(&mut self.store.borrow_mut())
+    }
+}
+
+impl fmt::Debug for GlyphCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with_store(|store| {
+            f.debug_struct("GlyphCache")
+                .field("glyphs", &store.entries.len())
+                .field("used", &store.used)
+                .field("capacity", &store.capacity)
+                .finish()
+        })
+    }
+}
+
+/// Split a position into its integer part and its quantized fractional part.
+fn split_position(v: f32) -> (i32, u8) {
+    let steps = (v * SUBPIXEL_STEPS).round() as i32;
+    let steps_per_pixel = SUBPIXEL_STEPS as i32;
+    (
+        steps.div_euclid(steps_per_pixel),
+        steps.rem_euclid(steps_per_pixel) as u8,
+    )
+}
+
+/// Fonts are reference counted, the shared data address identifies them.
+fn font_key(font: &Font<'_>) -> usize {
+    match font {
+        Font::Ref(face) => &**face as *const _ as *const u8 as usize,
+        Font::Owned(face) => &**face as *const _ as *const u8 as usize,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_depth_coverage_is_packed() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let glyph = font
+            .glyph('g')
+            .scaled(rusttype::Scale::uniform(20.0))
+            .positioned(point(0.0, 0.0));
+        let full = Coverage::rasterize(&glyph, CoverageDepth::Bits8, allocator::GLOBAL).unwrap();
+        let packed = Coverage::rasterize(&glyph, CoverageDepth::Bits2, allocator::GLOBAL).unwrap();
+        assert_eq!(packed.size(), full.size().div_ceil(4));
+
+        let mut levels = Vec::new();
+        full.for_each(0, 0, |x, y, v| levels.push((x, y, v)));
+        let mut i = 0;
+        packed.for_each(0, 0, |x, y, v| {
+            let (fx, fy, fv) = levels[i];
+            assert_eq!((x, y), (fx, fy));
+            assert!(LEVELS_2.contains(&v));
+            assert!((v as i32 - fv as i32).abs() <= 43);
+            i += 1;
+        });
+        assert_eq!(i, levels.len());
+    }
+
+    fn rasterized(font: &Font<'static>, c: char) -> (GlyphKey, Coverage) {
+        rasterized_at(font, c, 20.0)
+    }
+
+    fn rasterized_at(font: &Font<'static>, c: char, size: f32) -> (GlyphKey, Coverage) {
+        let scale = rusttype::Scale::uniform(size);
+        let glyph = font.glyph(c).scaled(scale).positioned(point(0.0, 0.0));
+        let key = GlyphKey {
+            font: font_key(font),
+            id: glyph.id().0,
+            scale_x: scale.x.to_bits(),
+            scale_y: scale.y.to_bits(),
+            subpixel_x: 0,
+            subpixel_y: 0,
+        };
+        (
+            key,
+            Coverage::rasterize(&glyph, CoverageDepth::Bits8, allocator::GLOBAL).unwrap(),
+        )
+    }
+
+    #[test]
+    fn eviction_policies() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        // at 20px, "1" is 55 bytes, "i" 24, "-" 12 and "r" 45
+        let glyphs: Vec<_> = "1i-r".chars().map(|c| rasterized(&font, c)).collect();
+        let fill = |policy, pin: &str, touched: &[usize], new: usize| {
+            let cache = GlyphCache::new(glyphs[0].1.size() + glyphs[1].1.size());
+            cache.set_eviction_policy(policy);
+            cache.pin(&font, pin);
+            cache.with_store(|store| {
+                store.insert(glyphs[0].0, glyphs[0].0.scale_y, glyphs[0].1.clone());
+                store.insert(glyphs[1].0, glyphs[1].0.scale_y, glyphs[1].1.clone());
+                for &i in touched {
+                    store.get(&glyphs[i].0);
+                }
+                store.insert(glyphs[new].0, glyphs[new].0.scale_y, glyphs[new].1.clone());
+                let kept = |i: usize| store.entries.iter().any(|e| e.key == glyphs[i].0);
+                (kept(0), kept(1))
+            })
+        };
+
+        // "1" is used more, "i" more recently
+        let touched = [0, 0, 1];
+        assert_eq!(
+            fill(EvictionPolicy::LeastRecentlyUsed, "", &touched, 2),
+            (false, true)
+        );
+        assert_eq!(
+            fill(EvictionPolicy::LeastFrequentlyUsed, "", &touched, 2),
+            (true, false)
+        );
+        assert_eq!(
+            fill(EvictionPolicy::LeastRecentlyUsed, "1", &touched, 2),
+            (true, false)
+        );
+        // "i" is the oldest but too small to make room for "r"
+        assert_eq!(
+            fill(EvictionPolicy::LeastRecentlyUsed, "", &[0], 3),
+            (false, false)
+        );
+        assert_eq!(fill(EvictionPolicy::SizeClass, "", &[0], 3), (false, true));
+    }
+
+    #[test]
+    fn budgeted_sizes_do_not_evict_other_sizes() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let body = rasterized_at(&font, 'a', 12.0);
+        let headlines: Vec<_> = "ABC"
+            .chars()
+            .map(|c| rasterized_at(&font, c, 40.0))
+            .collect();
+        let cache = GlyphCache::new(body.1.size() + 2 * headlines[0].1.size() + 100);
+        cache.set_size_budget(40.0, 2 * headlines[0].1.size());
+        cache.with_store(|store| {
+            store.insert(body.0, body.0.scale_y, body.1.clone());
+            for (key, coverage) in headlines.iter() {
+                store.insert(*key, key.scale_y, coverage.clone());
+            }
+            assert!(store.entries.iter().any(|e| e.key == body.0));
+            assert!(store.used <= store.capacity);
+        });
+    }
+
+    #[test]
+    fn budgets_follow_the_style_font_size() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let body = rasterized_at(&font, 'a', 12.0);
+        // a superscript of a 40 pixel style is rasterized smaller
+        let superscript = rasterized_at(&font, 'A', 26.0);
+        let headline = rasterized_at(&font, 'A', 40.0);
+        let headline_size = 40.0f32.to_bits();
+        let cache = GlyphCache::new(body.1.size() + headline.1.size());
+        cache.set_size_budget(40.0, headline.1.size());
+        cache.with_store(|store| {
+            store.insert(body.0, body.0.scale_y, body.1.clone());
+            store.insert(superscript.0, headline_size, superscript.1.clone());
+            assert_eq!(store.partition_usage(None).1, body.1.size());
+            assert_eq!(
+                store.partition_usage(Some(headline_size)).1,
+                superscript.1.size()
+            );
+
+            store.insert(headline.0, headline_size, headline.1.clone());
+            assert!(store.entries.iter().any(|e| e.key == body.0));
+            assert!(!store.entries.iter().any(|e| e.key == superscript.0));
+            assert_eq!(store.used, body.1.size() + headline.1.size());
+        });
+
+        cache.remove_size_budget(40.0);
+        cache.with_store(|store| {
+            assert_eq!(store.partition_usage(None).1, store.used);
+        });
+    }
+
+    #[test]
+    fn entries_are_found_by_key() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let glyphs: Vec<_> = "zebra".chars().map(|c| rasterized(&font, c)).collect();
+        let cache = GlyphCache::new(10_000);
+        cache.with_store(|store| {
+            for (key, coverage) in glyphs.iter() {
+                store.insert(*key, key.scale_y, coverage.clone());
+            }
+            assert!(store.entries.windows(2).all(|w| w[0].key < w[1].key));
+            let used = store.used;
+            store.insert(glyphs[0].0, glyphs[0].0.scale_y, glyphs[0].1.clone());
+            assert_eq!(store.used, used);
+            for (key, coverage) in glyphs.iter() {
+                assert_eq!(store.get(key).map(Coverage::size), Some(coverage.size()));
+            }
+        });
+        assert_eq!(cache.len(), glyphs.len());
+    }
+
+    #[test]
+    fn coverage_is_read_outside_of_the_lock() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let glyph = font
+            .glyph('a')
+            .scaled(rusttype::Scale::uniform(20.0))
+            .positioned(point(0.0, 0.0));
+        let cache = GlyphCache::new(1000);
+        assert!(!cache.with_coverage(&glyph, 20.0, |_, _, _| assert_eq!(cache.len(), 0)));
+        assert!(cache.with_coverage(&glyph, 20.0, |_, _, _| assert_eq!(cache.len(), 1)));
+    }
+
+    #[test]
+    fn forgotten_fonts_are_removed() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let other = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let (key, coverage) = rasterized(&font, 'a');
+        let (other_key, other_coverage) = rasterized(&other, 'a');
+        let cache = GlyphCache::new(1000);
+        cache.pin(&font, "a");
+        cache.with_store(|store| {
+            store.insert(key, key.scale_y, coverage);
+            store.insert(other_key, other_key.scale_y, other_coverage.clone());
+        });
+
+        cache.forget_font(&font);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.used(), other_coverage.size());
+        cache.with_store(|store| assert!(store.pinned.is_empty()));
+    }
+}
@@ -0,0 +1,132 @@
+//! Character clusters.
+//!
+//! Emoji sequences (zero width joiner sequences, variation selectors, skin tone modifiers, flags,
+//! keycaps, tags) and combining marks are kept together during layout, so that a cluster is
+//! rendered with a single font and joiners or selectors never show up as missing glyphs.
+//!
+//! With the `unicode-segmentation` feature, clusters are the extended grapheme clusters of
+//! UAX #29, which also covers Hangul syllables and prepended marks. Clusters are never split by
+//! truncation.
+
+#[cfg(not(feature = "unicode-segmentation"))]
+use crate::script::Script;
+
+/// Zero width joiner.
+#[cfg(not(feature = "unicode-segmentation"))]
+const ZWJ: char = '\u{200D}';
+
+/// Soft hyphen, only visible at the end of a wrapped line.
+pub(crate) const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Characters that never have a visible glyph of their own.
+pub(crate) fn is_invisible(c: char) -> bool {
+    matches!(c,
+        SOFT_HYPHEN
+        | '\u{200B}'..='\u{200D}'
+        | '\u{2060}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FEFF}'
+        | '\u{E0001}'
+        | '\u{E0020}'..='\u{E007F}'
+        | '\u{E0100}'..='\u{E01EF}')
+}
+
+/// Characters extending the preceding cluster.
+#[cfg(not(feature = "unicode-segmentation"))]
+fn is_extending(c: char) -> bool {
+    matches!(c,
+        '\u{FE00}'..='\u{FE0F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{20E3}'
+        | '\u{E0020}'..='\u{E007F}'
+        | '\u{E0100}'..='\u{E01EF}')
+        || Script::of(c) == Script::Inherited
+}
+
+#[cfg(not(feature = "unicode-segmentation"))]
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+/// Clusters of `text`, with the byte index where each one starts.
+///
+/// Clusters are the units kept together by layout, truncation and hit testing. Cut text at their
+/// boundaries, for instance to reveal it progressively, so that emoji sequences and combining
+/// marks are never split.
+pub fn clusters(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut index = 0;
+    Clusters::new(text).map(move |cluster| {
+        let start = index;
+        index += cluster.len();
+        (start, cluster)
+    })
+}
+
+/// Iterator over the clusters of a string.
+pub(crate) struct Clusters<'a> {
+    text: &'a str,
+}
+
+impl<'a> Clusters<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Clusters { text }
+    }
+}
+
+impl<'a> Iterator for Clusters<'a> {
+    type Item = &'a str;
+
+    #[cfg(feature = "unicode-segmentation")]
+    fn next(&mut self) -> Option<&'a str> {
+        use unicode_segmentation::UnicodeSegmentation;
+        let cluster = self.text.graphemes(true).next()?;
+        self.text = &self.text[cluster.len()..];
+        Some(cluster)
+    }
+
+    #[cfg(not(feature = "unicode-segmentation"))]
+    fn next(&mut self) -> Option<&'a str> {
+        let mut chars = self.text.char_indices().peekable();
+        let (_, first) = chars.next()?;
+        let mut end = self.text.len();
+        let mut joined = false;
+        let mut flag = is_regional_indicator(first);
+        while let Some(&(i, c)) = chars.peek() {
+            if joined || is_extending(c) || c == ZWJ {
+                joined = c == ZWJ;
+            } else if flag && is_regional_indicator(c) {
+                // flags are pairs of regional indicators
+            } else {
+                end = i;
+                break;
+            }
+            flag = false;
+            chars.next();
+        }
+        let (cluster, rest) = self.text.split_at(end);
+        self.text = rest;
+        Some(cluster)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::vec::Vec;
+
+    #[test]
+    fn emoji_sequences_are_single_clusters() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let thumbs = "👍🏽";
+        let heart = "❤\u{FE0F}";
+        let flags = "🇫🇷🇩🇪";
+        let text = [family, "a", thumbs, heart, "e\u{301}", flags].concat();
+        let split: Vec<_> = Clusters::new(&text).collect();
+        assert_eq!(split, [family, "a", thumbs, heart, "e\u{301}", "🇫🇷", "🇩🇪"]);
+
+        // revealing two clusters keeps the combining mark
+        let text = "ae\u{301}b";
+        let end = clusters(text).nth(2).map_or(text.len(), |(start, _)| start);
+        assert_eq!(&text[..end], "ae\u{301}");
+    }
+}
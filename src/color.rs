@@ -0,0 +1,554 @@
+//! Color handling for targets with a limited set of colors.
+//!
+//! Antialiasing blends the text color with a background color, see [`ColorBlend`], optionally
+//! in linear light with a [`Gamma`]. This produces colors a palettized display cannot show. With
+//! a [`Palette`], each blended color is replaced by the closest palette entry, optionally with
+//! ordered dithering to simulate the missing shades.
+
+use crate::stdlib::{fmt, vec::Vec};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::Float;
+
+use embedded_graphics::{
+    pixelcolor::{
+        Bgr555, Bgr565, Bgr666, Bgr888, BinaryColor, Gray2, Gray4, Gray8, Rgb555, Rgb565, Rgb666,
+        Rgb888,
+    },
+    prelude::*,
+};
+
+/// 4x4 Bayer threshold matrix.
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Threshold of the pixel at `point` in the Bayer matrix, from 0 to 15.
+fn bayer(point: Point) -> u8 {
+    BAYER[point.y.rem_euclid(4) as usize][point.x.rem_euclid(4) as usize]
+}
+
+/// Whether the pixel at `point` is drawn with the given coverage in ordered dithering.
+///
+/// Over each 4x4 block, a uniform coverage draws a proportional number of pixels, spread as
+/// evenly as possible.
+pub(crate) fn dither(coverage: u8, point: Point) -> bool {
+    coverage as u16 > (2 * bayer(point) as u16 + 1) * 255 / 32
+}
+
+/// Coverage rounded to the nearest of `levels` evenly spaced levels from 0 to 255.
+pub(crate) fn quantize(coverage: u8, levels: u8) -> u8 {
+    let steps = levels.max(2) as u32 - 1;
+    let level = (coverage as u32 * steps + 127) / 255;
+    ((level * 255 + steps / 2) / steps) as u8
+}
+
+/// Colors that can be blended, for antialiasing.
+///
+/// Implemented for the embedded-graphics colors and for colors implementing [`BlendViaRgb888`].
+/// Implement it for other custom colors to draw text on targets using them.
+///
+/// Styles used to accept any `PixelColor + Into<Rgb888> + From<Rgb888>` color. Such colors now
+/// opt in with an empty `impl BlendViaRgb888 for MyColor {}`.
+pub trait ColorBlend: PixelColor {
+    /// This color drawn over `background` with `coverage`, from 0 for the background alone to
+    /// 255 for this color alone.
+    fn blend(self, background: Self, coverage: u8) -> Self;
+
+    /// Like `blend`, mixing the colors in linear light. Colors without channels to convert
+    /// blend like `blend`.
+    fn blend_gamma(self, background: Self, coverage: u8, gamma: &Gamma) -> Self {
+        let _ = gamma;
+        self.blend(background, coverage)
+    }
+
+    /// This color drawn over `background` with the coverage of the red, green and blue stripes
+    /// of the pixel, for subpixel antialiasing, mixed in linear light with a gamma. Colors
+    /// without these channels blend with the mean coverage.
+    fn blend_subpixels(self, background: Self, coverage: [u8; 3], gamma: Option<&Gamma>) -> Self {
+        match gamma {
+            Some(gamma) => self.blend_gamma(background, mean(coverage), gamma),
+            None => self.blend(background, mean(coverage)),
+        }
+    }
+}
+
+/// Custom colors blended by converting them to [`Rgb888`] and back.
+///
+/// The empty implementation is enough to blend them, in linear light and per subpixel too:
+///
+/// ```
+/// # use embedded_graphics::pixelcolor::{PixelColor, Rgb888};
+/// use embedded_ttf::BlendViaRgb888;
+///
+/// #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+/// struct MyColor(Rgb888);
+///
+/// impl PixelColor for MyColor {
+///     type Raw = ();
+/// }
+/// # impl From<Rgb888> for MyColor { fn from(c: Rgb888) -> Self { MyColor(c) } }
+/// # impl From<MyColor> for Rgb888 { fn from(c: MyColor) -> Self { c.0 } }
+///
+/// impl BlendViaRgb888 for MyColor {}
+/// ```
+pub trait BlendViaRgb888: PixelColor + Into<Rgb888> + From<Rgb888> {}
+
+impl<C: BlendViaRgb888> ColorBlend for C {
+    fn blend(self, background: Self, coverage: u8) -> Self {
+        self.into().blend(background.into(), coverage).into()
+    }
+
+    fn blend_gamma(self, background: Self, coverage: u8, gamma: &Gamma) -> Self {
+        self.into()
+            .blend_gamma(background.into(), coverage, gamma)
+            .into()
+    }
+
+    fn blend_subpixels(self, background: Self, coverage: [u8; 3], gamma: Option<&Gamma>) -> Self {
+        self.into()
+            .blend_subpixels(background.into(), coverage, gamma)
+            .into()
+    }
+}
+
+/// Colors with an alpha channel.
+///
+/// With [`AntiAliasing::Alpha`](crate::AntiAliasing::Alpha), the coverage of the glyphs goes
+/// into the alpha channel of the text color instead of being blended with a background, and the
+/// target composes the text itself. None of the embedded-graphics colors have alpha, implement
+/// this for custom ARGB colors.
+pub trait AlphaPixelColor: ColorBlend {
+    /// This color with its alpha multiplied by `coverage`, from 0 for transparent to 255 for
+    /// unchanged.
+    fn scale_alpha(self, coverage: u8) -> Self;
+}
+
+/// Mean coverage of the stripes of a pixel.
+pub(crate) fn mean(coverage: [u8; 3]) -> u8 {
+    (coverage.iter().map(|c| *c as u16).sum::<u16>() / 3) as u8
+}
+
+/// Gamma of the target, to blend colors in linear light.
+///
+/// Channels are stored with a gamma encoding, mixing them directly makes the edges of dark text
+/// on a light background too light, and small text looks thin. With a gamma, channels are
+/// converted to linear light, mixed, and converted back. 2.2 approximates most displays.
+#[derive(Clone)]
+pub struct Gamma {
+    gamma: f32,
+    /// Linear light of each channel value, from 0 to 65535.
+    linear: [u16; 256],
+}
+
+impl Gamma {
+    /// Gamma `gamma`, values below 0.1 are raised to 0.1.
+    pub fn new(gamma: f32) -> Self {
+        let gamma = gamma.max(0.1);
+        let mut linear = [0; 256];
+        for (value, linear) in linear.iter_mut().enumerate() {
+            *linear = ((value as f32 / 255.0).powf(gamma) * 65535.0).round() as u16;
+        }
+        Gamma { gamma, linear }
+    }
+
+    /// The gamma.
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Channel of a color drawn over `background` with `coverage`, mixed in linear light.
+    pub(crate) fn mix(&self, channel: u8, background: u8, coverage: u8) -> u8 {
+        let a = coverage as u32;
+        let light = (a * self.linear[channel as usize] as u32
+            + (255 - a) * self.linear[background as usize] as u32)
+            / 255;
+        // closest encoded value, the mix is between the linear light of two values
+        let above = self.linear.partition_point(|&l| (l as u32) < light);
+        let distance = |value: usize| (self.linear[value] as u32).abs_diff(light);
+        match above {
+            0 => 0,
+            _ if distance(above - 1) < distance(above) => above as u8 - 1,
+            _ => above as u8,
+        }
+    }
+}
+
+impl fmt::Debug for Gamma {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Gamma").field(&self.gamma).finish()
+    }
+}
+
+/// Channel of a color drawn over `background` with `coverage`.
+fn mix(channel: u8, background: u8, coverage: u8) -> u8 {
+    let a = coverage as u16;
+    ((a * channel as u16 + (255 - a) * background as u16) / 255) as u8
+}
+
+impl ColorBlend for Rgb888 {
+    fn blend(self, background: Self, coverage: u8) -> Self {
+        Rgb888::new(
+            mix(self.r(), background.r(), coverage),
+            mix(self.g(), background.g(), coverage),
+            mix(self.b(), background.b(), coverage),
+        )
+    }
+
+    fn blend_gamma(self, background: Self, coverage: u8, gamma: &Gamma) -> Self {
+        Rgb888::new(
+            gamma.mix(self.r(), background.r(), coverage),
+            gamma.mix(self.g(), background.g(), coverage),
+            gamma.mix(self.b(), background.b(), coverage),
+        )
+    }
+
+    fn blend_subpixels(self, background: Self, coverage: [u8; 3], gamma: Option<&Gamma>) -> Self {
+        let [r, g, b] = coverage;
+        let blend = |channel, background, coverage| match gamma {
+            Some(gamma) => gamma.mix(channel, background, coverage),
+            None => mix(channel, background, coverage),
+        };
+        Rgb888::new(
+            blend(self.r(), background.r(), r),
+            blend(self.g(), background.g(), g),
+            blend(self.b(), background.b(), b),
+        )
+    }
+}
+
+impl ColorBlend for Gray8 {
+    fn blend(self, background: Self, coverage: u8) -> Self {
+        Gray8::new(mix(self.luma(), background.luma(), coverage))
+    }
+
+    fn blend_gamma(self, background: Self, coverage: u8, gamma: &Gamma) -> Self {
+        Gray8::new(gamma.mix(self.luma(), background.luma(), coverage))
+    }
+}
+
+/// Colors blended at the precision of a wider color.
+macro_rules! impl_color_blend_via {
+    ($wide:ident => $($type:ident),+) => {
+        $(impl ColorBlend for $type {
+            fn blend(self, background: Self, coverage: u8) -> Self {
+                $wide::from(self)
+                    .blend($wide::from(background), coverage)
+                    .into()
+            }
+
+            fn blend_gamma(self, background: Self, coverage: u8, gamma: &Gamma) -> Self {
+                $wide::from(self)
+                    .blend_gamma($wide::from(background), coverage, gamma)
+                    .into()
+            }
+
+            fn blend_subpixels(
+                self,
+                background: Self,
+                coverage: [u8; 3],
+                gamma: Option<&Gamma>,
+            ) -> Self {
+                $wide::from(self)
+                    .blend_subpixels($wide::from(background), coverage, gamma)
+                    .into()
+            }
+        })+
+    };
+}
+
+impl_color_blend_via!(Rgb888 => Rgb555, Bgr555, Rgb565, Bgr565, Rgb666, Bgr666, Bgr888);
+impl_color_blend_via!(Gray8 => Gray2, Gray4);
+
+impl ColorBlend for BinaryColor {
+    /// Binary colors can't be mixed, the color covering most of the pixel wins.
+    fn blend(self, background: Self, coverage: u8) -> Self {
+        match coverage > 127 {
+            true => self,
+            false => background,
+        }
+    }
+}
+
+/// Colors available on the target.
+#[derive(Debug, Clone)]
+pub struct Palette<C> {
+    colors: Vec<(C, Rgb888)>,
+    rgb: fn(C) -> Rgb888,
+    dithering: bool,
+}
+
+impl<C: PixelColor + Into<Rgb888>> Palette<C> {
+    /// Create a palette from the colors the target can show.
+    pub fn new(colors: impl IntoIterator<Item = C>) -> Self {
+        Palette {
+            colors: colors.into_iter().map(|c| (c, c.into())).collect(),
+            rgb: Into::into,
+            dithering: false,
+        }
+    }
+
+    /// Enable ordered dithering between palette colors.
+    pub fn dithering(mut self, dithering: bool) -> Self {
+        self.dithering = dithering;
+        self
+    }
+}
+
+impl<C: PixelColor> Palette<C> {
+    /// Palette entry closest to `color` for the pixel at `point`, `None` if the palette is
+    /// empty.
+    pub(crate) fn nearest(&self, color: C, point: Point) -> Option<C> {
+        let color = (self.rgb)(color);
+        let offset = if self.dithering && self.colors.len() > 1 {
+            // spread the threshold over the typical distance between two entries
+            let spread = 255 / (self.colors.len() as i32 - 1);
+            (2 * bayer(point) as i32 + 1 - 16) * spread / 32
+        } else {
+            0
+        };
+        let channel = |c: u8| c as i32 + offset;
+        let (r, g, b) = (channel(color.r()), channel(color.g()), channel(color.b()));
+
+        self.colors
+            .iter()
+            .min_by_key(|(_, rgb)| {
+                let dr = rgb.r() as i32 - r;
+                let dg = rgb.g() as i32 - g;
+                let db = rgb.b() as i32 - b;
+                dr * dr + dg * dg + db * db
+            })
+            .map(|(c, _)| *c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_graphics::pixelcolor::Gray8;
+
+    fn grays() -> Palette<Gray8> {
+        Palette::new([0, 85, 170, 255].map(Gray8::new))
+    }
+
+    #[test]
+    fn nearest_color_is_selected() {
+        let palette = grays();
+        let gray = Gray8::new;
+        assert_eq!(
+            palette.nearest(gray(30), Point::zero()),
+            Some(Gray8::new(0))
+        );
+        assert_eq!(
+            palette.nearest(gray(100), Point::zero()),
+            Some(Gray8::new(85))
+        );
+        assert_eq!(
+            palette.nearest(gray(250), Point::zero()),
+            Some(Gray8::new(255))
+        );
+        assert_eq!(
+            Palette::<Gray8>::new([]).nearest(gray(0), Point::zero()),
+            None
+        );
+    }
+
+    #[test]
+    fn colors_blend_at_their_depth() {
+        let blend = |coverage| Rgb888::new(200, 100, 0).blend(Rgb888::new(0, 100, 200), coverage);
+        assert_eq!(blend(255), Rgb888::new(200, 100, 0));
+        assert_eq!(blend(0), Rgb888::new(0, 100, 200));
+        assert_eq!(blend(128), Rgb888::new(100, 100, 99));
+
+        assert_eq!(Gray8::WHITE.blend(Gray8::BLACK, 51), Gray8::new(51));
+        assert_eq!(Gray4::WHITE.blend(Gray4::BLACK, 136), Gray4::new(8));
+        assert_eq!(
+            Rgb565::WHITE.blend(Rgb565::BLACK, 128),
+            Rgb565::from(Rgb888::new(128, 128, 128))
+        );
+        assert_eq!(
+            BinaryColor::On.blend(BinaryColor::Off, 100),
+            BinaryColor::Off
+        );
+        assert_eq!(
+            BinaryColor::On.blend(BinaryColor::Off, 200),
+            BinaryColor::On
+        );
+    }
+
+    #[test]
+    fn gamma_blends_in_linear_light() {
+        let gamma = Gamma::new(2.2);
+        for coverage in [0, 1, 100, 254, 255] {
+            for (fg, bg) in [(0, 255), (255, 0), (90, 90)] {
+                let linear = (coverage as f32 * (fg as f32 / 255.0).powf(2.2)
+                    + (255 - coverage) as f32 * (bg as f32 / 255.0).powf(2.2))
+                    / 255.0;
+                let expected = (linear.powf(1.0 / 2.2) * 255.0).round() as u8;
+                assert!(gamma.mix(fg, bg, coverage).abs_diff(expected) <= 1);
+            }
+        }
+        // half covered black on white is lighter than the plain average
+        let half = Gray8::BLACK.blend_gamma(Gray8::WHITE, 128, &gamma);
+        assert!(half.luma() > Gray8::BLACK.blend(Gray8::WHITE, 128).luma() + 40);
+        // a gamma of 1 is the plain blending
+        let linear = Gamma::new(1.0);
+        for coverage in (0..=255).step_by(15) {
+            assert!(
+                linear
+                    .mix(30, 220, coverage)
+                    .abs_diff(mix(30, 220, coverage))
+                    <= 1
+            );
+        }
+    }
+
+    #[test]
+    fn coverage_is_dithered() {
+        let drawn = |coverage| {
+            (0..4)
+                .flat_map(|y| (0..4).map(move |x| Point::new(x, y)))
+                .filter(|point| dither(coverage, *point))
+                .count()
+        };
+        assert_eq!(drawn(0), 0);
+        assert_eq!(drawn(64), 4);
+        assert_eq!(drawn(128), 8);
+        assert_eq!(drawn(255), 16);
+        // half covered pixels make a checkerboard
+        assert!(dither(128, Point::new(0, 0)) && dither(128, Point::new(1, 1)));
+        assert!(!dither(128, Point::new(1, 0)) && !dither(128, Point::new(0, 1)));
+    }
+
+    #[test]
+    fn coverage_is_rounded_to_levels() {
+        let quantized = |levels| [0, 40, 43, 128, 200, 255].map(|c| quantize(c, levels));
+        assert_eq!(quantized(4), [0, 0, 85, 170, 170, 255]);
+        assert_eq!(quantized(2), [0, 0, 0, 255, 255, 255]);
+        assert_eq!(quantized(255), [0, 40, 43, 128, 200, 255]);
+    }
+
+    #[test]
+    fn dithering_mixes_neighbour_colors() {
+        let palette = grays().dithering(true);
+        let mid = Gray8::new(128);
+        let mut counts = [0; 2];
+        for y in 0..4 {
+            for x in 0..4 {
+                match palette.nearest(mid, Point::new(x, y)).unwrap().luma() {
+                    85 => counts[0] += 1,
+                    170 => counts[1] += 1,
+                    other => panic!("unexpected level {}", other),
+                }
+            }
+        }
+        assert_eq!(counts, [8, 8]);
+    }
+
+    /// Color of a custom display, only convertible to and from `Rgb888`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct PanelColor(u8, u8, u8);
+
+    impl PixelColor for PanelColor {
+        type Raw = ();
+    }
+
+    impl From<Rgb888> for PanelColor {
+        fn from(color: Rgb888) -> Self {
+            PanelColor(color.r(), color.g(), color.b())
+        }
+    }
+
+    impl From<PanelColor> for Rgb888 {
+        fn from(PanelColor(r, g, b): PanelColor) -> Self {
+            Rgb888::new(r, g, b)
+        }
+    }
+
+    impl BlendViaRgb888 for PanelColor {}
+
+    #[test]
+    fn custom_colors_blend_via_rgb888() {
+        use crate::FontTextStyleBuilder;
+        use embedded_graphics::{
+            mock_display::MockDisplay,
+            text::{renderer::TextRenderer, Baseline},
+        };
+        use rusttype::Font;
+
+        let white = PanelColor(255, 255, 255);
+        let black = PanelColor(0, 0, 0);
+        assert_eq!(white.blend(black, 128), PanelColor(128, 128, 128));
+
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let mut display = MockDisplay::new();
+        FontTextStyleBuilder::new(font.clone())
+            .font_size(16)
+            .text_color(white)
+            .background_color(black)
+            .build()
+            .draw_string("Ag", Point::new(2, 2), Baseline::Top, &mut display)
+            .unwrap();
+        let mut expected = MockDisplay::new();
+        FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .build()
+            .draw_string("Ag", Point::new(2, 2), Baseline::Top, &mut expected)
+            .unwrap();
+        display.map(Rgb888::from).assert_eq(&expected);
+    }
+
+    /// Color with an alpha channel, as used by compositors.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Argb(u8, Rgb888);
+
+    impl PixelColor for Argb {
+        type Raw = ();
+    }
+
+    impl ColorBlend for Argb {
+        fn blend(self, background: Self, coverage: u8) -> Self {
+            Argb(self.0, self.1.blend(background.1, coverage))
+        }
+    }
+
+    impl AlphaPixelColor for Argb {
+        fn scale_alpha(self, coverage: u8) -> Self {
+            Argb((self.0 as u16 * coverage as u16 / 255) as u8, self.1)
+        }
+    }
+
+    #[test]
+    fn coverage_goes_to_alpha() {
+        use crate::FontTextStyleBuilder;
+        use embedded_graphics::{
+            mock_display::MockDisplay,
+            text::{renderer::TextRenderer, Baseline},
+        };
+        use rusttype::Font;
+
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Argb(255, Rgb888::RED))
+            .anti_aliasing_alpha()
+            .build();
+        let mut display = MockDisplay::new();
+        style
+            .draw_string("Ag", Point::new(2, 2), Baseline::Top, &mut display)
+            .unwrap();
+        let mut mask: MockDisplay<Gray8> = MockDisplay::new();
+        style
+            .draw_coverage("Ag", Point::new(2, 2), Baseline::Top, &mut mask)
+            .unwrap();
+
+        // the color is kept, the alpha is the coverage
+        mask.assert_eq(&display.map(|Argb(alpha, _)| Gray8::new(alpha)));
+        for point in display.affected_area().points() {
+            if let Some(Argb(_, rgb)) = display.get_pixel(point) {
+                assert_eq!(rgb, Rgb888::RED);
+            }
+        }
+    }
+}
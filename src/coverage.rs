@@ -0,0 +1,99 @@
+//! Glyph coverage drawn as grayscale values.
+
+use crate::{allocator, stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use embedded_graphics::{pixelcolor::Gray8, prelude::*, text::Baseline};
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw the coverage of the glyphs of `text` as gray levels, instead of blended colors.
+    ///
+    /// Each pixel touched by a glyph is drawn with its coverage, from black for uncovered to
+    /// white for fully covered, converted to the gray depth of the target. This alpha mask can
+    /// be composed with other layers by the application. Colors, antialiasing, backgrounds,
+    /// effects and decorations of the style are ignored, uncovered pixels are not drawn, and
+    /// where glyphs overlap the last one drawn wins. The text is laid out like `draw_string`.
+    ///
+    /// Returns the position following the last character.
+    pub fn draw_coverage<G, D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        G: PixelColor + From<Gray8>,
+        D: DrawTarget<Color = G>,
+    {
+        let offset = Point::new(0, self.baseline_offset(baseline));
+        let mut target = self.rotated_target(target, position);
+        let mut next = position;
+        for (line, line_offset) in self.lines(text) {
+            let line_position = position - offset + line_offset;
+            let layout = self.layout(line);
+            let mut pixels = allocator::buffer(self.allocator);
+            for glyph in layout.glyphs.iter() {
+                self.glyph_coverage(glyph, |x, y, coverage| {
+                    if coverage > 0 {
+                        let point = line_position + Point::new(x, y);
+                        pixels.push(Pixel(point, Gray8::new(coverage).into()));
+                    }
+                });
+            }
+            target.draw_iter(pixels)?;
+            next = line_position + self.advance_offset(layout.advance) + offset;
+        }
+        Ok(position + self.rotation.offset(next - position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay, pixelcolor::Gray4, pixelcolor::Rgb888,
+        text::renderer::TextRenderer,
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn coverage_matches_blending_over_black() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .anti_aliasing_color(Rgb888::BLACK)
+            .build();
+        let position = Point::new(2, 12);
+
+        let mut blended = MockDisplay::new();
+        let next = style
+            .draw_string("Ag", position, Baseline::Alphabetic, &mut blended)
+            .unwrap();
+        let mut mask: MockDisplay<Gray8> = MockDisplay::new();
+        let mask_next = style
+            .draw_coverage("Ag", position, Baseline::Alphabetic, &mut mask)
+            .unwrap();
+        assert_eq!(mask_next, next);
+        // white over black is the coverage itself
+        for point in blended.affected_area().points() {
+            let coverage = blended
+                .get_pixel(point)
+                .filter(|color| *color != Rgb888::BLACK)
+                .map(|color| Gray8::new(color.r()));
+            assert_eq!(mask.get_pixel(point), coverage, "{:?}", point);
+        }
+        assert!(!mask.affected_area().is_zero_sized());
+
+        // other gray depths are scaled
+        let mut mask4: MockDisplay<Gray4> = MockDisplay::new();
+        style
+            .draw_coverage("Ag", position, Baseline::Alphabetic, &mut mask4)
+            .unwrap();
+        mask4.assert_eq(&mask.map(Gray4::from));
+    }
+}
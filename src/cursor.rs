@@ -0,0 +1,105 @@
+//! Pen position carried across draw calls.
+//!
+//! Each `draw_string` returns a whole pixel position, drawing a line in several fragments loses
+//! the fractional part of every advance and the text drifts. A [`TextCursor`] keeps the exact pen
+//! position from one fragment to the next.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle, WritingMode};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+use embedded_graphics::prelude::*;
+
+/// Pen position with subpixel precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextCursor {
+    x: f32,
+    y: f32,
+}
+
+impl TextCursor {
+    /// Place the cursor at the top left corner of a line.
+    pub fn new(position: Point) -> Self {
+        TextCursor {
+            x: position.x as f32,
+            y: position.y as f32,
+        }
+    }
+
+    /// Pixel containing the cursor.
+    pub fn position(&self) -> Point {
+        Point::new(self.x.floor() as i32, self.y.floor() as i32)
+    }
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw a fragment of a line at `cursor` and move the cursor after it.
+    ///
+    /// The glyphs are placed at the exact cursor position, fragments drawn one after the other
+    /// are laid out like a single string, kerning between fragments aside.
+    pub fn draw_string_at<D>(
+        &self,
+        text: &str,
+        cursor: &mut TextCursor,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let position = cursor.position();
+        let start = match self.writing_mode {
+            WritingMode::Horizontal => cursor.x - position.x as f32,
+            WritingMode::Vertical => cursor.y - position.y as f32,
+        };
+        let layout = self.layout_from(text, start);
+        let mut target = self.clipped_target(target);
+        self.draw_layout(&layout, text, position, None, |_, _| None, &mut target)?;
+        match self.writing_mode {
+            WritingMode::Horizontal => cursor.x = position.x as f32 + layout.advance,
+            WritingMode::Vertical => cursor.y = position.y as f32 + layout.advance,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn fragments_do_not_drift() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(13)
+            .text_color(Rgb888::WHITE)
+            .build();
+
+        let mut fragments = MockDisplay::new();
+        let mut cursor = TextCursor::new(Point::zero());
+        for _ in 0..8 {
+            style
+                .draw_string_at("i", &mut cursor, &mut fragments)
+                .unwrap();
+        }
+        let mut whole = MockDisplay::new();
+        style
+            .draw_string("iiiiiiii", Point::zero(), Baseline::Top, &mut whole)
+            .unwrap();
+        fragments.assert_eq(&whole);
+
+        let advance = style.layout("iiiiiiii").advance;
+        assert_eq!(cursor.position(), Point::new(advance as i32, 0));
+    }
+}
@@ -0,0 +1,37 @@
+//! Diagnostic messages.
+//!
+//! With the `log` feature, font loading, cache evictions, fallback font selection and clipped
+//! drawing are reported through the [`log`](https://docs.rs/log) crate. Without it, the messages
+//! compile to nothing.
+
+/// Report a debug message.
+#[cfg(feature = "log")]
+macro_rules! debug {
+    ($($arg:tt)*) => { log::debug!($($arg)*) };
+}
+
+/// Report a debug message.
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
+
+/// Report a trace message, for frequent events.
+#[cfg(feature = "log")]
+macro_rules! trace {
+    ($($arg:tt)*) => { log::trace!($($arg)*) };
+}
+
+/// Report a trace message, for frequent events.
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    };
+}
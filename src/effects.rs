@@ -0,0 +1,312 @@
+//! Text effects drawn around the glyphs.
+//!
+//! A [`Shadow`] draws the coverage of the glyphs a second time, offset and optionally blurred,
+//! behind the text. An [`Outline`] draws the coverage dilated by a few pixels, which is much
+//! cheaper than stroking the glyph outlines. A [`Glow`] is a dilated and blurred halo, fading
+//! away from the glyphs.
+
+use crate::{
+    allocator::{self, Buffer},
+    stdlib::fmt::Debug,
+    ColorBlend, FontTextStyle,
+};
+
+use embedded_graphics::prelude::*;
+use rusttype::PositionedGlyph;
+
+/// Shadow drawn behind the text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shadow<C> {
+    /// Shadow color.
+    pub color: C,
+    /// Offset of the shadow from the text, in pixels.
+    pub offset: Point,
+    /// Blur radius in pixels, 0 for a hard shadow.
+    ///
+    /// The blur is approximated by two box blur passes, its cost grows with the text area, not
+    /// with the radius.
+    pub blur: u32,
+}
+
+impl<C> Shadow<C> {
+    /// Create a hard shadow.
+    pub fn new(color: C, offset: Point) -> Self {
+        Shadow {
+            color,
+            offset,
+            blur: 0,
+        }
+    }
+
+    /// Blur the shadow with the given radius in pixels.
+    pub fn blur(mut self, radius: u32) -> Self {
+        self.blur = radius;
+        self
+    }
+}
+
+/// Outline drawn around the glyphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outline<C> {
+    /// Outline color.
+    pub color: C,
+    /// Outline width in pixels, 1 or 2 give the best results.
+    pub width: u32,
+}
+
+impl<C> Outline<C> {
+    /// Create an outline.
+    pub fn new(color: C, width: u32) -> Self {
+        Outline { color, width }
+    }
+}
+
+/// Halo drawn behind the text, fading away from the glyphs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glow<C> {
+    /// Halo color.
+    pub color: C,
+    /// Distance in pixels from the glyphs at which the halo vanishes.
+    ///
+    /// The first half is solid, the second fades out.
+    pub radius: u32,
+}
+
+impl<C> Glow<C> {
+    /// Create a glow.
+    pub fn new(color: C, radius: u32) -> Self {
+        Glow { color, radius }
+    }
+}
+
+/// Coverage of a set of glyphs, in a rectangle of the layout space.
+struct CoverageMap {
+    left: i32,
+    top: i32,
+    width: usize,
+    height: usize,
+    data: Buffer<u8>,
+}
+
+impl CoverageMap {
+    fn get(&self, x: usize, y: usize) -> u8 {
+        self.data[y * self.width + x]
+    }
+
+    /// Call `f` on each line of the map along one axis, with a copy of the line in `scratch`
+    /// and a setter for the map values.
+    fn for_each_line(
+        &mut self,
+        horizontal: bool,
+        scratch: &mut Buffer<u8>,
+        mut f: impl FnMut(&[u8], &mut dyn FnMut(usize, u8)),
+    ) {
+        let (lines, len) = if horizontal {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        };
+        let width = self.width;
+        for line in 0..lines {
+            let index = |i: usize| {
+                if horizontal {
+                    line * width + i
+                } else {
+                    i * width + line
+                }
+            };
+            scratch.clear();
+            scratch.extend((0..len).map(|i| self.data[index(i)]));
+            let data = &mut self.data;
+            f(scratch, &mut |i, v| data[index(i)] = v);
+        }
+    }
+
+    /// Dilate the map in place by `radius` pixels along one axis.
+    fn dilate(&mut self, radius: usize, horizontal: bool, scratch: &mut Buffer<u8>) {
+        self.for_each_line(horizontal, scratch, |line, set| {
+            for i in 0..line.len() {
+                let window = &line[i.saturating_sub(radius)..(i + radius + 1).min(line.len())];
+                set(i, window.iter().copied().max().unwrap_or(0));
+            }
+        });
+    }
+
+    /// Blur the map in place with a box filter of the given radius along one axis.
+    fn box_blur(&mut self, radius: usize, horizontal: bool, scratch: &mut Buffer<u8>) {
+        let window = 2 * radius as u32 + 1;
+        self.for_each_line(horizontal, scratch, |line, set| {
+            let len = line.len();
+            let mut sum: u32 = line[..radius.min(len)].iter().map(|v| *v as u32).sum();
+            for i in 0..len {
+                if i + radius < len {
+                    sum += line[i + radius] as u32;
+                }
+                set(i, (sum / window) as u8);
+                if i >= radius {
+                    sum -= line[i - radius] as u32;
+                }
+            }
+        });
+    }
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Pixels of the shadow of the glyphs, relative to `position`.
+    pub(crate) fn shadow_pixels(
+        &self,
+        glyphs: &[PositionedGlyph<'_>],
+        position: Point,
+    ) -> Buffer<Pixel<C>> {
+        let mut pixels = allocator::buffer(self.allocator);
+        let shadow = match self.shadow {
+            Some(shadow) => shadow,
+            None => return pixels,
+        };
+        let mut map = match self.coverage_map(glyphs, shadow.blur as i32) {
+            Some(map) => map,
+            None => return pixels,
+        };
+        if shadow.blur > 0 {
+            let mut scratch = allocator::buffer(self.allocator);
+            for _ in 0..2 {
+                map.box_blur(shadow.blur as usize, true, &mut scratch);
+                map.box_blur(shadow.blur as usize, false, &mut scratch);
+            }
+        }
+
+        self.push_map_pixels(&map, shadow.color, position + shadow.offset, &mut pixels);
+        pixels
+    }
+
+    /// Pixels of the outline of the glyphs, relative to `position`.
+    pub(crate) fn outline_pixels(
+        &self,
+        glyphs: &[PositionedGlyph<'_>],
+        position: Point,
+    ) -> Buffer<Pixel<C>> {
+        let mut pixels = allocator::buffer(self.allocator);
+        let outline = match self.outline {
+            Some(outline) if outline.width > 0 => outline,
+            _ => return pixels,
+        };
+        let mut map = match self.coverage_map(glyphs, outline.width as i32) {
+            Some(map) => map,
+            None => return pixels,
+        };
+        let mut scratch = allocator::buffer(self.allocator);
+        map.dilate(outline.width as usize, true, &mut scratch);
+        map.dilate(outline.width as usize, false, &mut scratch);
+
+        self.push_map_pixels(&map, outline.color, position, &mut pixels);
+        pixels
+    }
+
+    /// Pixels of the glow around the glyphs, relative to `position`.
+    pub(crate) fn glow_pixels(
+        &self,
+        glyphs: &[PositionedGlyph<'_>],
+        position: Point,
+    ) -> Buffer<Pixel<C>> {
+        let mut pixels = allocator::buffer(self.allocator);
+        let glow = match self.glow {
+            Some(glow) if glow.radius > 0 => glow,
+            _ => return pixels,
+        };
+        let mut map = match self.coverage_map(glyphs, glow.radius as i32) {
+            Some(map) => map,
+            None => return pixels,
+        };
+        let solid = glow.radius as usize / 2;
+        let fade = glow.radius as usize - solid;
+        let mut scratch = allocator::buffer(self.allocator);
+        for horizontal in [true, false] {
+            map.dilate(solid, horizontal, &mut scratch);
+            map.box_blur(fade, horizontal, &mut scratch);
+        }
+
+        self.push_map_pixels(&map, glow.color, position, &mut pixels);
+        pixels
+    }
+
+    /// Blend the non empty pixels of a map with `color`, at `origin` in the layout space.
+    fn push_map_pixels(
+        &self,
+        map: &CoverageMap,
+        color: C,
+        origin: Point,
+        pixels: &mut Buffer<Pixel<C>>,
+    ) {
+        let origin = origin + Point::new(map.left, map.top);
+        for y in 0..map.height {
+            for x in 0..map.width {
+                let coverage = map.get(x, y);
+                if coverage == 0 {
+                    continue;
+                }
+                let point = origin + Point::new(x as i32, y as i32);
+                if let Some(color) = self.blend(color, coverage, point) {
+                    pixels.push(Pixel(point, color));
+                }
+            }
+        }
+    }
+
+    /// Coverage of all glyphs, with a margin around them.
+    fn coverage_map(&self, glyphs: &[PositionedGlyph<'_>], margin: i32) -> Option<CoverageMap> {
+        let bounds = glyphs
+            .iter()
+            .filter_map(|g| self.glyph_bounds(g))
+            .reduce(|a, b| rusttype::Rect {
+                min: rusttype::point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+                max: rusttype::point(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+            })?;
+        let left = bounds.min.x - margin;
+        let top = bounds.min.y - margin;
+        let width = (bounds.width() + 2 * margin) as usize;
+        let height = (bounds.height() + 2 * margin) as usize;
+        let mut data = allocator::zeroed(width * height, self.allocator);
+        for g in glyphs {
+            self.glyph_coverage(g, |x, y, v| {
+                let (x, y) = ((x - left) as usize, (y - top) as usize);
+                if x < width && y < height {
+                    let pixel = &mut data[y * width + x];
+                    *pixel = (*pixel).max(v);
+                }
+            });
+        }
+        Some(CoverageMap {
+            left,
+            top,
+            width,
+            height,
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_blur_spreads_coverage() {
+        let mut data = allocator::zeroed(5, allocator::GLOBAL);
+        data[2] = 255;
+        let mut map = CoverageMap {
+            left: 0,
+            top: 0,
+            width: 5,
+            height: 1,
+            data,
+        };
+        map.box_blur(1, true, &mut allocator::buffer(allocator::GLOBAL));
+        assert_eq!(&map.data[..], &[0, 85, 85, 85, 0]);
+
+        map.dilate(1, true, &mut allocator::buffer(allocator::GLOBAL));
+        assert_eq!(&map.data[..], &[85, 85, 85, 85, 85]);
+    }
+}
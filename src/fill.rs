@@ -0,0 +1,177 @@
+//! Glyph colors varying over the text.
+
+use crate::{stdlib::vec::Vec, ColorBlend};
+
+use embedded_graphics::{image::GetPixel, prelude::*, primitives::Rectangle};
+
+/// Color of the glyphs, replacing the text color.
+#[derive(Debug, Clone)]
+pub enum TextFill<C> {
+    /// Color of each pixel from its position and the index of its glyph in the line.
+    ///
+    /// Positions are those of the upright text, before rotation.
+    Function(fn(Point, usize) -> C),
+    /// Colors interpolated from `start` to `end` across the box of each line.
+    Gradient {
+        /// Color at the left or top of the line.
+        start: C,
+        /// Color at the right or bottom of the line.
+        end: C,
+        /// Direction of the interpolation.
+        direction: GradientDirection,
+    },
+    /// Image repeated from the top left corner of each line, for patterns or textures.
+    ///
+    /// Transparent pixels use the text color.
+    Texture {
+        /// Pixels of the image, row by row, `None` for transparent ones.
+        pixels: Vec<Option<C>>,
+        /// Width of the image.
+        width: u32,
+    },
+}
+
+/// Direction of a gradient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientDirection {
+    /// From left to right (default).
+    #[default]
+    Horizontal,
+    /// From top to bottom.
+    Vertical,
+}
+
+impl<C: PixelColor> TextFill<C> {
+    /// Texture copied from an image whose pixels can be read, like `ImageRaw`.
+    pub fn texture<I>(image: &I) -> Self
+    where
+        I: GetPixel<Color = C> + OriginDimensions,
+    {
+        let size = image.size();
+        TextFill::Texture {
+            pixels: Rectangle::new(Point::zero(), size)
+                .points()
+                .map(|point| image.pixel(point))
+                .collect(),
+            width: size.width,
+        }
+    }
+}
+
+impl<C: ColorBlend> TextFill<C> {
+    /// Color of the pixel at `point` of the glyph at index `glyph`, in a line covering `area`,
+    /// `None` if transparent.
+    pub(crate) fn color(&self, point: Point, glyph: usize, area: &Rectangle) -> Option<C> {
+        match self {
+            TextFill::Function(f) => Some(f(point, glyph)),
+            TextFill::Gradient {
+                start,
+                end,
+                direction,
+            } => {
+                let (offset, length) = match direction {
+                    GradientDirection::Horizontal => (point.x - area.top_left.x, area.size.width),
+                    GradientDirection::Vertical => (point.y - area.top_left.y, area.size.height),
+                };
+                let span = (length as i32 - 1).max(1);
+                let position = (offset.clamp(0, span) * 255 / span) as u8;
+                Some(end.blend(*start, position))
+            }
+            TextFill::Texture { pixels, width } => {
+                let width = *width as usize;
+                if width == 0 || pixels.len() < width {
+                    return None;
+                }
+                let height = pixels.len() / width;
+                let offset = point - area.top_left;
+                let x = offset.x.rem_euclid(width as i32) as usize;
+                let y = offset.y.rem_euclid(height as i32) as usize;
+                pixels[y * width + x]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        image::ImageRaw,
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn gradients_span_the_line() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_gradient(Rgb888::RED, Rgb888::BLUE, GradientDirection::Horizontal)
+            .build();
+        let mut display = MockDisplay::new();
+        style
+            .draw_string("HHHH", Point::new(3, 2), Baseline::Top, &mut display)
+            .unwrap();
+
+        let line = style
+            .measure_string("HHHH", Point::new(3, 2), Baseline::Top)
+            .bounding_box;
+        let mut last = (i32::MIN, u8::MAX);
+        for point in display.affected_area().points().filter(|p| p.y == 8) {
+            if let Some(color) = display.get_pixel(point) {
+                assert_eq!(color, Rgb888::BLUE.blend(Rgb888::RED, color.b()));
+                let fill = style.fill.as_ref().unwrap();
+                assert_eq!(Some(color), fill.color(point, 0, &line));
+                // red fades from left to right
+                assert!(point.x > last.0 && color.r() <= last.1);
+                last = (point.x, color.r());
+            }
+        }
+        assert!(last.1 < 64);
+
+        let vertical = TextFill::Gradient {
+            start: Rgb888::BLACK,
+            end: Rgb888::WHITE,
+            direction: GradientDirection::Vertical,
+        };
+        let area = Rectangle::new(Point::new(0, 10), Size::new(4, 6));
+        let color = |point| vertical.color(point, 0, &area);
+        assert_eq!(color(Point::new(2, 10)), Some(Rgb888::BLACK));
+        assert_eq!(color(Point::new(2, 15)), Some(Rgb888::WHITE));
+        assert_eq!(color(Point::new(2, 30)), Some(Rgb888::WHITE));
+    }
+
+    #[test]
+    fn textures_repeat_over_the_line() {
+        // 2x1 texture, red then blue
+        let texture = ImageRaw::<Rgb888>::new(&[255, 0, 0, 0, 0, 255], 2);
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_texture(&texture)
+            .build();
+        let position = Point::new(3, 2);
+        let mut display = MockDisplay::new();
+        style
+            .draw_string("HH", position, Baseline::Top, &mut display)
+            .unwrap();
+
+        let line = style
+            .measure_string("HH", position, Baseline::Top)
+            .bounding_box;
+        let area = display.affected_area();
+        assert!(!area.is_zero_sized());
+        for point in area.points() {
+            let expected = match (point.x - line.top_left.x).rem_euclid(2) {
+                0 => Rgb888::RED,
+                _ => Rgb888::BLUE,
+            };
+            assert!(display
+                .get_pixel(point)
+                .is_none_or(|color| color == expected));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Shrink to fit sizing.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle, TextBox, TextBoxStyle};
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Largest font size at which `text`, laid out as a [`TextBox`] with `style`, fits in `size`.
+    ///
+    /// Returns `None` if the text doesn't fit even at size 1.
+    pub fn fit_size(&self, text: &str, style: TextBoxStyle, size: Size) -> Option<u32> {
+        let mut text_box = TextBox::with_textbox_style(text, Point::zero(), self.clone(), style);
+        let mut fits = |font_size: u32| {
+            text_box.character_style.font_size = font_size as f32;
+            let text_size = text_box.size();
+            text_size.width <= size.width && text_size.height <= size.height
+        };
+
+        // lines are at least font_size pixels high
+        let (mut low, mut high) = (0, size.height);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if fits(middle) {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+        (low > 0).then_some(low)
+    }
+
+    /// Draw `text` in `area` at the largest font size at which it fits, see [`fit_size`].
+    ///
+    /// Returns the font size used, nothing is drawn if the text doesn't fit.
+    ///
+    /// [`fit_size`]: FontTextStyle::fit_size
+    pub fn fit_text<D>(
+        &self,
+        text: &str,
+        style: TextBoxStyle,
+        area: Rectangle,
+        target: &mut D,
+    ) -> Result<Option<u32>, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let font_size = match self.fit_size(text, style, area.size) {
+            Some(font_size) => font_size,
+            None => return Ok(None),
+        };
+        let mut character_style = self.clone();
+        character_style.font_size = font_size as f32;
+        TextBox::with_textbox_style(text, area.top_left, character_style, style).draw(target)?;
+        Ok(Some(font_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::pixelcolor::Rgb888;
+    use rusttype::Font;
+
+    #[test]
+    fn text_is_shrunk_to_fit() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::<Rgb888>::new(font)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let fit = |text, width, height| {
+            style.fit_size(text, TextBoxStyle::default(), Size::new(width, height))
+        };
+
+        // short text is limited by the height, long text by the width
+        assert_eq!(fit("1", 100, 30), Some(30));
+        let long = fit("123456789", 100, 30).unwrap();
+        assert!(long < 30);
+        assert!(fit("123456789", 100, 30 - long).unwrap() <= long);
+        // each line takes its height
+        assert_eq!(fit("1\n2", 100, 30), Some(15));
+        assert_eq!(fit("123456789", 1, 30), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! Laid out glyphs, for custom rendering and hit testing.
+
+use crate::{
+    stdlib::{fmt::Debug, vec::Vec},
+    ColorBlend, FontTextStyle,
+};
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+use rusttype::GlyphId;
+
+/// A glyph of laid out text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphInfo {
+    /// Glyph in the font that renders it, the main font or a fallback font.
+    pub id: GlyphId,
+
+    /// Pixels covered by the glyph outline, `None` for glyphs without outline such as spaces.
+    pub bounding_box: Option<Rectangle>,
+
+    /// Advance of the glyph along the writing direction, in pixels, kerning excluded.
+    pub advance: f32,
+
+    /// Byte index in the text of the cluster the glyph belongs to.
+    pub char_index: usize,
+
+    /// Byte index in the text following the cluster the glyph belongs to.
+    pub cluster_end: usize,
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Glyphs of `text` drawn at the top left `position`, in drawing order.
+    ///
+    /// The glyphs are laid out like `draw_string` does, including fallback fonts, ligatures and
+    /// the lines of multiline text. A cluster may have several glyphs, control characters and
+    /// invisible characters have none.
+    pub fn glyphs(&self, text: &str, position: Point) -> impl Iterator<Item = GlyphInfo> {
+        let mut glyphs = Vec::new();
+        for (line, offset) in self.lines(text) {
+            let origin = position + offset;
+            let start = line.as_ptr() as usize - text.as_ptr() as usize;
+            let layout = self.layout(line);
+            for (glyph, &(index, advance)) in layout.glyphs.iter().zip(&layout.glyph_sources) {
+                let end = layout
+                    .clusters
+                    .iter()
+                    .map(|&(start, _)| start)
+                    .find(|&start| start > index)
+                    .unwrap_or(line.len());
+                let bounding_box = glyph.pixel_bounding_box().map(|bounding_box| {
+                    Rectangle::new(
+                        Point::new(bounding_box.min.x, bounding_box.min.y) + origin,
+                        Size::new(bounding_box.width() as u32, bounding_box.height() as u32),
+                    )
+                });
+                glyphs.push(GlyphInfo {
+                    id: glyph.id(),
+                    bounding_box,
+                    advance,
+                    char_index: start + index,
+                    cluster_end: start + end,
+                });
+            }
+        }
+        glyphs.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn glyphs_follow_the_drawing() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font.clone())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .multiline()
+            .build();
+        let position = Point::new(3, 4);
+        let text = "Ab x\u{301}\nd";
+
+        let glyphs: Vec<GlyphInfo> = style.glyphs(text, position).collect();
+        let indices: Vec<usize> = glyphs.iter().map(|glyph| glyph.char_index).collect();
+        assert_eq!(indices, [0, 1, 2, 3, 3, 7]);
+        let ends: Vec<usize> = glyphs.iter().map(|glyph| glyph.cluster_end).collect();
+        assert_eq!(ends, [1, 2, 3, 6, 6, 8]);
+        assert_eq!(glyphs[0].id, font.glyph('A').id());
+        assert_eq!(glyphs[2].bounding_box, None);
+        let advance = font.glyph('A').scaled(rusttype::Scale::uniform(20.0));
+        assert_eq!(glyphs[0].advance, advance.h_metrics().advance_width);
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        style
+            .draw_string(text, position, Baseline::Top, &mut display)
+            .unwrap();
+        let ink = glyphs
+            .iter()
+            .filter_map(|glyph| glyph.bounding_box)
+            .reduce(|a, b| crate::envelope(&a, &b))
+            .unwrap();
+        assert_eq!(crate::envelope(&ink, &display.affected_area()), ink);
+        // the second line is below the first one
+        assert!(glyphs[5].bounding_box.unwrap().top_left.y >= position.y + 20);
+    }
+}
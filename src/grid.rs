@@ -0,0 +1,130 @@
+//! Baseline grid.
+//!
+//! A [`BaselineGrid`] puts the baselines of successive lines on a vertical rhythm, so that lines
+//! of different sizes, in different parts of a screen, still line up.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+use embedded_graphics::{
+    prelude::*,
+    text::{renderer::TextRenderer, Baseline},
+};
+
+/// Vertical rhythm of baselines, `spacing` pixels apart starting at `origin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaselineGrid {
+    /// Y coordinate of a baseline of the grid.
+    pub origin: i32,
+    /// Distance between two baselines of the grid, in pixels.
+    pub spacing: u32,
+}
+
+impl BaselineGrid {
+    /// Create a grid of baselines `spacing` pixels apart, one of them at `origin`.
+    pub fn new(origin: i32, spacing: u32) -> Self {
+        BaselineGrid { origin, spacing }
+    }
+
+    /// First baseline of the grid at or below `y`.
+    pub fn snap(&self, y: i32) -> i32 {
+        let spacing = self.spacing.max(1) as i32;
+        let offset = y - self.origin;
+        self.origin + (offset + spacing - 1).div_euclid(spacing) * spacing
+    }
+
+    /// Baseline of a line of `style` following a line with baseline `baseline`.
+    ///
+    /// The lines are at least `line_height()` apart, rounded up to the grid.
+    pub fn next_baseline<C>(&self, baseline: i32, style: &FontTextStyle<C>) -> i32
+    where
+        FontTextStyle<C>: TextRenderer,
+    {
+        self.snap(baseline + style.line_height() as i32)
+    }
+
+    /// Draw lines with their own style, on the baselines of the grid.
+    ///
+    /// The first baseline is the first grid baseline leaving room for the ascent of the first
+    /// line below `position`. Returns the baseline following the last line.
+    pub fn draw_lines<C, D>(
+        &self,
+        lines: &[(&str, &FontTextStyle<C>)],
+        position: Point,
+        target: &mut D,
+    ) -> Result<i32, D::Error>
+    where
+        C: ColorBlend + Debug,
+        D: DrawTarget<Color = C>,
+    {
+        let mut baseline = match lines.first() {
+            Some((_, style)) => self.snap(position.y + style.ascent()),
+            None => return Ok(self.snap(position.y)),
+        };
+        for (i, (text, style)) in lines.iter().enumerate() {
+            if i > 0 {
+                baseline = self.next_baseline(baseline, style);
+            }
+            let top_left = Point::new(position.x, baseline - style.ascent());
+            style.draw_string(text, top_left, Baseline::Top, target)?;
+        }
+        match lines.last() {
+            Some((_, style)) => Ok(self.next_baseline(baseline, style)),
+            None => Ok(baseline),
+        }
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Distance from the top of a line to its baseline, rounded up so that glyphs stand on the
+    /// baseline without crossing it.
+    fn ascent(&self) -> i32 {
+        (self.v_metrics().ascent - self.glyph_shift()).ceil() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+    use rusttype::Font;
+
+    #[test]
+    fn baselines_are_snapped() {
+        let grid = BaselineGrid::new(4, 16);
+        assert_eq!(grid.snap(4), 4);
+        assert_eq!(grid.snap(5), 20);
+        assert_eq!(grid.snap(-13), -12);
+    }
+
+    #[test]
+    fn lines_are_drawn_on_the_grid() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = |size| {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(size)
+                .text_color(Rgb888::WHITE)
+                .build()
+        };
+        let (large, small) = (style(20), style(12));
+        let grid = BaselineGrid::new(0, 16);
+
+        let mut display = MockDisplay::new();
+        let next = grid
+            .draw_lines(&[("H", &large), ("H", &small)], Point::zero(), &mut display)
+            .unwrap();
+        // H stands on the baselines at 16 and 32, the next one is at least 12 pixels below
+        let bottom = |rows: core::ops::Range<i32>| {
+            rows.filter(|&y| (0..64).any(|x| display.get_pixel(Point::new(x, y)).is_some()))
+                .max()
+        };
+        assert_eq!(bottom(0..16), Some(15));
+        // the small glyphs have a fractional ascent, their last row is partially covered
+        assert!(matches!(bottom(16..64), Some(30..=31)));
+        assert_eq!(next, 48);
+    }
+}
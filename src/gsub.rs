@@ -0,0 +1,125 @@
+//! OpenType glyph substitutions.
+//!
+//! rusttype maps characters to glyphs without looking at the font's `GSUB` table. With the
+//! `opentype-layout` feature, the single substitutions of a few features are applied during
+//! layout:
+//! * `locl`: localized forms, when a [`Locale`](crate::Locale) is set,
+//! * `ccmp` and `liga` ligatures inside clusters, which is how fonts render emoji sequences,
+//! * `vert`: vertical forms (rotated punctuation ...), in vertical writing mode.
+
+use crate::{script::Script, stdlib::vec::Vec};
+use owned_ttf_parser::{
+    gsub::{LigatureSubstitution, SingleSubstitution, SubstitutionSubtable},
+    opentype_layout::{LayoutTable, LookupIndex},
+    Face, GlyphId, Tag,
+};
+
+/// Substitutions of a font feature for a given script and language.
+///
+/// Only single and ligature substitutions are supported, which is what `locl`, `vert` and emoji
+/// lookups use in practice.
+pub(crate) struct Substitutions<'a> {
+    gsub: LayoutTable<'a>,
+    lookups: Vec<LookupIndex>,
+}
+
+impl<'a> Substitutions<'a> {
+    /// Find the lookups of a feature, `None` if the font has none for this script and language.
+    ///
+    /// Without language, or when the font doesn't know it, the script's default language is used.
+    pub(crate) fn new(
+        face: &Face<'a>,
+        script: Script,
+        language: Option<Tag>,
+        feature: &[u8; 4],
+    ) -> Option<Self> {
+        let gsub = face.tables().gsub?;
+        let script = script
+            .opentype_tag()
+            .and_then(|tag| gsub.scripts.find(tag))
+            .or_else(|| gsub.scripts.find(Tag::from_bytes(b"DFLT")))?;
+        let language = language
+            .and_then(|tag| script.languages.find(tag))
+            .or(script.default_language)?;
+        let feature = Tag::from_bytes(feature);
+        let lookups: Vec<LookupIndex> = language
+            .feature_indices
+            .into_iter()
+            .filter_map(|index| gsub.features.get(index))
+            .filter(|f| f.tag == feature)
+            .flat_map(|f| f.lookup_indices.into_iter())
+            .collect();
+        if lookups.is_empty() {
+            return None;
+        }
+        Some(Substitutions { gsub, lookups })
+    }
+
+    /// Apply single substitutions to a glyph.
+    pub(crate) fn apply(&self, glyph: rusttype::GlyphId) -> rusttype::GlyphId {
+        let mut glyph = GlyphId(glyph.0);
+        for lookup in self
+            .lookups
+            .iter()
+            .filter_map(|i| self.gsub.lookups.get(*i))
+        {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                if let SubstitutionSubtable::Single(single) = subtable {
+                    if let Some(index) = single.coverage().get(glyph) {
+                        glyph = match single {
+                            SingleSubstitution::Format1 { delta, .. } => {
+                                GlyphId((glyph.0 as i32 + delta as i32) as u16)
+                            }
+                            SingleSubstitution::Format2 { substitutes, .. } => {
+                                substitutes.get(index).unwrap_or(glyph)
+                            }
+                        };
+                        break;
+                    }
+                }
+            }
+        }
+        rusttype::GlyphId(glyph.0)
+    }
+
+    /// Apply ligature substitutions to a sequence of glyphs, the ligature keeps the data of its
+    /// first component.
+    pub(crate) fn apply_ligatures<T>(&self, glyphs: &mut Vec<(rusttype::GlyphId, T)>) {
+        for lookup in self
+            .lookups
+            .iter()
+            .filter_map(|i| self.gsub.lookups.get(*i))
+        {
+            for subtable in lookup.subtables.into_iter::<SubstitutionSubtable>() {
+                if let SubstitutionSubtable::Ligature(ligatures) = subtable {
+                    let mut i = 0;
+                    while i < glyphs.len() {
+                        if let Some(len) = Self::ligate(&ligatures, &mut glyphs[i..]) {
+                            glyphs.drain(i + 1..i + 1 + len);
+                        }
+                        i += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Replace the first glyph with a ligature, returns the number of other glyphs it replaces.
+    fn ligate<T>(
+        ligatures: &LigatureSubstitution<'_>,
+        glyphs: &mut [(rusttype::GlyphId, T)],
+    ) -> Option<usize> {
+        let index = ligatures.coverage.get(GlyphId(glyphs[0].0 .0))?;
+        let set = ligatures.ligature_sets.get(index)?;
+        let ligature = set.into_iter().find(|ligature| {
+            (ligature.components.len() as usize) < glyphs.len()
+                && ligature
+                    .components
+                    .into_iter()
+                    .zip(glyphs[1..].iter())
+                    .all(|(component, (glyph, _))| component.0 == glyph.0)
+        })?;
+        glyphs[0].0 = rusttype::GlyphId(ligature.glyph.0);
+        Some(ligature.components.len() as usize)
+    }
+}
@@ -0,0 +1,105 @@
+//! Vertical grid fitting of glyph outlines.
+//!
+//! At small sizes, the horizontal edges of glyphs fall between pixel rows and are smeared over
+//! two rows. Fitting moves the key heights of the font, the baseline, the x-height and the cap
+//! height, to whole rows and stretches the outline between them. Like the light hinting of
+//! FreeType, only heights are fitted, advances and horizontal positions are unchanged.
+
+use crate::FontTextStyle;
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+use embedded_graphics::pixelcolor::PixelColor;
+
+/// Piecewise linear fitting of the heights of a glyph outline.
+pub(crate) struct GridFit {
+    /// Heights of the outline and where they move, y pointing down from the glyph origin,
+    /// from the highest.
+    zones: [(f32, f32); 3],
+}
+
+impl GridFit {
+    /// Height of the outline `y` moved to the grid.
+    pub(crate) fn apply(&self, y: f32) -> f32 {
+        let shift = |(from, to): (f32, f32)| y + to - from;
+        let [top, .., bottom] = self.zones;
+        if y <= top.0 {
+            return shift(top);
+        }
+        for pair in self.zones.windows(2) {
+            let ((from_a, to_a), (from_b, to_b)) = (pair[0], pair[1]);
+            if y <= from_b {
+                if from_b <= from_a {
+                    return shift(pair[1]);
+                }
+                let t = (y - from_a) / (from_b - from_a);
+                return to_a + t * (to_b - to_a);
+            }
+        }
+        shift(bottom)
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Fitting of the glyphs whose baseline is at `baseline` in the layout space.
+    pub(crate) fn grid_fit(&self, baseline: f32) -> GridFit {
+        let metrics = self.metrics();
+        // rows are whole in the layout space
+        let snap = |height: f32| (-height, (baseline - height).round() - baseline);
+        GridFit {
+            zones: [metrics.cap_height, metrics.x_height, 0.0].map(snap),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::{Gray8, Rgb888},
+        prelude::*,
+        text::Baseline,
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn horizontal_edges_are_sharp() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        // height of an E and coverage of its top and bottom rows
+        let bars = |size, hinting| {
+            let style = FontTextStyleBuilder::new(font.clone())
+                .font_size(size)
+                .text_color(Rgb888::WHITE)
+                .hinting(hinting)
+                .build();
+            let mut display: MockDisplay<Gray8> = MockDisplay::new();
+            style
+                .draw_coverage("E", Point::new(1, 1), Baseline::Top, &mut display)
+                .unwrap();
+            let area = display.affected_area();
+            let row = |y| {
+                (area.top_left.x..area.top_left.x + area.size.width as i32)
+                    .filter_map(|x| display.get_pixel(Point::new(x, y)))
+                    .map(|c| c.luma())
+                    .max()
+                    .unwrap_or(0)
+            };
+            let bottom = area.bottom_right().unwrap().y;
+            (area.size.height, row(area.top_left.y), row(bottom))
+        };
+
+        let mut shorter = 0;
+        for size in 10..=14 {
+            let (height, top, bottom) = bars(size, false);
+            let (hinted_height, hinted_top, hinted_bottom) = bars(size, true);
+            // the bars are not smeared over two rows
+            assert!(hinted_height <= height, "{}", size);
+            assert!(hinted_top >= top && hinted_bottom >= bottom, "{}", size);
+            shorter += (hinted_height < height) as u32;
+        }
+        assert!(shorter > 0);
+    }
+}
@@ -0,0 +1,154 @@
+//! Hit testing and carets, mapping between points and characters of a text.
+//!
+//! Positions follow the layout used for drawing, with the text drawn at the top left `origin`.
+//! Characters are found by cluster, a point on a combining mark or an emoji sequence maps to the
+//! start of the whole cluster.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle, WritingMode};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+use embedded_graphics::{prelude::*, text::renderer::TextRenderer};
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Byte index of the character of `text` drawn at `origin` under `point`.
+    ///
+    /// A line spans `line_height()` pixels across and its advance along the writing direction.
+    /// Returns `None` if the point is not on a line.
+    pub fn char_index_at(&self, text: &str, origin: Point, point: Point) -> Option<usize> {
+        let line_height = self.line_height() as i32;
+        let relative = point - origin;
+        let (along, across) = match self.writing_mode {
+            WritingMode::Horizontal => (relative.x, relative.y),
+            // lines follow each other leftwards
+            WritingMode::Vertical => (relative.y, line_height - 1 - relative.x),
+        };
+        if across < 0 || along < 0 || line_height <= 0 {
+            return None;
+        }
+
+        let (line, _) = self.lines(text).nth((across / line_height) as usize)?;
+        let layout = self.layout(line);
+        if along as f32 >= layout.advance {
+            return None;
+        }
+        let (index, _) = layout
+            .clusters
+            .iter()
+            .take_while(|(_, caret)| *caret <= along as f32)
+            .last()
+            .or_else(|| layout.clusters.first())?;
+        Some(line.as_ptr() as usize - text.as_ptr() as usize + index)
+    }
+
+    /// Position of the insertion point before the character at byte index `char_index` of `text`
+    /// drawn at `origin`.
+    ///
+    /// The caret is placed at the top of its line, rounded up to a whole pixel like the position
+    /// returned by `draw_string`. An index inside a cluster is moved to its start, an index at a
+    /// line break or past the text to the end of the line.
+    pub fn caret_position(&self, text: &str, origin: Point, char_index: usize) -> Point {
+        let mut last = (text, Point::zero());
+        for (line, offset) in self.lines(text) {
+            if line.as_ptr() as usize - text.as_ptr() as usize > char_index {
+                break;
+            }
+            last = (line, offset);
+        }
+
+        let (line, offset) = last;
+        let index = char_index.saturating_sub(line.as_ptr() as usize - text.as_ptr() as usize);
+        let layout = self.layout(line);
+        let caret = match index < line.len() {
+            true => layout
+                .clusters
+                .iter()
+                .take_while(|(start, _)| *start <= index)
+                .last()
+                .map_or(0.0, |(_, caret)| *caret),
+            false => layout.advance,
+        };
+        let caret = caret.ceil() as i32;
+        let along = match self.writing_mode {
+            WritingMode::Horizontal => Point::new(caret, 0),
+            WritingMode::Vertical => Point::new(0, caret),
+        };
+        origin + offset + along
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{pixelcolor::Rgb888, text::Baseline};
+    use rusttype::Font;
+
+    #[test]
+    fn points_map_to_characters() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::<Rgb888>::new(font)
+            .font_size(20)
+            .multiline()
+            .build();
+        let origin = Point::new(10, 10);
+        let width = |text| {
+            style
+                .measure_string(text, Point::zero(), Baseline::Top)
+                .next_position
+                .x
+        };
+
+        let text = "ab\u{301}c\nde";
+        assert_eq!(style.char_index_at(text, origin, origin), Some(0));
+        let b = origin + Point::new(width("a"), 5);
+        assert_eq!(style.char_index_at(text, origin, b), Some(1));
+        // the combining mark belongs to the cluster of its base
+        let c = origin + Point::new(width("ab\u{301}"), 19);
+        assert_eq!(style.char_index_at(text, origin, c), Some(4));
+        let e = origin + Point::new(width("d"), 20);
+        assert_eq!(style.char_index_at(text, origin, e), Some(7));
+
+        assert_eq!(style.char_index_at(text, origin, Point::new(9, 10)), None);
+        assert_eq!(style.char_index_at(text, origin, Point::new(10, 9)), None);
+        assert_eq!(style.char_index_at(text, origin, Point::new(10, 50)), None);
+        let after = origin + Point::new(width("de") + 1, 20);
+        assert_eq!(style.char_index_at(text, origin, after), None);
+    }
+
+    #[test]
+    fn carets_follow_the_advances() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::<Rgb888>::new(font)
+            .font_size(20)
+            .multiline()
+            .build();
+        let origin = Point::new(10, 10);
+        let caret = |text| origin + Point::new(style.layout(text).advance.ceil() as i32, 0);
+
+        let text = "ab\u{301}c\r\nde";
+        assert_eq!(style.caret_position(text, origin, 0), origin);
+        assert_eq!(style.caret_position(text, origin, 1), caret("a"));
+        // inside a cluster, the caret is before it
+        assert_eq!(style.caret_position(text, origin, 2), caret("a"));
+        assert_eq!(style.caret_position(text, origin, 4), caret("ab\u{301}"));
+        assert_eq!(style.caret_position(text, origin, 5), caret("ab\u{301}c"));
+        assert_eq!(style.caret_position(text, origin, 6), caret("ab\u{301}c"));
+        let second = origin + Point::new(0, 20);
+        assert_eq!(style.caret_position(text, origin, 7), second);
+        assert_eq!(
+            style.caret_position(text, origin, 100),
+            second + Point::new(style.layout("de").advance.ceil() as i32, 0)
+        );
+
+        for index in [0, 1, 4, 7, 8] {
+            let point = style.caret_position(text, origin, index);
+            assert_eq!(style.char_index_at(text, origin, point), Some(index));
+        }
+    }
+}
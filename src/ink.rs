@@ -0,0 +1,146 @@
+//! Bounds of the drawn pixels.
+
+use crate::{envelope, stdlib::fmt::Debug, AntiAliasing, ColorBlend, FontTextStyle};
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Smallest rectangle containing the pixels drawn for `text` at the top left `position`.
+    ///
+    /// Unlike the bounding box of `measure_string`, it only covers the pixels the glyphs cover,
+    /// their outline, glow, shadow and decorations, not the whole line. The background, if any,
+    /// fills the bounding box of `measure_string`. The rectangle is empty at `position` if nothing
+    /// is drawn.
+    pub fn ink_bounding_box(&self, text: &str, position: Point) -> Rectangle {
+        let mut ink: Option<Rectangle> = None;
+        let mut add = |area: Rectangle| {
+            if !area.is_zero_sized() {
+                ink = Some(match ink {
+                    Some(ink) => envelope(&ink, &area),
+                    None => area,
+                });
+            }
+        };
+
+        for (line, offset) in self.lines(text) {
+            let origin = position + offset;
+            let layout = self.layout(line);
+
+            let mut glyphs: Option<(Point, Point)> = None;
+            for glyph in layout.glyphs.iter() {
+                self.glyph_coverage(glyph, |x, y, coverage| {
+                    if coverage > 0 {
+                        let point = Point::new(x, y);
+                        glyphs = Some(match glyphs {
+                            Some((min, max)) => {
+                                (min.component_min(point), max.component_max(point))
+                            }
+                            None => (point, point),
+                        });
+                    }
+                });
+            }
+            if let Some((min, max)) = glyphs {
+                let mut glyphs = Rectangle::with_corners(min, max).translate(origin);
+                if let AntiAliasing::Subpixel(_) = self.anti_aliasing {
+                    // the filter spreads the edges to the stripes of the neighboring pixels
+                    glyphs = Rectangle::new(
+                        glyphs.top_left - Point::new(1, 0),
+                        glyphs.size + Size::new(2, 0),
+                    );
+                }
+                add(glyphs);
+                if let Some(outline) = self.outline {
+                    add(glyphs.offset(outline.width as i32));
+                }
+                if let Some(glow) = self.glow {
+                    add(glyphs.offset(glow.radius as i32));
+                }
+                if let Some(shadow) = self.shadow {
+                    add(glyphs.translate(shadow.offset).offset(shadow.blur as i32));
+                }
+            }
+
+            for notdef in layout.notdef_boxes.iter() {
+                let min = Point::new(notdef.min.x.round() as i32, notdef.min.y.round() as i32);
+                let max = Point::new(notdef.max.x.round() as i32, notdef.max.y.round() as i32);
+                let size = Size::new((max.x - min.x).max(0) as u32, (max.y - min.y).max(0) as u32);
+                add(Rectangle::new(min + origin, size));
+            }
+
+            let line = self.line_box(&layout).translate(origin);
+            for (decoration, _) in self.decorations(line) {
+                add(decoration);
+            }
+        }
+
+        ink.unwrap_or_else(|| Rectangle::new(position, Size::zero()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FontTextStyleBuilder, NotdefMetrics};
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn ink_box_is_tight() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let builder = || {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .anti_aliasing_color(Rgb888::BLACK)
+        };
+        let position = Point::new(5, 5);
+
+        let boxed = builder().notdef_metrics(NotdefMetrics::default()).build();
+        for style in [builder().build(), builder().underline().build(), boxed] {
+            for text in ["x", "Hello", ". ,", "a\u{E000}"] {
+                let mut display = MockDisplay::new();
+                // the underline crosses descenders
+                display.set_allow_overdraw(true);
+                style
+                    .draw_string(text, position, Baseline::Top, &mut display)
+                    .unwrap();
+                let mut covered = MockDisplay::new();
+                covered.set_allow_overdraw(true);
+                for point in display.bounding_box().points() {
+                    if let Some(color) = display.get_pixel(point) {
+                        if color != Rgb888::BLACK {
+                            covered.set_pixel(point, Some(color));
+                        }
+                    }
+                }
+                assert_eq!(
+                    style.ink_bounding_box(text, position),
+                    covered.affected_area(),
+                    "{:?}",
+                    text
+                );
+            }
+        }
+        let metrics = builder()
+            .build()
+            .measure_string("x", position, Baseline::Top);
+        let ink = builder().build().ink_bounding_box("x", position);
+        assert!(ink.size.height < metrics.bounding_box.size.height);
+        assert_eq!(
+            builder().build().ink_bounding_box(" ", position).size,
+            Size::zero()
+        );
+    }
+}
@@ -0,0 +1,623 @@
+//! Glyph layout.
+//!
+//! A line of text is split into script runs, each run into clusters, and each cluster is mapped
+//! to positioned glyphs of the best font available.
+
+use crate::{
+    cluster, script, stdlib::vec::Vec, FontTextStyle, TabSize, VerticalPosition, WritingMode,
+};
+
+#[cfg(feature = "opentype-layout")]
+use crate::gsub::Substitutions;
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::FloatCore;
+
+use embedded_graphics::pixelcolor::PixelColor;
+use rusttype::{Font, GlyphId, PositionedGlyph, Rect, Scale, ScaledGlyph};
+
+/// Access the parsed font tables of a rusttype font.
+pub(crate) fn face<'a>(font: &'a Font<'_>) -> &'a owned_ttf_parser::Face<'a> {
+    use owned_ttf_parser::AsFaceRef;
+    match font {
+        Font::Ref(face) => face,
+        Font::Owned(face) => face.as_face_ref(),
+    }
+}
+
+/// Glyphs of a laid out line of text.
+#[derive(Debug)]
+pub(crate) struct LineLayout {
+    pub(crate) glyphs: Vec<PositionedGlyph<'static>>,
+    /// Caret position after the last glyph, along the writing direction.
+    pub(crate) advance: f32,
+    /// Boxes drawn in place of missing glyphs without outline.
+    pub(crate) notdef_boxes: Vec<Rect<f32>>,
+    /// Byte index in the text and caret position before each cluster.
+    pub(crate) clusters: Vec<(usize, f32)>,
+    /// Byte index in the text of the cluster and advance of each glyph.
+    pub(crate) glyph_sources: Vec<(usize, f32)>,
+}
+
+/// Receives the clusters and glyphs of a line as they are laid out.
+pub(crate) trait LayoutSink {
+    /// A cluster starts at byte `index` of the text, with the caret at `caret`.
+    fn cluster(&mut self, index: usize, caret: f32);
+
+    /// A glyph of the cluster starting at byte `cluster`, with its advance and the box drawn in
+    /// its place if it is missing.
+    fn glyph(
+        &mut self,
+        glyph: PositionedGlyph<'static>,
+        cluster: usize,
+        advance: f32,
+        notdef_box: Option<Rect<f32>>,
+    );
+}
+
+impl LayoutSink for LineLayout {
+    fn cluster(&mut self, index: usize, caret: f32) {
+        self.clusters.push((index, caret));
+    }
+
+    fn glyph(
+        &mut self,
+        glyph: PositionedGlyph<'static>,
+        cluster: usize,
+        advance: f32,
+        notdef_box: Option<Rect<f32>>,
+    ) {
+        self.glyphs.push(glyph);
+        self.glyph_sources.push((cluster, advance));
+        self.notdef_boxes.extend(notdef_box);
+    }
+}
+
+impl LineLayout {
+    /// Advance and horizontal extent of the line.
+    pub(crate) fn extent(&self) -> LineExtent {
+        let mut extent = LineExtent {
+            advance: self.advance,
+            ..LineExtent::default()
+        };
+        for glyph in self.glyphs.iter() {
+            extent.add_glyph(glyph);
+        }
+        for notdef_box in self.notdef_boxes.iter() {
+            extent.add_box(notdef_box);
+        }
+        extent
+    }
+}
+
+/// What measuring a line needs: its advance and the horizontal extent of its pixels, computed
+/// without keeping the glyphs.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct LineExtent {
+    /// Caret position after the last glyph, along the writing direction.
+    pub(crate) advance: f32,
+    /// Leftmost pixel column of the glyphs, from the line origin.
+    pub(crate) left: i32,
+    /// Column following the rightmost pixel of the glyphs.
+    pub(crate) right: i32,
+}
+
+impl LineExtent {
+    fn add_glyph(&mut self, glyph: &PositionedGlyph<'_>) {
+        if let Some(bounding_box) = glyph.pixel_bounding_box() {
+            self.left = self.left.min(bounding_box.min.x);
+            self.right = self.right.max(bounding_box.max.x);
+        }
+    }
+
+    fn add_box(&mut self, notdef_box: &Rect<f32>) {
+        self.left = self.left.min(notdef_box.min.x.floor() as i32);
+        self.right = self.right.max(notdef_box.max.x.ceil() as i32);
+    }
+}
+
+impl LayoutSink for LineExtent {
+    fn cluster(&mut self, _index: usize, _caret: f32) {}
+
+    fn glyph(
+        &mut self,
+        glyph: PositionedGlyph<'static>,
+        _cluster: usize,
+        _advance: f32,
+        notdef_box: Option<Rect<f32>>,
+    ) {
+        self.add_glyph(&glyph);
+        if let Some(notdef_box) = notdef_box {
+            self.add_box(&notdef_box);
+        }
+    }
+}
+
+/// Glyphs of a cluster, a cluster of a single glyph is not allocated.
+enum Shaped {
+    One(Option<(GlyphId, char)>),
+    Many(Vec<(GlyphId, char)>),
+}
+
+impl Shaped {
+    fn as_slice(&self) -> &[(GlyphId, char)] {
+        match self {
+            Shaped::One(glyph) => glyph.as_slice(),
+            Shaped::Many(glyphs) => glyphs,
+        }
+    }
+}
+
+/// Maps clusters of a run to glyphs of a font.
+///
+/// With the `opentype-layout` feature, the font substitutions are looked up once per run.
+struct Shaper<'a, C> {
+    style: &'a FontTextStyle<C>,
+    font: &'a Font<'static>,
+    #[cfg(feature = "opentype-layout")]
+    ligatures: [Option<Substitutions<'a>>; 2],
+    #[cfg(feature = "opentype-layout")]
+    forms: [Option<Substitutions<'a>>; 2],
+}
+
+impl<'a, C: PixelColor> Shaper<'a, C> {
+    #[cfg_attr(not(feature = "opentype-layout"), allow(unused_variables))]
+    fn new(style: &'a FontTextStyle<C>, font: &'a Font<'static>, script: script::Script) -> Self {
+        #[cfg(feature = "opentype-layout")]
+        {
+            let face = face(font);
+            let language = style.locale.and_then(|l| l.opentype_tag());
+            let substitutions = |feature| Substitutions::new(face, script, language, feature);
+            let local_forms = language
+                .and_then(|language| Substitutions::new(face, script, Some(language), b"locl"));
+            let vertical_forms = match style.writing_mode {
+                WritingMode::Horizontal => None,
+                WritingMode::Vertical => substitutions(b"vert"),
+            };
+            Shaper {
+                style,
+                font,
+                ligatures: [substitutions(b"ccmp"), substitutions(b"liga")],
+                forms: [local_forms, vertical_forms],
+            }
+        }
+        #[cfg(not(feature = "opentype-layout"))]
+        Shaper { style, font }
+    }
+
+    /// Glyphs of transformed characters, with the character each glyph comes from.
+    fn glyphs(&self, chars: impl Iterator<Item = char>) -> Vec<(GlyphId, char)> {
+        let style = self.style;
+        chars
+            .filter_map(|c| style.control_characters.apply(c))
+            .flat_map(|c| style.text_transform.apply(c, style.locale))
+            .map(|c| (self.font.glyph(c).id(), c))
+            .collect()
+    }
+
+    /// Map the characters of a cluster to glyphs, with the character each glyph comes from.
+    ///
+    /// Joiners and selectors are removed, as well as components of a sequence the font doesn't
+    /// know.
+    fn shape(&self, cluster: &str) -> Shaped {
+        if let Some(glyph) = self.shape_char(cluster) {
+            return Shaped::One(glyph);
+        }
+
+        #[cfg_attr(not(feature = "unicode-normalization"), allow(unused_mut))]
+        let mut glyphs = self.glyphs(cluster.chars());
+
+        // precomposed characters, unless the font only has the decomposed ones
+        #[cfg(feature = "unicode-normalization")]
+        {
+            use unicode_normalization::UnicodeNormalization;
+            if glyphs.len() > 1 {
+                let composed = self.glyphs(cluster.chars().nfc());
+                if composed.len() < glyphs.len() && composed.iter().all(|(id, _)| id.0 != 0) {
+                    glyphs = composed;
+                }
+            }
+        }
+
+        #[cfg(feature = "opentype-layout")]
+        {
+            if glyphs.len() > 1 {
+                for ligatures in self.ligatures.iter().flatten() {
+                    ligatures.apply_ligatures(&mut glyphs);
+                }
+            }
+            for forms in self.forms.iter().flatten() {
+                for (id, _) in glyphs.iter_mut() {
+                    *id = forms.apply(*id);
+                }
+            }
+        }
+
+        let mut first = true;
+        glyphs.retain(|&(id, c)| {
+            let keep = !cluster::is_invisible(c) && (first || id.0 != 0);
+            first = false;
+            keep
+        });
+        Shaped::Many(glyphs)
+    }
+
+    /// Glyph of a cluster of a single character which stays a single character, `None` for other
+    /// clusters.
+    fn shape_char(&self, cluster: &str) -> Option<Option<(GlyphId, char)>> {
+        let style = self.style;
+        let mut chars = cluster.chars();
+        let c = match (chars.next(), chars.next()) {
+            (Some(c), None) => c,
+            _ => return None,
+        };
+        let mut transformed = style
+            .control_characters
+            .apply(c)
+            .into_iter()
+            .flat_map(|c| style.text_transform.apply(c, style.locale));
+        let c = match (transformed.next(), transformed.next()) {
+            (None, _) => return Some(None),
+            (Some(c), None) => c,
+            _ => return None,
+        };
+        if cluster::is_invisible(c) {
+            return Some(None);
+        }
+        #[cfg_attr(not(feature = "opentype-layout"), allow(unused_mut))]
+        let mut id = self.font.glyph(c).id();
+        #[cfg(feature = "opentype-layout")]
+        for forms in self.forms.iter().flatten() {
+            id = forms.apply(id);
+        }
+        Some(Some((id, c)))
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Lay out a single line of text, the glyph origin is the top left corner of the line.
+    ///
+    /// The text is split into runs of a single script, each run is laid out with the font that
+    /// covers it best.
+    pub(crate) fn layout(&self, text: &str) -> LineLayout {
+        self.layout_from(text, 0.0)
+    }
+
+    /// Lay out a single line of text starting `start` pixels after the origin, along the writing
+    /// direction.
+    pub(crate) fn layout_from(&self, text: &str, start: f32) -> LineLayout {
+        let mut layout = LineLayout {
+            glyphs: Vec::new(),
+            advance: 0.0,
+            notdef_boxes: Vec::new(),
+            clusters: Vec::new(),
+            glyph_sources: Vec::new(),
+        };
+        layout.advance = self.lay_out(text, start, &mut layout);
+        layout
+    }
+
+    /// Advance and extent of a single line of text, like `layout` gives them.
+    ///
+    /// The glyphs are not kept. Without the `opentype-layout` feature, which looks up the font
+    /// substitutions of each run, nothing is allocated for clusters of a single character.
+    pub(crate) fn line_extent(&self, text: &str) -> LineExtent {
+        let mut extent = LineExtent::default();
+        extent.advance = self.lay_out(text, 0.0, &mut extent);
+        extent
+    }
+
+    /// Lay out a single line of text into `sink`, returns the caret after the last glyph.
+    fn lay_out(&self, text: &str, start: f32, sink: &mut impl LayoutSink) -> f32 {
+        let scale = self.scale();
+        let v_metrics = self.v_metrics();
+        let shift = self.glyph_shift();
+        let horizontal = self.writing_mode == WritingMode::Horizontal;
+
+        let mut caret = start;
+        let mut last: Option<(&Font<'static>, GlyphId)> = None;
+        let mut spaced = false;
+        let mut after_digit = false;
+        let mut digits: Option<(&Font<'static>, f32)> = None;
+        for (start, end, script) in script::ScriptRuns::new(text) {
+            let run = &text[start..end];
+            let run_font = self.run_font(run);
+            let run_shaper = Shaper::new(self, run_font, script);
+
+            let mut index = start;
+            for cluster in cluster::Clusters::new(run) {
+                let cluster_index = index;
+                sink.cluster(index, caret);
+                index += cluster.len();
+                if cluster == "\t" {
+                    caret = self.next_tab_stop(caret, scale);
+                    last = None;
+                    spaced = false;
+                    continue;
+                }
+                let font = self.cluster_font(run_font, cluster);
+                let shaped = if core::ptr::eq(font, run_font) {
+                    run_shaper.shape(cluster)
+                } else {
+                    Shaper::new(self, font, script).shape(cluster)
+                };
+                let shaped = shaped.as_slice();
+                // letter spacing goes between clusters, not at the start of the line or a tab
+                let drawn = !shaped.is_empty();
+                if drawn {
+                    if spaced {
+                        caret += self.letter_spacing as f32;
+                    }
+                    spaced = true;
+                }
+                // with a fixed advance, the first glyph takes the rest of the cell
+                let extra = self
+                    .fixed_advance
+                    .filter(|_| horizontal && drawn)
+                    .map(|cell| {
+                        let advances = shaped
+                            .iter()
+                            .map(|&(id, c)| self.resolve_glyph(font, id, c, scale).1);
+                        cell as f32 - advances.sum::<f32>()
+                    });
+                for (i, &(id, c)) in shaped.iter().enumerate() {
+                    let (glyph, h_advance, notdef) = self.resolve_glyph(font, id, c, scale);
+                    let id = glyph.id();
+                    let tabular = self.tabular_digits && c.is_ascii_digit() && extra.is_none();
+                    let (origin, advance) = match self.writing_mode {
+                        WritingMode::Horizontal => {
+                            // tabular digits keep their advance whatever their neighbours
+                            let kerned = self.kerning
+                                && !tabular
+                                && !after_digit
+                                && self.fixed_advance.is_none();
+                            if let Some((last_font, last_id)) = last.filter(|_| kerned) {
+                                if core::ptr::eq(last_font, font) {
+                                    caret += font.pair_kerning(scale, last_id, id);
+                                }
+                            }
+                            let (inset, advance) = match (tabular, extra) {
+                                (true, _) => {
+                                    let width = match digits {
+                                        Some((digits_font, width))
+                                            if core::ptr::eq(digits_font, font) =>
+                                        {
+                                            width
+                                        }
+                                        _ => digit_advance(font, scale) + self.faux_bold as f32,
+                                    };
+                                    digits = Some((font, width));
+                                    ((width - h_advance) / 2.0, width)
+                                }
+                                // clusters are centered in their cell
+                                (false, Some(extra)) if i == 0 => (extra / 2.0, h_advance + extra),
+                                (false, Some(extra)) => (extra / 2.0, h_advance),
+                                (false, None) => (0.0, h_advance),
+                            };
+                            let origin = rusttype::point(caret + inset, v_metrics.ascent - shift);
+                            (origin, advance)
+                        }
+                        WritingMode::Vertical => {
+                            let (y, advance) = self.vertical_metrics(font, id, scale);
+                            let x = (self.font_size - h_advance) / 2.0 + shift;
+                            (rusttype::point(x, caret + y), advance)
+                        }
+                    };
+                    caret += advance;
+                    let notdef_box = notdef.map(|(width, height)| {
+                        // leave a small gap between consecutive boxes
+                        let margin = (width / 10.0).max(1.0);
+                        Rect {
+                            min: rusttype::point(origin.x + margin, origin.y - height),
+                            max: rusttype::point(origin.x + width - margin, origin.y),
+                        }
+                    });
+                    sink.glyph(glyph.positioned(origin), cluster_index, advance, notdef_box);
+                    last = Some((font, id));
+                    after_digit = tabular;
+                }
+                if drawn && cluster.starts_with(char::is_whitespace) {
+                    caret += self.word_spacing as f32;
+                }
+            }
+        }
+        caret
+    }
+
+    /// Glyph drawn for `id`, mapped from `c`, with its horizontal advance and the width and height
+    /// of the box drawn if it is missing.
+    fn resolve_glyph(
+        &self,
+        font: &Font<'static>,
+        id: GlyphId,
+        c: char,
+        scale: Scale,
+    ) -> (ScaledGlyph<'static>, f32, Option<(f32, f32)>) {
+        // spaces missing from the font are drawn as a regular space of their width
+        let space = match id.0 {
+            0 => space_advance(font, c, scale),
+            _ => None,
+        };
+        let id = space.map_or(id, |_| font.glyph(' ').id());
+        let glyph = font.glyph(id).scaled(scale);
+        let mut h_advance = space.unwrap_or(glyph.h_metrics().advance_width);
+        let notdef = match self.notdef_metrics {
+            Some(metrics) if id.0 == 0 && space.is_none() => {
+                let em = font.units_per_em() as f32 * font.scale_for_pixel_height(scale.y);
+                if h_advance <= 0.0 {
+                    h_advance = metrics.advance * em * scale.x / scale.y;
+                }
+                glyph
+                    .exact_bounding_box()
+                    .is_none()
+                    .then_some((h_advance, metrics.height * em))
+            }
+            _ => None,
+        };
+        (glyph, h_advance + self.faux_bold as f32, notdef)
+    }
+
+    /// Scale of the glyphs, `font_size` pixels tall, reduced for superscripts and subscripts.
+    pub(crate) fn scale(&self) -> Scale {
+        let size = self.font_size * self.vertical_position_metrics().0;
+        Scale {
+            x: size * self.horizontal_scale,
+            y: size,
+        }
+    }
+
+    /// Kerning between `a` followed by `b` in the main font, in pixels at the style size.
+    ///
+    /// Negative values bring the characters closer. Pairs come from the `kern` table, as applied
+    /// by layout in horizontal text unless kerning is disabled, rounded to whole pixels.
+    pub fn kerning(&self, a: char, b: char) -> i32 {
+        let (a, b) = (self.font.glyph(a).id(), self.font.glyph(b).id());
+        self.font.pair_kerning(self.scale(), a, b).round() as i32
+    }
+
+    /// Distance in pixels the glyphs are raised above the baseline.
+    pub(crate) fn glyph_shift(&self) -> f32 {
+        self.baseline_shift as f32 + self.vertical_position_metrics().1
+    }
+
+    /// Size of the glyphs relative to the font size and distance in pixels they are raised, from
+    /// the OS/2 superscript and subscript metrics of the main font.
+    ///
+    /// Fonts without them get glyphs of 65% of the size, raised by 35% of the em or lowered by
+    /// 15% of it.
+    fn vertical_position_metrics(&self) -> (f32, f32) {
+        let face = face(&self.font);
+        let (metrics, fallback) = match self.vertical_position {
+            VerticalPosition::Normal => return (1.0, 0.0),
+            VerticalPosition::Superscript => (face.superscript_metrics(), 0.35),
+            VerticalPosition::Subscript => (face.subscript_metrics(), -0.15),
+        };
+        let units_per_em = face.units_per_em() as f32;
+        let pixels_per_unit = self.font.scale_for_pixel_height(self.font_size);
+        match metrics {
+            Some(metrics) if metrics.y_size > 0 && units_per_em > 0.0 => {
+                let offset = metrics.y_offset as f32 * pixels_per_unit;
+                // subscript offsets are positive downwards
+                let raise = match self.vertical_position {
+                    VerticalPosition::Subscript => -offset,
+                    _ => offset,
+                };
+                (metrics.y_size as f32 / units_per_em, raise)
+            }
+            _ => (0.65, fallback * units_per_em * pixels_per_unit),
+        }
+    }
+
+    /// Position of the first tab stop after `caret`.
+    fn next_tab_stop(&self, caret: f32, scale: Scale) -> f32 {
+        let size = match self.tab_size {
+            TabSize::Spaces(n) => {
+                let space = match self.fixed_advance {
+                    Some(cell) => cell as f32,
+                    None => self.font.glyph(' ').scaled(scale).h_metrics().advance_width,
+                };
+                n as f32 * space
+            }
+            TabSize::Pixels(n) => n as f32,
+        };
+        if size <= 0.0 {
+            return caret;
+        }
+        ((caret / size).floor() + 1.0) * size
+    }
+
+    /// Font used for a cluster: the run font, unless it lacks the first character of the
+    /// cluster and a fallback font has it.
+    fn cluster_font<'a>(&'a self, run_font: &'a Font<'static>, cluster: &str) -> &'a Font<'static> {
+        let first = cluster.chars().next();
+        let first = match first.and_then(|c| self.control_characters.apply(c)) {
+            Some(c) => c,
+            None => return run_font,
+        };
+        if run_font.glyph(first).id().0 != 0 {
+            return run_font;
+        }
+        let font = core::iter::once(&self.font)
+            .chain(self.fallback_fonts.iter())
+            .find(|font| font.glyph(first).id().0 != 0);
+        if font.is_none() {
+            debug!("no font has a glyph for {:?}", first);
+        }
+        font.unwrap_or(run_font)
+    }
+
+    /// Vertical origin (distance from the top of the glyph cell to its baseline) and vertical
+    /// advance of a glyph, in pixels.
+    fn vertical_metrics(&self, font: &Font<'static>, id: GlyphId, scale: Scale) -> (f32, f32) {
+        let face = face(font);
+        let id = owned_ttf_parser::GlyphId(id.0);
+        let units = font.scale_for_pixel_height(scale.y);
+        let advance = match face.glyph_ver_advance(id) {
+            Some(advance) => advance as f32 * units,
+            None => self.font_size,
+        };
+        let side_bearing_origin = || {
+            let top = face.glyph_ver_side_bearing(id)?;
+            let bounding_box = face.glyph_bounding_box(id)?;
+            Some(top + bounding_box.y_max)
+        };
+        let origin = face
+            .glyph_y_origin(id)
+            .or_else(side_bearing_origin)
+            .or_else(|| face.vertical_ascender())
+            .unwrap_or_else(|| face.ascender());
+        (origin as f32 * units, advance)
+    }
+
+    /// Find the font that renders the most characters of a run, preferring the main font.
+    fn run_font(&self, run: &str) -> &Font<'static> {
+        if self.fallback_fonts.is_empty() {
+            return &self.font;
+        }
+        let covered = |font: &Font<'static>| {
+            run.chars()
+                .filter(|c| !c.is_whitespace() && font.glyph(*c).id().0 != 0)
+                .count()
+        };
+        let mut best = (&self.font, covered(&self.font), None);
+        for (i, font) in self.fallback_fonts.iter().enumerate() {
+            let count = covered(font);
+            if count > best.1 {
+                best = (font, count, Some(i));
+            }
+        }
+        if let Some(i) = best.2 {
+            debug!("using fallback font {} for {:?}", i, run);
+        }
+        best.0
+    }
+}
+
+/// Advance in pixels of the widest digit of `font`.
+fn digit_advance(font: &Font<'static>, scale: Scale) -> f32 {
+    ('0'..='9')
+        .map(|digit| font.glyph(digit).scaled(scale).h_metrics().advance_width)
+        .fold(0.0, f32::max)
+}
+
+/// Advance in pixels of a space character, from its typographic width.
+fn space_advance(font: &Font<'static>, c: char, scale: Scale) -> Option<f32> {
+    // width of an em, glyphs may be condensed or stretched
+    let em = font.units_per_em() as f32 * font.scale_for_pixel_height(scale.y) * scale.x / scale.y;
+    let advance_of = |c: char| font.glyph(c).scaled(scale).h_metrics().advance_width;
+    Some(match c {
+        '\u{A0}' => advance_of(' '),
+        '\u{2000}' | '\u{2002}' => em / 2.0,
+        '\u{2001}' | '\u{2003}' | '\u{3000}' => em,
+        '\u{2004}' => em / 3.0,
+        '\u{2005}' => em / 4.0,
+        '\u{2006}' => em / 6.0,
+        '\u{2007}' => advance_of('0'),
+        '\u{2008}' => advance_of('.'),
+        '\u{2009}' | '\u{202F}' => em / 5.0,
+        '\u{200A}' => em / 10.0,
+        '\u{205F}' => em * 4.0 / 18.0,
+        _ => return None,
+    })
+}
@@ -13,8 +13,12 @@
 //! This style can then be directly used with embedded graphics' [`Text`] struct.
 //!
 //! ```
-//! let mut display: SimulatorDisplay<Rgb565> = SimulatorDisplay::new(Size::new(350, 200));
-//!
+//! # use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb565, prelude::*, text::Text};
+//! # use embedded_ttf::FontTextStyleBuilder;
+//! # use rusttype::Font;
+//! # let mut display: MockDisplay<Rgb565> = MockDisplay::new();
+//! # display.set_allow_out_of_bounds_drawing(true);
+//! # display.set_allow_overdraw(true);
 //! let style = FontTextStyleBuilder::new(
 //!     Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap())
 //!     .font_size(16)
@@ -22,8 +26,15 @@
 //!     .build();
 //!
 //! Text::new("Hello World!", Point::new(15, 30), style).draw(&mut display)?;
+//! # Ok::<(), core::convert::Infallible>(())
 //! ```
 //!
+//! # Multi-line text
+//!
+//! A [`WrappedText`] breaks text into lines fitting a width, and lays out its paragraphs with
+//! spacing, indentation and a maximum line count set by a [`TextBoxStyle`]. A [`TextBox`] lays
+//! out its lines the same way, without wrapping them.
+//!
 //! # Antialiasing
 //!
 //! TrueType fonts are much nicer with antialiasing. However, embedded_graphics does not support
@@ -31,7 +42,29 @@
 //!
 //! If you have a background color, the color is known and antialiasing is applied.
 //! Otherwise, you can use the [`AntiAliasing`] enum either to disable antialiasing or to define
-//! an antialiasing background color.
+//! an antialiasing background color. Targets implementing [`ReadPixel`] can also be blended with
+//! their actual pixels. On color LCD panels, [`AntiAliasing::Subpixel`] gives each color stripe
+//! of the pixels its own coverage.
+//!
+//! To compose text yourself, [`FontTextStyle::draw_coverage`] draws the coverage of the glyphs to
+//! a grayscale target instead.
+//!
+//! Text can be drawn in any embedded-graphics color type, including `BinaryColor` and gray
+//! levels, and in custom color types implementing [`ColorBlend`]. Custom colors convertible to
+//! and from `Rgb888` only need an empty [`BlendViaRgb888`] implementation. Custom colors with an
+//! alpha channel can implement [`AlphaPixelColor`] to receive the coverage as alpha instead.
+//!
+//! On palettized displays, a [`Palette`] maps the blended colors to the closest color the display
+//! can show, with optional dithering.
+//!
+//! # Glyph cache
+//!
+//! Rasterizing glyphs is expensive, a [`GlyphCache`] can be attached to any number of styles
+//! with [`FontTextStyleBuilder::glyph_cache`] to keep recently drawn glyphs.
+//!
+//! With the `allocator-api2` feature, the cached coverage and the pixel buffers of a style can be
+//! allocated from a dedicated memory pool, see `GlyphCache::new_in` and
+//! `FontTextStyleBuilder::allocator`.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -39,6 +72,7 @@
 extern crate alloc;
 
 #[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
 use num_traits::float::FloatCore;
 
 #[cfg(feature = "std")]
@@ -52,9 +86,86 @@ mod stdlib {
 
 use stdlib::{f32, vec::Vec};
 
+#[macro_use]
+mod diagnostics;
+
+mod align;
+mod allocator;
+mod arc;
+mod background;
+mod batch;
+mod bold;
+mod cache;
+mod cluster;
+mod color;
+mod coverage;
+mod cursor;
+mod effects;
+mod fill;
+mod fit;
+mod glyphs;
+mod grid;
+#[cfg(feature = "opentype-layout")]
+mod gsub;
+#[cfg(feature = "hinting")]
+mod hinting;
+mod hit;
+mod ink;
+mod layout;
+mod locale;
+mod markup;
+mod metrics;
+mod mixed;
+mod progress;
+mod read_back;
+#[cfg(feature = "std")]
+mod reload;
+mod rotation;
+mod runs;
+mod script;
+mod scrolling;
+mod stats;
+mod subpixel;
+mod text_box;
+mod text_layout;
+mod transform;
+mod truncate;
+mod wrap;
+
+pub use align::{HorizontalAlign, VerticalAlign};
+#[cfg(feature = "allocator-api2")]
+pub use allocator::AllocatorRef;
+pub use background::{BackgroundArea, Padding};
+pub use cache::{CoverageDepth, EvictionPolicy, GlyphCache, SharedGlyphCache};
+pub use cluster::clusters;
+pub use color::{AlphaPixelColor, BlendViaRgb888, ColorBlend, Gamma, Palette};
+pub use cursor::TextCursor;
+pub use effects::{Glow, Outline, Shadow};
+pub use fill::{GradientDirection, TextFill};
+pub use glyphs::GlyphInfo;
+pub use grid::BaselineGrid;
+pub use locale::{Locale, TextTransform};
+pub use markup::MarkupText;
+pub use metrics::{FontMetrics, MetricsOverride, MetricsSource};
+pub use mixed::MixedText;
+pub use progress::DrawStatus;
+pub use read_back::ReadPixel;
+#[cfg(feature = "std")]
+pub use reload::FontWatcher;
+pub use rotation::Rotation;
+pub use runs::Run;
+pub use scrolling::ScrollingText;
+#[cfg(feature = "stats")]
+pub use stats::{global_stats, RenderStats, SharedRenderStats, StatsSnapshot};
+pub use subpixel::SubpixelOrder;
+pub use text_box::{ParagraphBreak, TextBox, TextBoxStyle, TextBoxStyleBuilder, VerticalOverflow};
+pub use text_layout::TextLayout;
+pub use transform::AffineTransform;
+pub use wrap::{WrapMetrics, WrappedText};
+
 use embedded_graphics::{
     draw_target::DrawTarget,
-    pixelcolor::Rgb888,
+    image::GetPixel,
     prelude::*,
     primitives::Rectangle,
     text::{
@@ -63,7 +174,15 @@ use embedded_graphics::{
     },
 };
 
-use rusttype::Font;
+use core::{
+    borrow::Borrow,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use layout::{LineExtent, LineLayout};
+use progress::Progress;
+use rotation::RotatedTarget;
+use rusttype::{Font, PositionedGlyph};
+use stats::Counter;
 
 /// Antialiasing can be challenging with embedded graphics since the background pixel is not known
 /// during the drawing process.
@@ -77,6 +196,148 @@ pub enum AntiAliasing<C> {
     SolidColor(C),
     /// Replace the alpha channel with a simple transparency (cutoff at 50%), choose this if you don't know the background at all.
     None,
+    /// Blend with the pixels already on the target, choose this to draw over images or gradients.
+    ///
+    /// Only drawing with [`FontTextStyle::draw_string_read_back`] can read the target, the pixels
+    /// are read before the line is drawn. The background color is used when defined, like
+    /// BackgroundColor, and so is any drawing that can't read the target.
+    ReadBack,
+    /// Replace the alpha channel with an ordered dithering pattern of the text color, choose this
+    /// on monochrome or low bit depth displays, like e-paper or 1-bit OLEDs.
+    Dithering,
+    /// Let a function choose the color of each pixel touched by a glyph from its coverage and
+    /// position, `None` leaves the pixel untouched. Glyphs are drawn when the style has a text
+    /// color, which the function replaces.
+    Custom(fn(u8, Point) -> Option<C>),
+    /// Blend each color channel with the coverage of its stripe of the pixel, choose this for
+    /// small text on color LCD panels whose stripe order is known.
+    ///
+    /// Glyphs are rasterized at three times the horizontal resolution, along the lines of text.
+    /// Blends with the background color like BackgroundColor, colors without red, green and blue
+    /// channels blend with the mean coverage of the stripes.
+    Subpixel(SubpixelOrder),
+    /// Keep the color and scale its alpha channel by the coverage, choose this on targets with
+    /// alpha, like the layers of a compositor, which blend the text correctly themselves.
+    ///
+    /// The function is [`AlphaPixelColor::scale_alpha`], set with
+    /// [`FontTextStyleBuilder::anti_aliasing_alpha`]. Uncovered pixels are not drawn.
+    Alpha(fn(C, u8) -> C),
+}
+
+/// Direction in which glyphs of a line follow each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WritingMode {
+    /// Glyphs are laid out from left to right (default).
+    #[default]
+    Horizontal,
+    /// Glyphs are stacked from top to bottom, in a column `font_size` pixels wide.
+    ///
+    /// Vertical advances and origins come from the font's `vhea`, `vmtx` and `VORG` tables when
+    /// present, otherwise each glyph advances by `font_size`. Glyphs are kept upright and centered
+    /// in the column. With the `opentype-layout` feature, the font's vertical forms (`vert`) are
+    /// used, which rotates punctuation for traditional CJK text. Decorations run along the
+    /// column, the underline on its right side.
+    Vertical,
+}
+
+/// Synthetic metrics for characters missing from all fonts.
+///
+/// Some fonts have a `.notdef` glyph without advance or without outline, missing characters then
+/// collapse the layout or are invisible. With synthetic metrics, such a glyph advances by
+/// `advance` and, when it has no outline, is drawn as a hollow box `height` tall standing on the
+/// baseline. Both values are a fraction of the em size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NotdefMetrics {
+    /// Advance of a missing glyph, in ems.
+    pub advance: f32,
+    /// Height of the box drawn for a missing glyph, in ems.
+    pub height: f32,
+}
+
+impl Default for NotdefMetrics {
+    fn default() -> Self {
+        NotdefMetrics {
+            advance: 0.5,
+            height: 0.7,
+        }
+    }
+}
+
+/// Distance between two tab stops.
+///
+/// A tab character advances to the next stop, stops are measured from the start of the string
+/// being drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabSize {
+    /// Stops every given number of space widths of the main font.
+    Spaces(u32),
+    /// Stops every given number of pixels.
+    Pixels(u32),
+}
+
+impl Default for TabSize {
+    fn default() -> Self {
+        TabSize::Spaces(8)
+    }
+}
+
+/// Measurement of whitespace at the end of a line.
+///
+/// `draw_whitespace` is not affected: it draws whitespace placed by the caller, between words,
+/// with the background and decorations `draw_string` draws for whitespace of the same advance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingWhitespace {
+    /// Trailing whitespace advances the position and is covered by the background and
+    /// decorations like any other character (default).
+    #[default]
+    Include,
+    /// Trailing whitespace is ignored by `draw_string` and `measure_string`: the bounding box and
+    /// the next position end with the last visible character.
+    Exclude,
+}
+
+/// Rendering of control characters such as `\r` or escape.
+///
+/// Tabs are always laid out with the tab stops, and newlines split the text in multiline mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlCharacters {
+    /// Control characters are mapped to the font like any other character, usually to the
+    /// `.notdef` glyph (default).
+    #[default]
+    Notdef,
+    /// Control characters are not drawn and don't advance the position.
+    Skip,
+    /// Control characters are drawn as the given character, for instance U+FFFD.
+    Replacement(char),
+}
+
+impl ControlCharacters {
+    /// Character drawn for `c`, if any.
+    pub(crate) fn apply(self, c: char) -> Option<char> {
+        if !c.is_control() || c == '\t' {
+            return Some(c);
+        }
+        match self {
+            ControlCharacters::Notdef => Some(c),
+            ControlCharacters::Skip => None,
+            ControlCharacters::Replacement(replacement) => Some(replacement),
+        }
+    }
+}
+
+/// Size and position of the glyphs relative to the baseline.
+///
+/// Superscripts and subscripts keep the line box, the background and the decorations of the
+/// font size, so they can follow normal text in a [`MixedText`] of the same size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalPosition {
+    /// Glyphs of the font size standing on the baseline (default).
+    #[default]
+    Normal,
+    /// Smaller glyphs raised above the baseline, like the exponent of m².
+    Superscript,
+    /// Smaller glyphs lowered below the baseline, like the digits of chemical formulas.
+    Subscript,
 }
 
 /// Style properties for text using a ttf and otf font.
@@ -91,6 +352,15 @@ pub struct FontTextStyle<C> {
     /// Background color.
     pub background_color: Option<C>,
 
+    /// Space added around each line by the background.
+    pub background_padding: Padding,
+
+    /// Radius in pixels of the corners of the background of each line, 0 for square corners.
+    pub background_corner_radius: u32,
+
+    /// Area filled by the background of each line.
+    pub background_area: BackgroundArea,
+
     /// How to apply antialiasing.
     pub anti_aliasing: AntiAliasing<C>,
 
@@ -100,11 +370,141 @@ pub struct FontTextStyle<C> {
     /// Strikethrough color.
     pub strikethrough_color: DecorationColor<C>,
 
-    /// Font size.
-    pub font_size: u32,
+    /// Font size in pixels, lines are laid out on whole pixels.
+    pub font_size: f32,
+
+    /// Case transformation applied before layout.
+    pub text_transform: TextTransform,
+
+    /// Language of the text.
+    pub locale: Option<Locale>,
+
+    /// Direction of the text.
+    pub writing_mode: WritingMode,
+
+    /// Quarter turns of the whole text around its position.
+    pub rotation: Rotation,
+
+    /// Area of the target outside which nothing is drawn, in the coordinates of the target.
+    pub clip: Option<Rectangle>,
+
+    /// Transform applied to the glyphs, around the start of the baseline of their line.
+    pub transform: Option<AffineTransform>,
+
+    /// Slant in degrees of synthetic italic, measured boxes are widened to the slanted glyphs.
+    /// The shear itself is part of `transform`.
+    pub faux_italic: f32,
+
+    /// Font table used for ascent, descent and line gap.
+    pub metrics_source: MetricsSource,
+
+    /// Replacement values for the font's ascent, descent and line gap.
+    pub metrics_override: MetricsOverride,
+
+    /// Width of the glyphs relative to their height, below 1 the text is condensed.
+    pub horizontal_scale: f32,
+
+    /// Distance in pixels the glyphs are raised above the baseline, negative values lower them.
+    pub baseline_shift: i32,
+
+    /// Superscript or subscript glyphs.
+    pub vertical_position: VerticalPosition,
+
+    /// Distance between the tops of two lines in pixels, derived from the vertical metrics if
+    /// `None`.
+    pub line_height: Option<u32>,
+
+    /// Factor applied to the line height derived from the vertical metrics.
+    pub line_spacing: f32,
+
+    /// Distance between tab stops.
+    pub tab_size: TabSize,
+
+    /// Measurement of whitespace at the end of lines.
+    pub trailing_whitespace: TrailingWhitespace,
+
+    /// Rendering of control characters.
+    pub control_characters: ControlCharacters,
+
+    /// Apply the kerning pairs of the font.
+    pub kerning: bool,
+
+    /// Give all digits the advance of the widest one.
+    pub tabular_digits: bool,
+
+    /// Advance in pixels of every cluster of horizontal text, `None` for the font's advances.
+    pub fixed_advance: Option<u32>,
+
+    /// Extra advance in pixels between clusters, negative values tighten the text.
+    pub letter_spacing: i32,
+
+    /// Extra advance in pixels after whitespace characters, negative values tighten the text.
+    pub word_spacing: i32,
+
+    /// Width in pixels added to the strokes and the advance of each glyph, synthetic bold.
+    pub faux_bold: u32,
+
+    /// Split the text of `draw_string` and `measure_string` into lines at `\n`.
+    pub multiline: bool,
+
+    /// Synthetic metrics of missing glyphs, the font's `.notdef` is used as is if `None`.
+    pub notdef_metrics: Option<NotdefMetrics>,
+
+    /// Draw the background in the text color and the glyphs in the background color.
+    pub inverse: bool,
+
+    /// Shadow drawn behind the text.
+    pub shadow: Option<Shadow<C>>,
+
+    /// Outline drawn around the glyphs.
+    pub outline: Option<Outline<C>>,
+
+    /// Halo drawn behind the text, above the shadow.
+    pub glow: Option<Glow<C>>,
+
+    /// Colors of a palettized target, blended colors are mapped to the closest entry.
+    pub palette: Option<Palette<C>>,
+
+    /// Opacity of the text, from 0 for invisible to 255 (default) for opaque.
+    ///
+    /// The coverage of glyphs, effects and decorations is scaled by the opacity before blending.
+    pub opacity: u8,
+
+    /// Number of coverage levels blended, `None` for all 256.
+    pub coverage_levels: Option<u8>,
+
+    /// Gamma of the target, colors are blended in linear light when set.
+    pub gamma: Option<Gamma>,
+
+    /// Colors of the glyphs, replacing the text color when set.
+    pub fill: Option<TextFill<C>>,
+
+    /// Send the pixels of each line as horizontal spans with `fill_contiguous`, instead of a
+    /// single `draw_iter`.
+    pub contiguous_rows: bool,
 
     /// Font from rusttype.
     font: Font<'static>,
+
+    /// Fonts used for runs of text the main font cannot render.
+    fallback_fonts: Vec<Font<'static>>,
+
+    /// Optional cache of rasterized glyphs.
+    glyph_cache: Option<SharedGlyphCache>,
+
+    /// Allocator of the pixel buffers.
+    allocator: allocator::Alloc,
+
+    /// Statistics updated when drawing, in addition to the global ones.
+    #[cfg(feature = "stats")]
+    stats: Option<SharedRenderStats>,
+
+    /// Whether glyph outlines are fitted to the pixel grid.
+    #[cfg(feature = "hinting")]
+    hinting: bool,
+
+    /// Callback reporting the progress of long draws.
+    progress: Option<Progress>,
 }
 
 impl<C: PixelColor> FontTextStyle<C> {
@@ -116,15 +516,207 @@ impl<C: PixelColor> FontTextStyle<C> {
             .build()
     }
 
+    /// The main font of the style.
+    pub fn font(&self) -> &Font<'static> {
+        &self.font
+    }
+
+    /// Replace the main font of the style.
+    ///
+    /// The glyphs of the previous font are removed from the glyph cache of the style.
+    pub fn set_font(&mut self, font: Font<'static>) {
+        if let Some(cache) = &self.glyph_cache {
+            cache.forget_font(&self.font);
+        }
+        self.font = font;
+    }
+
+    /// Text and background colors, swapped in inverse video.
+    pub(crate) fn colors(&self) -> (Option<C>, Option<C>) {
+        if self.inverse {
+            (self.background_color, self.text_color)
+        } else {
+            (self.text_color, self.background_color)
+        }
+    }
+
+    /// Font size rounded up to whole pixels, the width of a vertical column.
+    pub(crate) fn pixel_size(&self) -> u32 {
+        self.font_size.ceil() as u32
+    }
+
+    /// Height of a horizontal line box, from the ascent to the descent rounded up to whole
+    /// pixels.
+    pub(crate) fn band_height(&self) -> u32 {
+        let v_metrics = self.v_metrics();
+        (v_metrics.ascent - v_metrics.descent).ceil().max(0.0) as u32
+    }
+
+    /// Distance from the top of a line to `baseline`, as in the mono fonts of embedded-graphics.
+    ///
+    /// Vertical text is always positioned by its top.
+    fn baseline_offset(&self, baseline: Baseline) -> i32 {
+        if self.writing_mode == WritingMode::Vertical {
+            return 0;
+        }
+        let height = self.band_height().saturating_sub(1) as i32;
+        match baseline {
+            Baseline::Top => 0,
+            Baseline::Bottom => height,
+            Baseline::Middle => height / 2,
+            Baseline::Alphabetic => self.v_metrics().ascent.round() as i32,
+        }
+    }
+
     /// Resolves a decoration color.
     fn resolve_decoration_color(&self, color: DecorationColor<C>) -> Option<C> {
         match color {
             DecorationColor::None => None,
-            DecorationColor::TextColor => self.text_color,
+            DecorationColor::TextColor => self.colors().0,
             DecorationColor::Custom(c) => Some(c),
         }
     }
 
+    /// Transform of the glyphs relative to the layout origin, around the start of the baseline.
+    fn glyph_transform(&self) -> Option<AffineTransform> {
+        let transform = self.transform?;
+        let baseline = self.v_metrics().ascent;
+        Some(
+            AffineTransform::translation(0.0, -baseline)
+                .then(&transform)
+                .then(&AffineTransform::translation(0.0, baseline)),
+        )
+    }
+
+    /// Transform of the outline of a glyph to the layout space.
+    pub(crate) fn outline_transform(
+        &self,
+        glyph: &rusttype::PositionedGlyph<'_>,
+    ) -> AffineTransform {
+        let position = glyph.position();
+        let translation = AffineTransform::translation(position.x, position.y);
+        match self.glyph_transform() {
+            Some(transform) => translation.then(&transform),
+            None => translation,
+        }
+    }
+
+    /// Whether glyph outlines are fitted to the pixel grid.
+    #[cfg(feature = "hinting")]
+    fn hinted(&self) -> bool {
+        self.hinting
+    }
+
+    /// Whether glyph outlines are fitted to the pixel grid.
+    #[cfg(not(feature = "hinting"))]
+    fn hinted(&self) -> bool {
+        false
+    }
+
+    /// Call `f(x, y, coverage)` for each pixel of a glyph rasterized from its outline with
+    /// `transform`, fitted to the pixel grid when hinting.
+    pub(crate) fn outline_coverage(
+        &self,
+        glyph: &rusttype::PositionedGlyph<'_>,
+        transform: &AffineTransform,
+        f: impl FnMut(i32, i32, u8),
+    ) {
+        #[cfg(feature = "hinting")]
+        if self.hinting {
+            let fit = self.grid_fit(glyph.position().y);
+            return transform::fitted_coverage(
+                glyph.unpositioned(),
+                |y| fit.apply(y),
+                transform,
+                f,
+            );
+        }
+        transform::transformed_coverage(glyph.unpositioned(), transform, f)
+    }
+
+    /// Pixels a glyph may cover, relative to the layout origin.
+    pub(crate) fn glyph_bounds(
+        &self,
+        glyph: &rusttype::PositionedGlyph<'_>,
+    ) -> Option<rusttype::Rect<i32>> {
+        let mut bounds = glyph.pixel_bounding_box()?;
+        if self.hinted() {
+            // fitted edges move by up to half a pixel
+            bounds.min.y -= 1;
+            bounds.max.y += 1;
+        }
+        if let Some(transform) = self.glyph_transform() {
+            let (min, max) = (bounds.min, bounds.max);
+            let corners = [
+                (min.x, min.y),
+                (max.x, min.y),
+                (min.x, max.y),
+                (max.x, max.y),
+            ]
+            .map(|(x, y)| transform.apply(x as f32, y as f32));
+            let (mut left, mut top, mut right, mut bottom) =
+                (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+            for (x, y) in corners {
+                (left, top) = (left.min(x), top.min(y));
+                (right, bottom) = (right.max(x), bottom.max(y));
+            }
+            bounds = rusttype::Rect {
+                min: rusttype::point(left.floor() as i32, top.floor() as i32),
+                max: rusttype::point(right.ceil() as i32, bottom.ceil() as i32),
+            };
+        }
+        // synthetic bold widens the glyphs to the right
+        bounds.max.x += self.faux_bold as i32;
+        Some(bounds)
+    }
+
+    /// Call `f(x, y, coverage)` for each pixel of a glyph, relative to the layout origin.
+    fn glyph_coverage(&self, glyph: &rusttype::PositionedGlyph<'_>, f: impl FnMut(i32, i32, u8)) {
+        match self.faux_bold {
+            0 => self.plain_coverage(glyph, f),
+            _ => self.bold_coverage(glyph, f),
+        }
+    }
+
+    /// Call `f(x, y, coverage)` for each pixel of a glyph, without synthetic bold.
+    fn plain_coverage(
+        &self,
+        glyph: &rusttype::PositionedGlyph<'_>,
+        mut f: impl FnMut(i32, i32, u8),
+    ) {
+        if self.transform.is_some() || self.hinted() {
+            if glyph.pixel_bounding_box().is_some() {
+                self.count(Counter::GlyphsRasterized, 1);
+            }
+            self.outline_coverage(glyph, &self.outline_transform(glyph), f);
+            return;
+        }
+        match &self.glyph_cache {
+            Some(cache) => {
+                if cache.with_coverage(glyph, self.font_size, f) {
+                    self.count(Counter::CacheHits, 1);
+                } else {
+                    self.count(Counter::CacheMisses, 1);
+                    if glyph.pixel_bounding_box().is_some() {
+                        self.count(Counter::GlyphsRasterized, 1);
+                    }
+                }
+            }
+            None => {
+                if let Some(bb) = glyph.pixel_bounding_box() {
+                    self.count(Counter::GlyphsRasterized, 1);
+                    glyph.draw(|x, y, v| {
+                        f(
+                            x as i32 + bb.min.x,
+                            y as i32 + bb.min.y,
+                            (v * 255.0).min(255.0) as u8,
+                        )
+                    });
+                }
+            }
+        }
+    }
+
     fn draw_background<D>(
         &self,
         width: u32,
@@ -134,13 +726,15 @@ impl<C: PixelColor> FontTextStyle<C> {
     where
         D: DrawTarget<Color = C>,
     {
-        if width == 0 {
+        // whitespace has no glyphs
+        if width == 0 || self.background_area == BackgroundArea::Glyphs {
             return Ok(());
         }
 
-        if let Some(background_color) = self.background_color {
+        if let Some(background_color) = self.colors().1 {
+            let area = Rectangle::new(position, Size::new(width, self.band_height()));
             target.fill_solid(
-                &Rectangle::new(position, Size::new(width, self.font_size)),
+                &self.background_padding.above_and_below(&area),
                 background_color,
             )?;
         }
@@ -148,39 +742,59 @@ impl<C: PixelColor> FontTextStyle<C> {
         Ok(())
     }
 
-    fn draw_strikethrough<D>(
-        &self,
-        width: u32,
-        position: Point,
-        target: &mut D,
-    ) -> Result<(), D::Error>
-    where
-        D: DrawTarget<Color = C>,
-    {
-        if let Some(strikethrough_color) = self.resolve_decoration_color(self.strikethrough_color) {
-            let top_left = position + Point::new(0, self.font_size as i32 / 2);
-            // small strikethrough width
-            let size = Size::new(width, self.font_size / 30 + 1);
-
-            target.fill_solid(&Rectangle::new(top_left, size), strikethrough_color)?;
+    /// Offset from the top of the line and thickness of the strikethrough and the underline.
+    ///
+    /// Horizontal decorations are placed from the baseline with the font's `OS/2` strikeout and
+    /// `post` underline metrics, thin lines at half and at the full font size otherwise.
+    fn decoration_metrics(&self) -> [(i32, u32); 2] {
+        // small decoration width
+        let default_thickness = (self.font_size / 30.0) as u32 + 1;
+        let half = ((self.font_size / 2.0) as i32, default_thickness);
+        let full = (self.font_size as i32, default_thickness);
+        if self.writing_mode == WritingMode::Vertical {
+            return [half, full];
         }
 
-        Ok(())
+        let face = layout::face(&self.font);
+        let scale = self.font.scale_for_pixel_height(self.font_size);
+        let baseline = self.v_metrics().ascent;
+        // positions are the top of the line, upwards from the baseline
+        let placed = |metrics: Option<owned_ttf_parser::LineMetrics>, default| match metrics {
+            Some(metrics) if metrics.thickness > 0 => (
+                (baseline - f32::from(metrics.position) * scale).round() as i32,
+                ((f32::from(metrics.thickness) * scale).round() as u32).max(1),
+            ),
+            _ => default,
+        };
+        [
+            placed(face.strikeout_metrics(), half),
+            placed(face.underline_metrics(), full),
+        ]
     }
 
-    fn draw_underline<D>(&self, width: u32, position: Point, target: &mut D) -> Result<(), D::Error>
-    where
-        D: DrawTarget<Color = C>,
-    {
-        if let Some(underline_color) = self.resolve_decoration_color(self.underline_color) {
-            let top_left = position + Point::new(0, self.font_size as i32);
-            // small underline width
-            let size = Size::new(width, self.font_size / 30 + 1);
-
-            target.fill_solid(&Rectangle::new(top_left, size), underline_color)?;
-        }
-
-        Ok(())
+    /// Strikethrough and underline of a line box, in drawing order.
+    ///
+    /// In vertical writing mode the decorations run along the column, the strikethrough through
+    /// its middle and the underline on its right side, as usual for CJK text.
+    fn decorations(&self, line: Rectangle) -> impl Iterator<Item = (Rectangle, C)> {
+        let decoration = |(offset, thickness): (i32, u32)| match self.writing_mode {
+            WritingMode::Horizontal => rotation::translate(
+                &Rectangle::new(Point::zero(), Size::new(line.size.width, thickness)),
+                rotation::offset(line.top_left, Point::new(0, offset)),
+            ),
+            WritingMode::Vertical => rotation::translate(
+                &Rectangle::new(Point::zero(), Size::new(thickness, line.size.height)),
+                rotation::offset(line.top_left, Point::new(offset, 0)),
+            ),
+        };
+        let [strikethrough, underline] = self.decoration_metrics();
+        let strikethrough = self
+            .resolve_decoration_color(self.strikethrough_color)
+            .map(|color| (decoration(strikethrough), color));
+        let underline = self
+            .resolve_decoration_color(self.underline_color)
+            .map(|color| (decoration(underline), color));
+        strikethrough.into_iter().chain(underline)
     }
 }
 
@@ -208,188 +822,881 @@ impl<C: PixelColor> CharacterStyle for FontTextStyle<C> {
     }
 }
 
-impl<C> TextRenderer for FontTextStyle<C>
+impl<C> FontTextStyle<C>
 where
-    C: PixelColor + Into<Rgb888> + From<Rgb888> + stdlib::fmt::Debug,
+    C: ColorBlend + stdlib::fmt::Debug,
 {
-    type Color = C;
+    /// Draw glyphs laid out by the caller with the colors and antialiasing of this style.
+    ///
+    /// Glyph positions are offsets from `position`. This allows custom rusttype layouts to reuse
+    /// the blending of this crate. Backgrounds and decorations are not drawn.
+    pub fn draw_positioned_glyphs<D>(
+        &self,
+        glyphs: &[PositionedGlyph<'_>],
+        position: Point,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let (pixels, _) = self.glyph_pixels(glyphs, 0, position, None, |_| None);
+        self.count(Counter::DrawCalls, 1);
+        self.count(Counter::PixelsEmitted, pixels.len() as u32);
+        self.draw_pixels(pixels, &mut self.clipped_target(target))
+    }
 
-    fn draw_string<D>(
+    /// Draw a line of text scrolled horizontally under `viewport`.
+    ///
+    /// The text starts `scroll_x` pixels left of the viewport, at its top, and is clipped at all
+    /// edges of the viewport. Changing `scroll_x` pans the text without changing its layout, with
+    /// a glyph cache the glyphs are only rasterized once.
+    ///
+    /// Returns the position following the last character, which may be outside the viewport.
+    pub fn draw_string_scrolled<D>(
         &self,
         text: &str,
-        position: Point,
-        _baseline: Baseline,
+        viewport: &Rectangle,
+        scroll_x: i32,
         target: &mut D,
     ) -> Result<Point, D::Error>
     where
-        D: DrawTarget<Color = Self::Color>,
+        D: DrawTarget<Color = C>,
     {
-        let scale = rusttype::Scale::uniform(self.font_size as f32);
-
-        let v_metrics = self.font.v_metrics(scale);
-        let offset = rusttype::point(0.0, v_metrics.ascent);
-
-        let glyphs: Vec<rusttype::PositionedGlyph> =
-            self.font.layout(text, scale, offset).collect();
+        let position = viewport.top_left - Point::new(scroll_x, 0);
+        self.draw_string(text, position, Baseline::Top, &mut target.clipped(viewport))
+    }
 
-        let width = glyphs
-            .iter()
-            .rev()
-            .filter_map(|g| {
-                g.pixel_bounding_box()
-                    .map(|b| b.min.x as f32 + g.unpositioned().h_metrics().advance_width)
+    /// Lines of a text with their offset from the first one.
+    ///
+    /// Unless the style is multiline, the whole text is a single line. Lines follow each other
+    /// downwards, or leftwards in vertical writing mode, `line_height()` pixels apart. Trailing
+    /// whitespace is removed if the style excludes it.
+    fn lines<'t>(&self, text: &'t str) -> impl Iterator<Item = (&'t str, Point)> + 't {
+        let line_height = self.line_height() as i32;
+        let step = match self.writing_mode {
+            WritingMode::Horizontal => Point::new(0, line_height),
+            WritingMode::Vertical => Point::new(-line_height, 0),
+        };
+        let multiline = self.multiline;
+        let trailing_whitespace = self.trailing_whitespace;
+        text.split(move |c| multiline && c == '\n')
+            .map(move |line| match multiline {
+                true => line.strip_suffix('\r').unwrap_or(line),
+                false => line,
             })
-            .next()
-            .unwrap_or(0.0)
-            .ceil() as i32;
-
-        let height = self.font_size as i32;
-
-        let mut pixels = Vec::new();
-
-        if let Some(text_color) = self.text_color {
-            for g in glyphs.iter() {
-                if let Some(bb) = g.pixel_bounding_box() {
-                    g.draw(|off_x, off_y, v| {
-                        let off_x = off_x as i32 + bb.min.x;
-                        let off_y = off_y as i32 + bb.min.y;
-                        // There's still a possibility that the glyph clips the boundaries of the bitmap
-                        if off_x >= 0 && off_x < width as i32 && off_y >= 0 && off_y < height as i32
-                        {
-                            let text_a = (v * 255.0) as u32;
-
-                            let bg_color = match self.anti_aliasing {
-                                AntiAliasing::BackgroundColor => self.background_color,
-                                AntiAliasing::SolidColor(c) => Some(c),
-                                AntiAliasing::None => None,
-                            };
-                            match bg_color {
-                                None => if text_a > 127 {
-                                    pixels.push(Pixel(
-                                        Point::new(position.x + off_x, position.y + off_y),
-                                        text_color
-                                    ));
-                                }
-                                Some(color) => {
-                                    let a = text_a as u16;
-                                    let fg = text_color.into();
-                                    let bg = color.into();
-                                    // blend with background color
-                                    let new_r = (a * fg.r() as u16 + (255-a) * bg.r() as u16) / 255;
-                                    let new_g = (a * fg.g() as u16 + (255-a) * bg.g() as u16) / 255;
-                                    let new_b = (a * fg.b() as u16 + (255-a) * bg.b() as u16) / 255;
-
-                                    pixels.push(Pixel(
-                                        Point::new(position.x + off_x, position.y + off_y),
-                                        Rgb888::new(new_r as u8, new_g as u8, new_b as u8).into(),
-                                    ));
-                                }
-                            }
-                        }
-                    });
-                }
-            }
-        }
+            .map(move |line| match trailing_whitespace {
+                TrailingWhitespace::Include => line,
+                TrailingWhitespace::Exclude => line.trim_end(),
+            })
+            .zip(0..)
+            .map(move |(line, i)| (line, step * i))
+    }
 
-        self.draw_background(width as u32, position, target)?;
-        target.draw_iter(pixels)?;
-        self.draw_strikethrough(width as u32, position, target)?;
-        self.draw_underline(width as u32, position, target)?;
+    /// `target` turned by the rotation of the style around `position`, and clipped.
+    pub(crate) fn rotated_target<'a, D>(
+        &self,
+        target: &'a mut D,
+        position: Point,
+    ) -> RotatedTarget<'a, D> {
+        RotatedTarget::new(target, position, self.rotation, self.clip)
+    }
 
-        Ok(position + Point::new(width, 0))
+    /// `target` clipped, for drawing that doesn't turn with the style.
+    pub(crate) fn clipped_target<'a, D>(&self, target: &'a mut D) -> RotatedTarget<'a, D> {
+        RotatedTarget::new(target, Point::zero(), Rotation::Deg0, self.clip)
     }
 
-    fn draw_whitespace<D>(
+    /// Draw laid out lines of text with their offset from the first one, see `draw_string`.
+    ///
+    /// `backdrop` reads a pixel of the target for read-back antialiasing.
+    pub(crate) fn draw_lines<'t, L, D>(
         &self,
-        width: u32,
+        lines: impl Iterator<Item = (&'t str, L, Point)>,
         position: Point,
-        _baseline: Baseline,
+        baseline: Baseline,
+        backdrop: impl Fn(&D, Point) -> Option<C>,
         target: &mut D,
     ) -> Result<Point, D::Error>
     where
-        D: DrawTarget<Color = Self::Color>,
+        L: Borrow<LineLayout>,
+        D: DrawTarget<Color = C>,
     {
-        self.draw_background(width, position, target)?;
-        self.draw_strikethrough(width, position, target)?;
-        self.draw_underline(width, position, target)?;
-
-        Ok(position + Size::new(width, 0))
+        let offset = Point::new(0, self.baseline_offset(baseline));
+        let mut target = self.rotated_target(target, position);
+        let mut next = position;
+        for (line, layout, line_offset) in lines {
+            let line_position = rotation::offset(position, line_offset - offset);
+            let layout = layout.borrow();
+            let backdrop = |target: &RotatedTarget<'_, D>, point| {
+                target
+                    .target_point(point)
+                    .and_then(|point| backdrop(target.target(), point))
+            };
+            next =
+                match self.draw_layout(layout, line, line_position, None, backdrop, &mut target)? {
+                    DrawStatus::Complete(next) => rotation::offset(next, offset),
+                    DrawStatus::Cancelled { .. } => {
+                        unreachable!("drawing without cancellation flag")
+                    }
+                };
+        }
+        Ok(rotation::offset(
+            position,
+            self.rotation.offset(next - position),
+        ))
     }
 
-    fn measure_string(&self, text: &str, position: Point, _baseline: Baseline) -> TextMetrics {
-        let scale = rusttype::Scale::uniform(self.font_size as f32);
-        let v_metrics = self.font.v_metrics(scale);
-        let offset = rusttype::point(0.0, v_metrics.ascent);
-
-        let glyphs: Vec<rusttype::PositionedGlyph> =
-            self.font.layout(text, scale, offset).collect();
-
-        let width = glyphs
-            .iter()
-            .rev()
-            .map(|g| g.position().x as f32 + g.unpositioned().h_metrics().advance_width)
-            .next()
-            .unwrap_or(0.0)
-            .ceil() as f64;
-
-        let size = Size::new(width as u32, self.font_size);
+    /// Measure laid out lines of text with their offset from the first one, see
+    /// `measure_string`.
+    pub(crate) fn measure_lines(
+        &self,
+        lines: impl Iterator<Item = (LineExtent, Point)>,
+        position: Point,
+        baseline: Baseline,
+    ) -> TextMetrics {
+        let top_left = rotation::offset(position, Point::new(0, -self.baseline_offset(baseline)));
+        let mut bounding_box: Option<Rectangle> = None;
+        let mut next_position = position;
+        for (extent, line_offset) in lines {
+            let line_box = rotation::translate(
+                &self.extent_box(&extent),
+                rotation::offset(top_left, line_offset),
+            );
+            let line_box = self.background_padding.around(&line_box);
+            bounding_box = Some(match bounding_box {
+                Some(bounding_box) => envelope(&bounding_box, &line_box),
+                None => line_box,
+            });
+            next_position = rotation::offset(
+                rotation::offset(position, line_offset),
+                self.advance_offset(extent.advance),
+            );
+        }
 
+        let bounding_box = bounding_box.unwrap_or_else(|| Rectangle::new(top_left, Size::zero()));
         TextMetrics {
-            bounding_box: Rectangle::new(position, size),
-            next_position: position + size.x_axis(),
+            bounding_box: self.rotation.rectangle(&bounding_box, position),
+            next_position: rotation::offset(
+                position,
+                self.rotation.offset(next_position - position),
+            ),
         }
     }
 
-    fn line_height(&self) -> u32 {
-        self.font_size
+    /// Draw a line of text, checking `cancel` between glyphs.
+    pub(crate) fn draw_line<D>(
+        &self,
+        text: &str,
+        position: Point,
+        cancel: Option<&AtomicBool>,
+        target: &mut D,
+    ) -> Result<DrawStatus, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        self.draw_layout(
+            &self.layout(text),
+            text,
+            position,
+            cancel,
+            |_, _| None,
+            &mut self.clipped_target(target),
+        )
     }
-}
-
-/// Text style builder for ttf and otf fonts.
-///
-/// Use this builder to create [`FontTextStyle`]s for [`Text`].
-pub struct FontTextStyleBuilder<C: PixelColor> {
-    style: FontTextStyle<C>,
-}
 
-impl<C: PixelColor> FontTextStyleBuilder<C> {
-    /// Create a new text style builder.
-    pub fn new(font: Font<'static>) -> Self {
-        Self {
-            style: FontTextStyle {
-                font,
-                background_color: None,
-                anti_aliasing: AntiAliasing::None,
-                font_size: 12,
-                text_color: None,
-                underline_color: DecorationColor::None,
-                strikethrough_color: DecorationColor::None,
-            },
+    /// Draw a laid out line of `text`, checking `cancel` between glyphs.
+    ///
+    /// `backdrop` reads a pixel of the target for read-back antialiasing.
+    pub(crate) fn draw_layout<D>(
+        &self,
+        layout: &LineLayout,
+        text: &str,
+        position: Point,
+        cancel: Option<&AtomicBool>,
+        backdrop: impl Fn(&D, Point) -> Option<C>,
+        target: &mut D,
+    ) -> Result<DrawStatus, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let glyphs = &layout.glyphs;
+
+        let line = rotation::translate(&self.line_box(layout), position);
+        let next = rotation::offset(position, self.advance_offset(layout.advance));
+        self.count(Counter::DrawCalls, 1);
+
+        let mut pixels = self.shadow_pixels(glyphs, position);
+        pixels.extend(self.glow_pixels(glyphs, position));
+        pixels.extend(self.outline_pixels(glyphs, position));
+        let notdef = self.notdef_pixels(&layout.notdef_boxes, position);
+
+        let clipped = match cancel {
+            None => {
+                let (glyph_pixels, clipped) =
+                    self.glyph_pixels(glyphs, 0, position, Some(line), |point| {
+                        backdrop(target, point)
+                    });
+                self.count(
+                    Counter::PixelsEmitted,
+                    (glyph_pixels.len() + notdef.len()) as u32,
+                );
+                pixels.extend(glyph_pixels);
+                pixels.extend(notdef);
+                self.flush_line(line, glyphs, position, pixels, target)?;
+                clipped
+            }
+            // glyphs are drawn one by one when the draw can be cancelled
+            Some(cancel) => {
+                self.draw_line_background(&line, glyphs, position, target)?;
+                self.draw_pixels(pixels, target)?;
+                let mut clipped = 0;
+                for (i, glyph) in glyphs.iter().enumerate() {
+                    if cancel.load(Ordering::Relaxed) {
+                        return Ok(DrawStatus::Cancelled { glyphs: i });
+                    }
+                    let (pixels, glyph_clipped) = self.glyph_pixels(
+                        core::slice::from_ref(glyph),
+                        i,
+                        position,
+                        Some(line),
+                        |point| backdrop(target, point),
+                    );
+                    clipped += glyph_clipped;
+                    self.count(Counter::PixelsEmitted, pixels.len() as u32);
+                    self.draw_pixels(pixels, target)?;
+                }
+                self.count(Counter::PixelsEmitted, notdef.len() as u32);
+                self.draw_pixels(notdef, target)?;
+                self.draw_decorations(line, target)?;
+                clipped
+            }
+        };
+        if clipped > 0 {
+            debug!(
+                "{} pixels of {:?} clipped by the text bounds",
+                clipped, text
+            );
         }
-    }
 
-    /// Set the font size of the style in pixels.
-    pub fn font_size(mut self, font_size: u32) -> Self {
-        self.style.font_size = font_size;
-        self
+        Ok(DrawStatus::Complete(next))
     }
 
-    /// Enable underline using the text color.
-    pub fn underline(mut self) -> Self {
-        self.style.underline_color = DecorationColor::TextColor;
-        self
+    /// Decorations of a line box with their color faded by the opacity, see `decorations`.
+    fn visible_decorations(&self, line: Rectangle) -> impl Iterator<Item = (Rectangle, C)> + '_ {
+        self.decorations(line)
+            .filter_map(move |(area, color)| match self.opacity {
+                255 => Some((area, color)),
+                _ => self
+                    .blend(color, 255, area.top_left)
+                    .map(|color| (area, color)),
+            })
     }
 
-    /// Enable strikethrough using the text color.
-    pub fn strikethrough(mut self) -> Self {
-        self.style.strikethrough_color = DecorationColor::TextColor;
-        self
+    fn draw_decorations<D>(&self, line: Rectangle, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        for (area, color) in self.visible_decorations(line) {
+            target.fill_solid(&area, color)?;
+        }
+
+        Ok(())
     }
 
-    /// Set the text color.
-    pub fn text_color(mut self, text_color: C) -> Self {
-        self.style.text_color = Some(text_color);
-        self.style.anti_aliasing = AntiAliasing::BackgroundColor;
+    /// Send a line to the target with as few operations as possible.
+    ///
+    /// With a background color filling the line, the background, `pixels` and decorations are
+    /// composed in a buffer sent with a single `fill_contiguous`, only the pixels outside the
+    /// line, like those of a shadow, are drawn separately. Otherwise, `pixels` and decorations
+    /// are sent together, see `draw_pixels`, after the background of the `glyphs` at `position`,
+    /// if any.
+    fn flush_line<D>(
+        &self,
+        line: Rectangle,
+        glyphs: &[PositionedGlyph<'_>],
+        position: Point,
+        mut pixels: allocator::Buffer<Pixel<C>>,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let decorations = self.visible_decorations(line);
+
+        // rounded corners and glyph boxes don't fill the area
+        let composed =
+            self.background_area == BackgroundArea::Line && self.background_corner_radius == 0;
+        let background_color =
+            match self.colors().1 {
+                Some(color) if !line.is_zero_sized() && composed => color,
+                _ => {
+                    self.draw_line_background(&line, glyphs, position, target)?;
+                    pixels.extend(decorations.flat_map(|(area, color)| {
+                        area.points().map(move |point| Pixel(point, color))
+                    }));
+                    return self.draw_pixels(pixels, target);
+                }
+            };
+
+        // the underline may be outside the line, the area includes it
+        let background = self.background_padding.around(&line);
+        let area = self
+            .decorations(line)
+            .fold(background, |area, (decoration, _)| {
+                envelope(&area, &decoration)
+            });
+        let index = |point: Point| {
+            let offset = point - area.top_left;
+            offset.y as usize * area.size.width as usize + offset.x as usize
+        };
+
+        let mut colors = allocator::buffer(self.allocator);
+        colors.resize(
+            (area.size.width * area.size.height) as usize,
+            background_color,
+        );
+        let mut outside = allocator::buffer(self.allocator);
+        for Pixel(point, color) in pixels {
+            if area.contains(point) {
+                colors[index(point)] = color;
+            } else {
+                outside.push(Pixel(point, color));
+            }
+        }
+        for (decoration, color) in decorations {
+            for point in decoration.points() {
+                colors[index(point)] = color;
+            }
+        }
+
+        if !outside.is_empty() {
+            self.draw_pixels(outside, target)?;
+        }
+        target.fill_contiguous(&area, colors)
+    }
+
+    /// Send `pixels` to the target, in a single `draw_iter`, or as horizontal spans with
+    /// `fill_contiguous` if the style asks for contiguous rows.
+    ///
+    /// Spans are sorted in row major order, where pixels overlap the last one drawn wins.
+    fn draw_pixels<D>(
+        &self,
+        mut pixels: allocator::Buffer<Pixel<C>>,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        if !self.contiguous_rows {
+            return target.draw_iter(pixels);
+        }
+        // the sort is stable, reversed the last pixel drawn at a point comes first
+        pixels.sort_by_key(|Pixel(point, _)| (point.y, point.x));
+        pixels.reverse();
+        pixels.dedup_by_key(|Pixel(point, _)| *point);
+        pixels.reverse();
+
+        let mut rest = &pixels[..];
+        while let Some(Pixel(start, _)) = rest.first() {
+            let length = rest
+                .iter()
+                .zip(0..)
+                .take_while(|(Pixel(point, _), i)| {
+                    point.y == start.y && point.x as i64 - start.x as i64 == *i
+                })
+                .count();
+            let (span, tail) = rest.split_at(length);
+            let area = Rectangle::new(*start, Size::new(length as u32, 1));
+            target.fill_contiguous(&area, span.iter().map(|Pixel(_, color)| *color))?;
+            rest = tail;
+        }
+        Ok(())
+    }
+
+    /// Box of a laid out line relative to its position, from the origin to the advance and
+    /// extended to the ink of glyphs overhanging either side, like italic or swash glyphs.
+    pub(crate) fn line_box(&self, layout: &LineLayout) -> Rectangle {
+        self.extent_box(&layout.extent())
+    }
+
+    /// Line box of a line of the given extent, see `line_box`.
+    pub(crate) fn extent_box(&self, extent: &LineExtent) -> Rectangle {
+        let advance = extent.advance.ceil().max(0.0) as i32;
+        match self.writing_mode {
+            WritingMode::Horizontal => {
+                let (mut left, mut right) = (extent.left, extent.right);
+                if self.faux_italic != 0.0 && right > left {
+                    let v_metrics = self.v_metrics();
+                    let (slant_left, slant_right) = transform::slant_overhang(
+                        self.faux_italic,
+                        v_metrics.ascent,
+                        v_metrics.descent,
+                    );
+                    left += slant_left;
+                    right += slant_right;
+                }
+                let (left, right) = (left.min(0), right.max(advance));
+                Rectangle::new(
+                    Point::new(left, 0),
+                    Size::new((right - left) as u32, self.band_height()),
+                )
+            }
+            WritingMode::Vertical => {
+                Rectangle::new(Point::zero(), Size::new(self.pixel_size(), advance as u32))
+            }
+        }
+    }
+
+    /// Offset from the position of a laid out line to the position following it.
+    pub(crate) fn advance_offset(&self, advance: f32) -> Point {
+        let advance = advance.ceil().max(0.0) as i32;
+        match self.writing_mode {
+            WritingMode::Horizontal => Point::new(advance, 0),
+            WritingMode::Vertical => Point::new(0, advance),
+        }
+    }
+
+    /// Fill the background of a line of `glyphs` drawn at `position`, `line` is its line box.
+    pub(crate) fn draw_line_background<D>(
+        &self,
+        line: &Rectangle,
+        glyphs: &[PositionedGlyph<'_>],
+        position: Point,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let background_color = match self.colors().1 {
+            Some(color) if !line.is_zero_sized() => color,
+            _ => return Ok(()),
+        };
+        match self.background_area {
+            BackgroundArea::Line => self.fill_background(
+                &self.background_padding.around(line),
+                background_color,
+                target,
+            ),
+            BackgroundArea::Glyphs => {
+                for bounds in glyphs.iter().filter_map(|g| self.glyph_bounds(g)) {
+                    let area = Rectangle::new(
+                        Point::new(bounds.min.x, bounds.min.y) + position,
+                        Size::new(bounds.width() as u32, bounds.height() as u32),
+                    );
+                    let area = self.background_padding.around(&area);
+                    self.fill_background(&area, background_color, target)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Outlines of the boxes drawn for missing glyphs.
+    fn notdef_pixels(
+        &self,
+        boxes: &[rusttype::Rect<f32>],
+        position: Point,
+    ) -> allocator::Buffer<Pixel<C>> {
+        let mut pixels = allocator::buffer(self.allocator);
+        if let Some(text_color) = self.colors().0 {
+            for b in boxes.iter() {
+                let left = position.x + b.min.x.round() as i32;
+                let top = position.y + b.min.y.round() as i32;
+                let right = position.x + b.max.x.round() as i32 - 1;
+                let bottom = position.y + b.max.y.round() as i32 - 1;
+                for x in left..=right {
+                    pixels.push(Pixel(Point::new(x, top), text_color));
+                    pixels.push(Pixel(Point::new(x, bottom), text_color));
+                }
+                for y in top + 1..bottom {
+                    pixels.push(Pixel(Point::new(left, y), text_color));
+                    pixels.push(Pixel(Point::new(right, y), text_color));
+                }
+            }
+        }
+        pixels
+    }
+
+    /// Color of the pixel at `point` of the glyph at index `glyph`, in a line covering `area`,
+    /// from the fill or the text color.
+    pub(crate) fn glyph_color(&self, point: Point, glyph: usize, area: &Rectangle) -> Option<C> {
+        match &self.fill {
+            Some(fill) => fill.color(point, glyph, area).or_else(|| self.colors().0),
+            None => self.colors().0,
+        }
+    }
+
+    /// Coverage scaled by the opacity.
+    fn faded(&self, coverage: u8) -> u8 {
+        (coverage as u16 * self.opacity as u16 / 255) as u8
+    }
+
+    /// Coverage scaled by the opacity and rounded to the coverage levels, as blended.
+    fn blended_coverage(&self, coverage: u8) -> u8 {
+        let coverage = self.faded(coverage);
+        match self.coverage_levels {
+            Some(levels) => color::quantize(coverage, levels),
+            None => coverage,
+        }
+    }
+
+    /// Color of a pixel of `color` with the given coverage, according to the antialiasing mode.
+    ///
+    /// Returns `None` if the pixel is transparent.
+    fn blend(&self, color: C, coverage: u8, point: Point) -> Option<C> {
+        self.blend_over(color, coverage, point, None)
+    }
+
+    /// Color of a pixel of `color` with the given coverage, drawn over `backdrop`, the pixel of
+    /// the target if it could be read, see `blend`.
+    fn blend_over(&self, color: C, coverage: u8, point: Point, backdrop: Option<C>) -> Option<C> {
+        let coverage = self.blended_coverage(coverage);
+        let bg_color = match self.anti_aliasing {
+            AntiAliasing::BackgroundColor => self.colors().1,
+            AntiAliasing::SolidColor(c) => Some(c),
+            AntiAliasing::None => None,
+            AntiAliasing::ReadBack => self.colors().1.or(backdrop),
+            AntiAliasing::Dithering => return color::dither(coverage, point).then_some(color),
+            AntiAliasing::Custom(f) => return f(coverage, point),
+            AntiAliasing::Subpixel(_) => self.colors().1,
+            AntiAliasing::Alpha(scale_alpha) => {
+                return (coverage > 0).then(|| scale_alpha(color, coverage))
+            }
+        };
+        match bg_color {
+            None => (coverage > 127).then_some(color),
+            Some(bg_color) => {
+                let blended = match &self.gamma {
+                    Some(gamma) => color.blend_gamma(bg_color, coverage, gamma),
+                    None => color.blend(bg_color, coverage),
+                };
+                let color = match &self.palette {
+                    Some(palette) => palette.nearest(blended, point),
+                    None => None,
+                };
+                Some(color.unwrap_or(blended))
+            }
+        }
+    }
+
+    /// Color of a pixel of `color` with the coverage of each stripe, for subpixel antialiasing.
+    ///
+    /// Returns `None` if the pixel is transparent.
+    fn blend_subpixels(&self, color: C, coverage: [u8; 3], point: Point) -> Option<C> {
+        let background = match self.colors().1 {
+            Some(background) => background,
+            None => return self.blend(color, color::mean(coverage), point),
+        };
+        let blended = color.blend_subpixels(
+            background,
+            coverage.map(|c| self.blended_coverage(c)),
+            self.gamma.as_ref(),
+        );
+        let color = match &self.palette {
+            Some(palette) => palette.nearest(blended, point),
+            None => None,
+        };
+        Some(color.unwrap_or(blended))
+    }
+
+    /// Blend the coverage of glyphs into pixels at `position`.
+    ///
+    /// Pixels outside of `bounds`, relative to `position`, are dropped and their number returned.
+    /// `done` is the number of glyphs of the current draw already blended. `backdrop` reads the
+    /// pixels of the target for read-back antialiasing.
+    fn glyph_pixels(
+        &self,
+        glyphs: &[PositionedGlyph<'_>],
+        done: usize,
+        position: Point,
+        bounds: Option<Rectangle>,
+        backdrop: impl Fn(Point) -> Option<C>,
+    ) -> (allocator::Buffer<Pixel<C>>, u32) {
+        let mut pixels = allocator::buffer(self.allocator);
+        let mut clipped = 0;
+        // fills span the line, or the glyphs without one
+        let area = bounds.unwrap_or_else(|| rotation::translate(&glyphs_box(glyphs), position));
+        // transformed glyphs may leave the line box
+        let bounds = bounds.filter(|_| self.transform.is_none());
+        let inside = |point: Point| match bounds {
+            Some(bounds) => bounds.contains(point),
+            None => true,
+        };
+
+        let subpixel = match self.anti_aliasing {
+            AntiAliasing::Subpixel(order) => Some(order),
+            _ => None,
+        };
+
+        if self.colors().0.is_some() || self.fill.is_some() {
+            for (i, g) in glyphs.iter().enumerate() {
+                // the coverage of each stripe is the same, except in subpixel antialiasing
+                let mut draw = |off_x: i32, off_y: i32, coverage: [u8; 3]| {
+                    let point = match (position.x.checked_add(off_x), position.y.checked_add(off_y))
+                    {
+                        (Some(x), Some(y)) => Some(Point::new(x, y)),
+                        _ => None,
+                    };
+                    // There's still a possibility that the glyph clips the boundaries of the bitmap
+                    let point = match point.filter(|point| inside(*point)) {
+                        Some(point) => point,
+                        None => {
+                            if coverage != [0; 3] {
+                                clipped += 1;
+                            }
+                            return;
+                        }
+                    };
+                    let text_a = coverage[0];
+                    let blended = self
+                        .glyph_color(point, done + i, &area)
+                        .and_then(|text_color| match subpixel {
+                            Some(_) => self.blend_subpixels(text_color, coverage, point),
+                            None => self.blend_over(
+                                text_color,
+                                text_a,
+                                point,
+                                backdrop(point).filter(|_| (1..255).contains(&self.faded(text_a))),
+                            ),
+                        });
+                    if let Some(color) = blended {
+                        pixels.push(Pixel(point, color));
+                    }
+                };
+                match subpixel {
+                    Some(order) => self.subpixel_coverage(g, order, &mut draw),
+                    None => self.glyph_coverage(g, |x, y, text_a| draw(x, y, [text_a; 3])),
+                }
+                self.report_progress((done + i) as u32 + 1);
+            }
+        }
+
+        (pixels, clipped)
+    }
+}
+
+impl<C> TextRenderer for FontTextStyle<C>
+where
+    C: ColorBlend + stdlib::fmt::Debug,
+{
+    type Color = C;
+
+    fn draw_string<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let lines = self
+            .lines(text)
+            .map(|(line, offset)| (line, self.layout(line), offset));
+        self.draw_lines(lines, position, baseline, |_, _| None, target)
+    }
+
+    fn draw_whitespace<D>(
+        &self,
+        width: u32,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = Self::Color>,
+    {
+        let top_left = position - Point::new(0, self.baseline_offset(baseline));
+        let line = Rectangle::new(top_left, Size::new(width, self.band_height()));
+        let mut target = self.rotated_target(target, position);
+        self.draw_background(width, top_left, &mut target)?;
+        self.draw_decorations(line, &mut target)?;
+
+        Ok(position + self.rotation.offset(Point::new(width as i32, 0)))
+    }
+
+    fn measure_string(&self, text: &str, position: Point, baseline: Baseline) -> TextMetrics {
+        let lines = self
+            .lines(text)
+            .map(|(line, offset)| (self.line_extent(line), offset));
+        self.measure_lines(lines, position, baseline)
+    }
+
+    fn line_height(&self) -> u32 {
+        if let Some(line_height) = self.line_height {
+            return line_height;
+        }
+        let height = match self.writing_mode {
+            WritingMode::Horizontal => {
+                let v_metrics = self.v_metrics();
+                v_metrics.ascent - v_metrics.descent + v_metrics.line_gap
+            }
+            // columns are `font_size` wide
+            WritingMode::Vertical => self.font_size,
+        };
+        (height * self.line_spacing).round().max(0.0) as u32
+    }
+}
+
+/// Smallest rectangle containing both `a` and `b`.
+fn envelope(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    match (a.bottom_right(), b.bottom_right()) {
+        (Some(a_end), Some(b_end)) => Rectangle::with_corners(
+            a.top_left.component_min(b.top_left),
+            a_end.component_max(b_end),
+        ),
+        (Some(_), None) => *a,
+        _ => *b,
+    }
+}
+
+/// Smallest rectangle containing the pixels of `glyphs`, relative to their origin.
+fn glyphs_box(glyphs: &[PositionedGlyph<'_>]) -> Rectangle {
+    glyphs
+        .iter()
+        .filter_map(|glyph| glyph.pixel_bounding_box())
+        .map(|b| {
+            Rectangle::with_corners(
+                Point::new(b.min.x, b.min.y),
+                Point::new(b.max.x - 1, b.max.y - 1),
+            )
+        })
+        .fold(Rectangle::zero(), |a, b| envelope(&a, &b))
+}
+
+/// Text style builder for ttf and otf fonts.
+///
+/// Use this builder to create [`FontTextStyle`]s for [`Text`].
+pub struct FontTextStyleBuilder<C: PixelColor> {
+    style: FontTextStyle<C>,
+}
+
+impl<C: PixelColor> FontTextStyleBuilder<C> {
+    /// Create a new text style builder.
+    pub fn new(font: Font<'static>) -> Self {
+        debug!(
+            "font loaded: {} glyphs, {} units per em",
+            font.glyph_count(),
+            font.units_per_em()
+        );
+        Self {
+            style: FontTextStyle {
+                font,
+                fallback_fonts: Vec::new(),
+                background_color: None,
+                background_padding: Padding::default(),
+                background_corner_radius: 0,
+                background_area: BackgroundArea::Line,
+                anti_aliasing: AntiAliasing::None,
+                font_size: 12.0,
+                text_transform: TextTransform::None,
+                locale: None,
+                writing_mode: WritingMode::Horizontal,
+                rotation: Rotation::Deg0,
+                clip: None,
+                transform: None,
+                faux_italic: 0.0,
+                metrics_source: MetricsSource::Auto,
+                metrics_override: MetricsOverride::default(),
+                horizontal_scale: 1.0,
+                baseline_shift: 0,
+                vertical_position: VerticalPosition::Normal,
+                line_height: None,
+                line_spacing: 1.0,
+                multiline: false,
+                tab_size: TabSize::default(),
+                trailing_whitespace: TrailingWhitespace::Include,
+                control_characters: ControlCharacters::Notdef,
+                kerning: true,
+                tabular_digits: false,
+                fixed_advance: None,
+                letter_spacing: 0,
+                word_spacing: 0,
+                faux_bold: 0,
+                notdef_metrics: None,
+                palette: None,
+                opacity: 255,
+                coverage_levels: None,
+                gamma: None,
+                fill: None,
+                contiguous_rows: false,
+                inverse: false,
+                shadow: None,
+                outline: None,
+                glow: None,
+                text_color: None,
+                underline_color: DecorationColor::None,
+                strikethrough_color: DecorationColor::None,
+                glyph_cache: None,
+                allocator: allocator::GLOBAL,
+                #[cfg(feature = "stats")]
+                stats: None,
+                #[cfg(feature = "hinting")]
+                hinting: false,
+                progress: None,
+            },
+        }
+    }
+
+    /// Set the font size of the style in pixels.
+    pub fn font_size(mut self, font_size: u32) -> Self {
+        self.style.font_size = font_size as f32;
+        self
+    }
+
+    /// Set a font size of the style that is not a whole number of pixels.
+    ///
+    /// Glyphs and metrics are scaled to the exact size, lines still take whole pixels.
+    pub fn fractional_font_size(mut self, font_size: f32) -> Self {
+        self.style.font_size = font_size;
+        self
+    }
+
+    /// Set the font size in typographic points, for a display of `dpi` pixels per inch.
+    ///
+    /// A point is 1/72 inch and the point size is the size of the em square, as in other
+    /// software. The pixel font size is the height from descent to ascent, so the conversion
+    /// depends on the proportions of the font: call it after changing the font.
+    pub fn font_size_pt(mut self, points: f32, dpi: u32) -> Self {
+        let em = points * dpi as f32 / 72.0;
+        let font = &self.style.font;
+        let v_metrics = font.v_metrics_unscaled();
+        let height = v_metrics.ascent - v_metrics.descent;
+        let units_per_em = font.units_per_em() as f32;
+        self.style.font_size = match height > 0.0 && units_per_em > 0.0 {
+            true => em * height / units_per_em,
+            false => em,
+        };
+        self
+    }
+
+    /// Scale the glyphs to `x` pixels wide and `y` pixels tall, as `rusttype::Scale`.
+    ///
+    /// `y` is the font size, the text is condensed when `x` is smaller and stretched when it is
+    /// larger. Changing the font size afterwards keeps the proportions.
+    pub fn font_scale(mut self, x: f32, y: f32) -> Self {
+        self.style.font_size = y;
+        self.style.horizontal_scale = if y > 0.0 { x / y } else { 1.0 };
+        self
+    }
+
+    /// Enable underline using the text color.
+    pub fn underline(mut self) -> Self {
+        self.style.underline_color = DecorationColor::TextColor;
+        self
+    }
+
+    /// Enable strikethrough using the text color.
+    pub fn strikethrough(mut self) -> Self {
+        self.style.strikethrough_color = DecorationColor::TextColor;
+        self
+    }
+
+    /// Set the text color.
+    pub fn text_color(mut self, text_color: C) -> Self {
+        self.style.text_color = Some(text_color);
+        self.style.anti_aliasing = AntiAliasing::BackgroundColor;
         self
     }
 
@@ -399,12 +1706,63 @@ impl<C: PixelColor> FontTextStyleBuilder<C> {
         self
     }
 
+    /// Grow the background of each line by the given number of pixels on each side.
+    ///
+    /// The text stays in place and advances as without padding, the measured bounding box
+    /// includes the padding. Whitespace drawn separately, like the justified spaces of a text
+    /// box, is only padded above and below.
+    pub fn background_padding(mut self, top: u32, right: u32, bottom: u32, left: u32) -> Self {
+        self.style.background_padding = Padding::new(top, right, bottom, left);
+        self
+    }
+
+    /// Round the corners of the background of each line with the given radius in pixels, for
+    /// pill shaped labels.
+    ///
+    /// Whitespace drawn separately, like the justified spaces of a text box, keeps square
+    /// corners.
+    pub fn background_corner_radius(mut self, radius: u32) -> Self {
+        self.style.background_corner_radius = radius;
+        self
+    }
+
+    /// Select the area filled by the background of each line, see [`BackgroundArea`].
+    pub fn background_area(mut self, area: BackgroundArea) -> Self {
+        self.style.background_area = area;
+        self
+    }
+
     /// Apply antialiasing over a known color.
     pub fn anti_aliasing_color(mut self, background_color: C) -> Self {
         self.style.anti_aliasing = AntiAliasing::SolidColor(background_color);
         self
     }
 
+    /// Dither the edges of glyphs, see [`AntiAliasing::Dithering`].
+    pub fn anti_aliasing_dithering(mut self) -> Self {
+        self.style.anti_aliasing = AntiAliasing::Dithering;
+        self
+    }
+
+    /// Choose the color of glyph pixels with a function, see [`AntiAliasing::Custom`].
+    pub fn anti_aliasing_custom(mut self, blend: fn(u8, Point) -> Option<C>) -> Self {
+        self.style.anti_aliasing = AntiAliasing::Custom(blend);
+        self
+    }
+
+    /// Apply subpixel antialiasing for a panel with the given stripe order, see
+    /// [`AntiAliasing::Subpixel`].
+    pub fn anti_aliasing_subpixel(mut self, order: SubpixelOrder) -> Self {
+        self.style.anti_aliasing = AntiAliasing::Subpixel(order);
+        self
+    }
+
+    /// Apply antialiasing over the pixels of the target, see [`AntiAliasing::ReadBack`].
+    pub fn anti_aliasing_read_back(mut self) -> Self {
+        self.style.anti_aliasing = AntiAliasing::ReadBack;
+        self
+    }
+
     /// Enable underline with a custom color.
     pub fn underline_with_color(mut self, underline_color: C) -> Self {
         self.style.underline_color = DecorationColor::Custom(underline_color);
@@ -418,16 +1776,1680 @@ impl<C: PixelColor> FontTextStyleBuilder<C> {
         self
     }
 
+    /// Transform the case of the text before rendering it.
+    pub fn text_transform(mut self, text_transform: TextTransform) -> Self {
+        self.style.text_transform = text_transform;
+        self
+    }
+
+    /// Set the language of the text.
+    ///
+    /// The locale is used by case transformations and, with the `opentype-layout` feature, to
+    /// select the localized glyph forms of the font.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.style.locale = Some(locale);
+        self
+    }
+
+    /// Set the writing mode, horizontal by default.
+    pub fn writing_mode(mut self, writing_mode: WritingMode) -> Self {
+        self.style.writing_mode = writing_mode;
+        self
+    }
+
+    /// Rotate the text by quarter turns around its position, upright by default.
+    ///
+    /// Backgrounds, effects and decorations turn with the glyphs, and the positions and boxes
+    /// returned by `draw_string` and `measure_string` are turned the same way.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.style.rotation = rotation;
+        self
+    }
+
+    /// Only draw inside `area`, in the coordinates of the target.
+    ///
+    /// Glyphs, backgrounds, effects and decorations crossing the edges of the area are cut at
+    /// them, whatever the rotation, so text can be placed partially off screen on targets that
+    /// reject pixels out of bounds. Pixels whose coordinates overflow are dropped too, and the
+    /// boxes and positions returned stop at the limits of the coordinates. To clip a single call,
+    /// draw to `target.clipped(area)` instead.
+    pub fn clip(mut self, area: Rectangle) -> Self {
+        self.style.clip = Some(area);
+        self
+    }
+
+    /// Transform the glyphs, to skew or rotate them by any angle.
+    ///
+    /// The transform applies to the glyph outlines before rasterization, around the start of the
+    /// baseline of their line. The layout, backgrounds, decorations and measured boxes are not
+    /// transformed, [`ink_bounding_box`](FontTextStyle::ink_bounding_box) covers the transformed
+    /// glyphs. The glyph cache is not used for transformed glyphs.
+    ///
+    /// This replaces any transform already set, including the slant of
+    /// [`faux_italic`](FontTextStyleBuilder::faux_italic).
+    pub fn transform(mut self, transform: AffineTransform) -> Self {
+        self.style.transform = Some(transform);
+        self.style.faux_italic = 0.0;
+        self
+    }
+
+    /// Slant the glyphs `degrees` to the right, synthetic italic.
+    ///
+    /// This shows italic text when only a regular font is available, at the cost of a font file.
+    /// The glyphs are sheared horizontally around the baseline, before any [`transform`]
+    /// already set. Advances lie along the baseline, the shear leaves them unchanged, but the
+    /// measured boxes, backgrounds and clip boxes of horizontal text are widened by the slant of
+    /// the ascent and descent, so that they cover the slanted glyphs.
+    ///
+    /// [`transform`]: FontTextStyleBuilder::transform
+    pub fn faux_italic(self, degrees: f32) -> Self {
+        let shear = AffineTransform::skew(Angle::from_degrees(-degrees), Angle::zero());
+        let transform = match self.style.transform {
+            Some(transform) => shear.then(&transform),
+            None => shear,
+        };
+        let mut builder = self.transform(transform);
+        builder.style.faux_italic = degrees;
+        builder
+    }
+
+    /// Select the font table used for ascent, descent and line gap, see [`MetricsSource`].
+    pub fn metrics_source(mut self, metrics_source: MetricsSource) -> Self {
+        self.style.metrics_source = metrics_source;
+        self
+    }
+
+    /// Override the font ascent, as a fraction of the font size.
+    pub fn ascent_override(mut self, ascent: f32) -> Self {
+        self.style.metrics_override.ascent = Some(ascent);
+        self
+    }
+
+    /// Override the font descent, as a positive fraction of the font size.
+    pub fn descent_override(mut self, descent: f32) -> Self {
+        self.style.metrics_override.descent = Some(descent);
+        self
+    }
+
+    /// Override the font line gap, as a fraction of the font size.
+    pub fn line_gap_override(mut self, line_gap: f32) -> Self {
+        self.style.metrics_override.line_gap = Some(line_gap);
+        self
+    }
+
+    /// Raise the glyphs by `shift` pixels, or lower them if negative.
+    ///
+    /// Use it to align styles with different fonts or sizes on a common baseline. In vertical
+    /// writing mode, glyphs are moved to the right.
+    pub fn baseline_shift(mut self, shift: i32) -> Self {
+        self.style.baseline_shift = shift;
+        self
+    }
+
+    /// Draw superscript or subscript glyphs, see [`VerticalPosition`].
+    ///
+    /// The size and offset of the glyphs come from the OS/2 table of the main font, they add to
+    /// the `baseline_shift`.
+    pub fn vertical_position(mut self, vertical_position: VerticalPosition) -> Self {
+        self.style.vertical_position = vertical_position;
+        self
+    }
+
+    /// Set a fixed line height in pixels, instead of the ascent, descent and line gap of the font.
+    pub fn line_height(mut self, line_height: u32) -> Self {
+        self.style.line_height = Some(line_height);
+        self
+    }
+
+    /// Set the distance between tab stops, 8 spaces by default.
+    pub fn tab_size(mut self, tab_size: TabSize) -> Self {
+        self.style.tab_size = tab_size;
+        self
+    }
+
+    /// Set how whitespace at the end of lines is measured, see [`TrailingWhitespace`].
+    ///
+    /// Keep the default when measuring single spaces, as embedded-text does.
+    pub fn trailing_whitespace(mut self, trailing_whitespace: TrailingWhitespace) -> Self {
+        self.style.trailing_whitespace = trailing_whitespace;
+        self
+    }
+
+    /// Set how control characters are rendered, see [`ControlCharacters`].
+    ///
+    /// Skip them or replace them to draw logs containing `\r` or escape sequences without boxes
+    /// of missing glyphs.
+    pub fn control_characters(mut self, control_characters: ControlCharacters) -> Self {
+        self.style.control_characters = control_characters;
+        self
+    }
+
+    /// Enable or disable kerning, enabled by default.
+    ///
+    /// Without kerning, the advance of a character doesn't depend on its neighbours, for
+    /// fixed-grid interfaces.
+    pub fn kerning(mut self, kerning: bool) -> Self {
+        self.style.kerning = kerning;
+        self
+    }
+
+    /// Give the digits 0 to 9 the advance of the widest one, centered in it.
+    ///
+    /// Clocks and counters keep their layout when their value changes, even with a font with
+    /// proportional figures. Digits are not kerned.
+    pub fn tabular_digits(mut self) -> Self {
+        self.style.tabular_digits = true;
+        self
+    }
+
+    /// Lay out horizontal text on a grid of cells `advance` pixels wide, each cluster centered in
+    /// its cell.
+    ///
+    /// Any font can then be used for terminal-like interfaces. Clusters wider than a cell
+    /// overflow it on both sides, choose the cell from the widest glyphs used. Kerning is disabled
+    /// and tab stops of [`TabSize::Spaces`] count cells.
+    pub fn fixed_advance(mut self, advance: u32) -> Self {
+        self.style.fixed_advance = Some(advance);
+        self
+    }
+
+    /// Add `spacing` pixels between clusters, or remove them if negative.
+    ///
+    /// Marks stay on their base character, no spacing is added after the last cluster.
+    pub fn letter_spacing(mut self, spacing: i32) -> Self {
+        self.style.letter_spacing = spacing;
+        self
+    }
+
+    /// Add `spacing` pixels after each whitespace character, or remove them if negative.
+    ///
+    /// Tabs still advance to the next tab stop.
+    pub fn word_spacing(mut self, spacing: i32) -> Self {
+        self.style.word_spacing = spacing;
+        self
+    }
+
+    /// Embolden the glyphs by widening their strokes by `weight_px` pixels to the right.
+    ///
+    /// This shows bold text when only a regular font is available, at the cost of a font file.
+    /// Each glyph advances by `weight_px` more, so that emboldened glyphs don't touch.
+    pub fn faux_bold(mut self, weight_px: u32) -> Self {
+        self.style.faux_bold = weight_px;
+        self
+    }
+
+    /// Multiply the line height derived from the font metrics by `spacing`, 1.2 leaves 20% more
+    /// room between lines.
+    ///
+    /// A fixed [`line_height`](Self::line_height) is used as is.
+    pub fn line_spacing(mut self, spacing: f32) -> Self {
+        self.style.line_spacing = spacing;
+        self
+    }
+
+    /// Break lines at `\n` when the style is used directly as a [`TextRenderer`].
+    ///
+    /// [`Text`] already splits its text into lines, this is for callers of `draw_string` and
+    /// `measure_string`.
+    pub fn multiline(mut self) -> Self {
+        self.style.multiline = true;
+        self
+    }
+
+    /// Use synthetic metrics for missing glyphs, see [`NotdefMetrics`].
+    pub fn notdef_metrics(mut self, notdef_metrics: NotdefMetrics) -> Self {
+        self.style.notdef_metrics = Some(notdef_metrics);
+        self
+    }
+
+    /// Enable inverse video: the background is filled with the text color and the glyphs are
+    /// drawn in the background color, as for a selected menu item.
+    ///
+    /// The style needs a background color, otherwise the glyphs are not drawn at all.
+    pub fn inverse(mut self) -> Self {
+        self.style.inverse = true;
+        self
+    }
+
+    /// Draw a shadow behind the text, see [`Shadow`].
+    pub fn shadow(mut self, shadow: Shadow<C>) -> Self {
+        self.style.shadow = Some(shadow);
+        self
+    }
+
+    /// Draw a hard shadow of the text moved by `offset`, in the same call as the text.
+    ///
+    /// Shorthand for `shadow(Shadow::new(color, offset))`, use [`shadow`](Self::shadow) for a
+    /// blurred shadow.
+    pub fn drop_shadow(self, offset: Point, color: C) -> Self {
+        self.shadow(Shadow::new(color, offset))
+    }
+
+    /// Draw an outline around the glyphs, see [`Outline`].
+    pub fn outline(mut self, outline: Outline<C>) -> Self {
+        self.style.outline = Some(outline);
+        self
+    }
+
+    /// Draw a halo behind the text, see [`Glow`].
+    pub fn glow(mut self, glow: Glow<C>) -> Self {
+        self.style.glow = Some(glow);
+        self
+    }
+
+    /// Stroke the glyphs with `width` pixels of `color`, drawn before the glyphs, a shortcut
+    /// for an [`Outline`].
+    pub fn stroke(self, color: C, width: u32) -> Self {
+        self.outline(Outline::new(color, width))
+    }
+
+    /// Restrict antialiased colors to the entries of a palette, see [`Palette`].
+    pub fn palette(mut self, palette: Palette<C>) -> Self {
+        self.style.palette = Some(palette);
+        self
+    }
+
+    /// Set the opacity of the text, from 0 for invisible to 255 for opaque.
+    ///
+    /// Glyphs, effects and decorations are blended with the antialiasing background, without
+    /// one they disappear below half opacity.
+    pub fn opacity(mut self, opacity: u8) -> Self {
+        self.style.opacity = opacity;
+        self
+    }
+
+    /// Round the coverage to `levels` evenly spaced levels before blending, at least 2.
+    ///
+    /// On 2 or 4 bit grayscale panels, 4 or 16 levels blend the text to shades the panel can
+    /// show. To also shrink the glyph cache, give it the matching [`CoverageDepth`].
+    pub fn coverage_levels(mut self, levels: u8) -> Self {
+        self.style.coverage_levels = Some(levels.max(2));
+        self
+    }
+
+    /// Blend colors in linear light for a target of the given gamma, see [`Gamma`].
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.style.gamma = Some(Gamma::new(gamma));
+        self
+    }
+
+    /// Set the colors of the glyphs, replacing the text color, see [`TextFill`].
+    pub fn text_fill(mut self, fill: TextFill<C>) -> Self {
+        self.style.fill = Some(fill);
+        self
+    }
+
+    /// Compute the color of each glyph pixel from its position and the index of its glyph,
+    /// see [`TextFill::Function`].
+    pub fn text_color_fn(self, color: fn(Point, usize) -> C) -> Self {
+        self.text_fill(TextFill::Function(color))
+    }
+
+    /// Fill the glyphs with a copy of `image`, repeated, see [`TextFill::Texture`].
+    pub fn text_texture<I>(self, image: &I) -> Self
+    where
+        I: GetPixel<Color = C> + OriginDimensions,
+    {
+        self.text_fill(TextFill::texture(image))
+    }
+
+    /// Interpolate the glyph color from `start` to `end` across each line, see
+    /// [`TextFill::Gradient`].
+    pub fn text_gradient(self, start: C, end: C, direction: GradientDirection) -> Self {
+        self.text_fill(TextFill::Gradient {
+            start,
+            end,
+            direction,
+        })
+    }
+
+    /// Add a fallback font, used for runs of text the main font has no glyphs for.
+    ///
+    /// Fallback fonts are tried in the order they are added.
+    pub fn fallback_font(mut self, font: Font<'static>) -> Self {
+        debug!(
+            "fallback font {} loaded: {} glyphs",
+            self.style.fallback_fonts.len(),
+            font.glyph_count()
+        );
+        self.style.fallback_fonts.push(font);
+        self
+    }
+
+    /// Send the pixels drawn as horizontal spans with `fill_contiguous`, instead of one
+    /// `draw_iter` call per line.
+    ///
+    /// The pixels of each line are composed row by row, and each run of adjacent pixels is sent
+    /// as a rectangle one pixel high. Targets addressing a window of the display per operation,
+    /// like most SPI displays, then send a window and a block of colors per run rather than per
+    /// pixel. Lines with a background color are always composed into a single `fill_contiguous`.
+    pub fn contiguous_rows(mut self) -> Self {
+        self.style.contiguous_rows = true;
+        self
+    }
+
+    /// Cache rasterized glyphs in a shared [`GlyphCache`].
+    pub fn glyph_cache(mut self, glyph_cache: SharedGlyphCache) -> Self {
+        self.style.glyph_cache = Some(glyph_cache);
+        self
+    }
+
+    /// Allocate the pixel buffers used while drawing in `allocator` instead of the global heap.
+    #[cfg(feature = "allocator-api2")]
+    pub fn allocator(mut self, allocator: AllocatorRef) -> Self {
+        self.style.allocator = allocator::Alloc(allocator);
+        self
+    }
+
+    /// Count the drawing done with this style in `stats`, in addition to the global statistics.
+    #[cfg(feature = "stats")]
+    pub fn stats(mut self, stats: SharedRenderStats) -> Self {
+        self.style.stats = Some(stats);
+        self
+    }
+
+    /// Fit the glyph outlines to the pixel grid, which sharpens small text.
+    ///
+    /// The baseline, x-height and cap height of each glyph are moved to whole pixel rows and the
+    /// outline is stretched between them, so that horizontal edges and bars are not smeared over
+    /// two rows. Horizontal positions are kept. Hinted glyphs are rasterized from their outline on
+    /// each draw, without using the glyph cache.
+    #[cfg(feature = "hinting")]
+    pub fn hinting(mut self, hinting: bool) -> Self {
+        self.style.hinting = hinting;
+        self
+    }
+
+    /// Call `callback` every `every` glyphs while drawing, with the number of glyphs drawn so
+    /// far by the current call.
+    ///
+    /// This allows feeding a watchdog or animating a busy indicator during long draws. The
+    /// callback is a plain function, it has no access to the drawing.
+    pub fn progress(mut self, every: u32, callback: fn(u32)) -> Self {
+        self.style.progress = Some(Progress::new(every, callback));
+        self
+    }
+
     /// Build the text style.
     pub fn build(self) -> FontTextStyle<C> {
         self.style
     }
 }
 
+impl<C: AlphaPixelColor> FontTextStyleBuilder<C> {
+    /// Write the coverage into the alpha channel of the colors, see [`AntiAliasing::Alpha`].
+    pub fn anti_aliasing_alpha(mut self) -> Self {
+        self.style.anti_aliasing = AntiAliasing::Alpha(C::scale_alpha);
+        self
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use embedded_graphics::pixelcolor::Rgb888;
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888, text::Text};
+
+    fn font() -> Font<'static> {
+        Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap()
+    }
+
+    fn display() -> MockDisplay<Rgb888> {
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        display.set_allow_overdraw(true);
+        display
+    }
+
+    #[test]
+    fn cached_glyphs_are_reused() {
+        let cache = SharedGlyphCache::new(GlyphCache::new(64 * 1024));
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .glyph_cache(cache.clone())
+            .build();
+
+        let mut first = display();
+        Text::new("abab", Point::new(0, 0), style.clone())
+            .draw(&mut first)
+            .unwrap();
+        // "a" and "b" are each rasterized at 2 subpixel positions at most
+        let glyphs = cache.len();
+        assert!(glyphs > 0 && glyphs <= 4);
+
+        let mut second = display();
+        Text::new("abab", Point::new(0, 0), style)
+            .draw(&mut second)
+            .unwrap();
+        assert_eq!(cache.len(), glyphs);
+        first.assert_eq(&second);
+    }
+
+    #[test]
+    fn cache_respects_capacity() {
+        let cache = SharedGlyphCache::new(GlyphCache::new(200));
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .glyph_cache(cache.clone())
+            .build();
+
+        Text::new("Hello World!", Point::new(0, 0), style)
+            .draw(&mut display())
+            .unwrap();
+        assert!(!cache.is_empty());
+        assert!(cache.used() <= cache.capacity());
+    }
+
+    #[test]
+    fn point_sizes_depend_on_the_density() {
+        let style = |dpi| {
+            FontTextStyleBuilder::<Rgb888>::new(font())
+                .font_size_pt(12.0, dpi)
+                .build()
+        };
+        assert_eq!(style(192).font_size, 2.0 * style(96).font_size);
+        // 12 points at 96 dpi is a 16 pixel em
+        let font = font();
+        let v_metrics = font.v_metrics_unscaled();
+        let em = style(96).font_size * font.units_per_em() as f32
+            / (v_metrics.ascent - v_metrics.descent);
+        assert!((em - 16.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn trailing_whitespace_can_be_excluded() {
+        let builder = || {
+            FontTextStyleBuilder::<Rgb888>::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+                .underline()
+        };
+        let included = builder().build();
+        let excluded = builder()
+            .trailing_whitespace(TrailingWhitespace::Exclude)
+            .build();
+        let measure = |style: &FontTextStyle<Rgb888>, text| {
+            style.measure_string(text, Point::zero(), Baseline::Top)
+        };
+        assert_eq!(measure(&excluded, "ab  "), measure(&included, "ab"));
+        assert!(
+            measure(&included, "ab  ").next_position.x > measure(&included, "ab").next_position.x
+        );
+        // leading whitespace is kept
+        assert_eq!(measure(&excluded, " ab "), measure(&included, " ab"));
+
+        let mut expected = display();
+        included
+            .draw_string("ab", Point::zero(), Baseline::Top, &mut expected)
+            .unwrap();
+        let mut drawn = display();
+        let next = excluded
+            .draw_string("ab \t", Point::zero(), Baseline::Top, &mut drawn)
+            .unwrap();
+        drawn.assert_eq(&expected);
+        assert_eq!(next, measure(&included, "ab").next_position);
+
+        // whitespace drawn by the caller matches whitespace in a string
+        let space = measure(&included, " ").next_position.x as u32;
+        let mut whitespace = display();
+        included
+            .draw_whitespace(space, Point::zero(), Baseline::Top, &mut whitespace)
+            .unwrap();
+        let mut string = display();
+        included
+            .draw_string(" ", Point::zero(), Baseline::Top, &mut string)
+            .unwrap();
+        whitespace.assert_eq(&string);
+    }
+
+    #[test]
+    fn vertical_layout_stacks_glyphs() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .writing_mode(WritingMode::Vertical)
+            .build();
+
+        // Roboto has no vertical metrics, each glyph advances by the font size
+        let metrics = style.measure_string("abc", Point::new(5, 5), Baseline::Top);
+        assert_eq!(
+            metrics.bounding_box,
+            Rectangle::new(Point::new(5, 5), Size::new(20, 60))
+        );
+        assert_eq!(metrics.next_position, Point::new(5, 65));
+    }
+
+    #[test]
+    fn vertical_decorations_follow_the_column() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .underline_with_color(Rgb888::RED)
+            .strikethrough_with_color(Rgb888::BLUE)
+            .writing_mode(WritingMode::Vertical)
+            .build();
+        let mut display = display();
+        display.set_allow_overdraw(true);
+        style
+            .draw_string("ab", Point::new(5, 5), Baseline::Top, &mut display)
+            .unwrap();
+
+        let column = |color| {
+            let points = display
+                .bounding_box()
+                .points()
+                .filter(|&point| display.get_pixel(point) == Some(color));
+            let mut area = Rectangle::zero();
+            for point in points {
+                let pixel = Rectangle::new(point, Size::new(1, 1));
+                area = match area.is_zero_sized() {
+                    true => pixel,
+                    false => envelope(&area, &pixel),
+                };
+            }
+            area
+        };
+        // the underline runs along the right side of the column
+        assert_eq!(
+            column(Rgb888::RED),
+            Rectangle::new(Point::new(25, 5), Size::new(1, 40))
+        );
+        assert_eq!(column(Rgb888::BLUE).size.height, 40);
+        assert_eq!(column(Rgb888::BLUE).top_left, Point::new(15, 5));
+    }
+
+    #[test]
+    fn baselines_move_the_text() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let top = |baseline| {
+            let metrics = style.measure_string("x", Point::new(0, 30), baseline);
+            assert_eq!(metrics.next_position.y, 30);
+            metrics.bounding_box.top_left.y
+        };
+        assert_eq!(top(Baseline::Top), 30);
+        assert_eq!(top(Baseline::Middle), 21);
+        assert_eq!(top(Baseline::Bottom), 11);
+        assert_eq!(top(Baseline::Alphabetic), 14);
+
+        // an x drawn on the alphabetic baseline stands on it
+        let mut display = display();
+        Text::new("x", Point::new(0, 30), style)
+            .draw(&mut display)
+            .unwrap();
+        assert_eq!(display.affected_area().bottom_right().unwrap().y, 29);
+    }
+
+    #[test]
+    fn joiners_and_selectors_are_not_rendered() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font()).build();
+
+        assert_eq!(style.layout("a\u{FE0F}b").glyphs.len(), 2);
+        assert_eq!(style.layout("a\u{200D}b").glyphs.len(), 2);
+        // a skin tone modifier unknown to the font doesn't show up as a missing glyph
+        assert_eq!(style.layout("a\u{1F3FD}").glyphs.len(), 1);
+        assert_eq!(
+            style.measure_string("a\u{FE0F}b", Point::zero(), Baseline::Top),
+            style.measure_string("ab", Point::zero(), Baseline::Top)
+        );
+    }
+
+    #[test]
+    fn missing_glyphs_are_drawn_as_boxes() {
+        let builder = || {
+            FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+        };
+
+        // Roboto's .notdef has no outline
+        let mut plain = display();
+        Text::new("\u{E000}", Point::new(0, 20), builder().build())
+            .draw(&mut plain)
+            .unwrap();
+        assert_eq!(plain.affected_area().size, Size::zero());
+
+        let mut boxed = display();
+        let style = builder().notdef_metrics(NotdefMetrics::default()).build();
+        Text::new("\u{E000}", Point::new(0, 20), style)
+            .draw(&mut boxed)
+            .unwrap();
+        assert!(boxed.affected_area().size.height > 10);
+    }
+
+    #[test]
+    fn whitespace_and_decorations_follow_the_metrics() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .underline_with_color(Rgb888::RED)
+            .descent_override(0.5)
+            .build();
+        let v_metrics = style.v_metrics();
+        let height = (v_metrics.ascent - v_metrics.descent).ceil() as u32;
+        let space = style.measure_string(" ", Point::zero(), Baseline::Top);
+        assert_eq!(space.bounding_box.size.height, height);
+
+        let mut whitespace = display();
+        style
+            .draw_whitespace(5, Point::zero(), Baseline::Top, &mut whitespace)
+            .unwrap();
+        let band = whitespace.affected_area();
+        assert_eq!(band.size.height, height);
+
+        // the underline is just below the baseline, not at the bottom of the line
+        let underline = band
+            .points()
+            .filter(|&point| whitespace.get_pixel(point) == Some(Rgb888::RED))
+            .map(|point| point.y)
+            .min()
+            .unwrap();
+        let baseline = v_metrics.ascent.round() as i32;
+        assert!((baseline..baseline + 3).contains(&underline));
+    }
+
+    #[test]
+    fn ascent_override_moves_the_baseline() {
+        let draw = |builder: FontTextStyleBuilder<Rgb888>| {
+            let mut display = display();
+            let style = builder.font_size(20).text_color(Rgb888::WHITE).build();
+            Text::with_baseline("x", Point::zero(), style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            display.affected_area()
+        };
+
+        let normal = draw(FontTextStyleBuilder::new(font()));
+        let lowered = draw(FontTextStyleBuilder::new(font()).ascent_override(1.0));
+        assert!(lowered.top_left.y > normal.top_left.y);
+        assert_eq!(lowered.size, normal.size);
+    }
+
+    #[test]
+    fn baseline_shift_raises_glyphs() {
+        let draw = |shift| {
+            let mut display = display();
+            let style = FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .baseline_shift(shift)
+                .build();
+            Text::new("x", Point::new(0, 30), style)
+                .draw(&mut display)
+                .unwrap();
+            display.affected_area()
+        };
+
+        let normal = draw(0);
+        assert_eq!(draw(3).top_left, normal.top_left - Point::new(0, 3));
+        assert_eq!(draw(-2).top_left, normal.top_left + Point::new(0, 2));
+    }
+
+    #[test]
+    fn control_characters_follow_the_policy() {
+        let draw = |policy, text| {
+            let style = FontTextStyleBuilder::<Rgb888>::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .control_characters(policy)
+                .build();
+            let mut display = display();
+            let next = style
+                .draw_string(text, Point::new(0, 2), Baseline::Top, &mut display)
+                .unwrap();
+            (display, next)
+        };
+
+        let (plain, plain_next) = draw(ControlCharacters::Notdef, "ab");
+        let (skipped, skipped_next) = draw(ControlCharacters::Skip, "a\r\u{1b}b");
+        skipped.assert_eq(&plain);
+        assert_eq!(skipped_next, plain_next);
+
+        let (replaced, replaced_next) = draw(ControlCharacters::Replacement('-'), "a\u{1b}b");
+        let (expected, expected_next) = draw(ControlCharacters::Notdef, "a-b");
+        replaced.assert_eq(&expected);
+        assert_eq!(replaced_next, expected_next);
+
+        let (_, notdef_next) = draw(ControlCharacters::Notdef, "a\u{1b}b");
+        assert!(notdef_next.x > plain_next.x);
+    }
+
+    #[test]
+    fn scripts_are_smaller_and_shifted() {
+        let style = |position| {
+            FontTextStyleBuilder::<Rgb888>::new(font())
+                .font_size(30)
+                .text_color(Rgb888::WHITE)
+                .vertical_position(position)
+                .build()
+        };
+        let ink = |position| style(position).ink_bounding_box("x", Point::new(0, 40));
+        let normal = ink(VerticalPosition::Normal);
+        let superscript = ink(VerticalPosition::Superscript);
+        let subscript = ink(VerticalPosition::Subscript);
+
+        assert!(superscript.size.height < normal.size.height);
+        assert!(subscript.size.height < normal.size.height);
+        // the bottom of an x sits on the baseline
+        let bottom = |area: Rectangle| area.bottom_right().unwrap().y;
+        assert!(bottom(superscript) < bottom(normal));
+        assert!(bottom(subscript) > bottom(normal));
+
+        // the line box is the one of the font size
+        let measure = |position| {
+            style(position)
+                .measure_string("x", Point::zero(), Baseline::Top)
+                .bounding_box
+                .size
+                .height
+        };
+        assert_eq!(
+            measure(VerticalPosition::Superscript),
+            measure(VerticalPosition::Normal)
+        );
+    }
+
+    #[test]
+    fn newlines_start_new_lines() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .multiline()
+            .build();
+
+        let mut lines = display();
+        let next = style
+            .draw_string("Hi\r\nyo", Point::zero(), Baseline::Top, &mut lines)
+            .unwrap();
+        let mut expected = display();
+        style
+            .draw_string("Hi", Point::zero(), Baseline::Top, &mut expected)
+            .unwrap();
+        let end = style
+            .draw_string("yo", Point::new(0, 20), Baseline::Top, &mut expected)
+            .unwrap();
+        lines.assert_eq(&expected);
+        assert_eq!(next, end);
+
+        let metrics = style.measure_string("Hi\nyo", Point::zero(), Baseline::Top);
+        assert_eq!(metrics.next_position, end);
+        // as wide as the longest line
+        let width = style.measure_string("yo", Point::zero(), Baseline::Top);
+        assert_eq!(
+            metrics.bounding_box.size,
+            Size::new(width.bounding_box.size.width, 40)
+        );
+    }
+
+    #[test]
+    fn tabs_advance_to_the_next_stop() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(20);
+        let width = |style: &FontTextStyle<Rgb888>, text| {
+            style
+                .measure_string(text, Point::zero(), Baseline::Top)
+                .bounding_box
+                .size
+                .width
+        };
+
+        let style = builder().tab_size(TabSize::Pixels(40)).build();
+        assert_eq!(width(&style, "\t"), 40);
+        assert_eq!(width(&style, "ab\t"), 40);
+        assert_eq!(width(&style, "\t\t"), 80);
+        let layout = style.layout("a\tb");
+        assert_eq!(layout.glyphs.len(), 2);
+        assert_eq!(layout.glyphs[1].position().x, 40.0);
+
+        let style = builder().tab_size(TabSize::Spaces(2)).build();
+        assert_eq!(width(&style, "\t"), width(&style, "  "));
+    }
+
+    #[test]
+    fn letter_spacing_separates_clusters() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(20);
+        let measure = |style: &FontTextStyle<Rgb888>, text| {
+            style.measure_string(text, Point::zero(), Baseline::Top)
+        };
+
+        let normal = builder().build();
+        let spaced = builder().letter_spacing(3).build();
+        let tight = builder().letter_spacing(-1).build();
+        let width = |style, text| measure(style, text).bounding_box.size.width;
+        assert_eq!(width(&spaced, "abc"), width(&normal, "abc") + 6);
+        assert_eq!(width(&tight, "abc"), width(&normal, "abc") - 2);
+        assert_eq!(
+            measure(&spaced, "abc").next_position,
+            measure(&normal, "abc").next_position + Point::new(6, 0)
+        );
+        // a combining mark is not spaced from its base
+        assert_eq!(width(&spaced, "e\u{301}"), width(&normal, "e\u{301}"));
+
+        let b = spaced.layout("ab").glyphs[1].position().x;
+        assert_eq!(b, normal.layout("ab").glyphs[1].position().x + 3.0);
+    }
+
+    #[test]
+    fn kerning_can_be_disabled() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(40);
+        let kerned = builder().build();
+        let fixed = builder().kerning(false).build();
+        let x = |style: &FontTextStyle<Rgb888>| style.layout("AV").glyphs[1].position().x;
+
+        // without kerning, glyphs follow each other by their advance
+        let advance = font()
+            .glyph('A')
+            .scaled(kerned.scale())
+            .h_metrics()
+            .advance_width;
+        assert_eq!(x(&fixed), advance);
+        // the query matches the layout and doesn't depend on the switch
+        let kerning = kerned.kerning('A', 'V');
+        assert_eq!(fixed.kerning('A', 'V'), kerning);
+        assert!((x(&kerned) - x(&fixed) - kerning as f32).abs() <= 0.5);
+    }
+
+    #[test]
+    fn tabular_digits_have_the_same_advance() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(20);
+        let advance = |style: &FontTextStyle<Rgb888>, text: &str| style.layout(text).advance;
+        let tabular = builder().tabular_digits().build();
+        let proportional = builder().build();
+
+        let widths: Vec<f32> = ('0'..='9')
+            .map(|digit| advance(&proportional, digit.encode_utf8(&mut [0; 4])))
+            .collect();
+        let widest = widths.iter().cloned().fold(0.0, f32::max);
+        assert_eq!(advance(&tabular, "1111"), 4.0 * widest);
+        assert_eq!(advance(&tabular, "12:05"), advance(&tabular, "88:88"));
+        // narrow digits are centered in their cell
+        let one = tabular.layout("1").glyphs[0].position().x;
+        assert_eq!(one, (widest - widths[1]) / 2.0);
+    }
+
+    #[test]
+    fn fixed_advance_centers_clusters_in_cells() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(20)
+            .fixed_advance(12)
+            .build();
+        let layout = style.layout("iW e\u{301}\tx");
+        assert_eq!(layout.advance, 12.0 * 9.0);
+        let clusters: Vec<f32> = layout.clusters.iter().map(|&(_, caret)| caret).collect();
+        assert_eq!(clusters, [0.0, 12.0, 24.0, 36.0, 48.0, 96.0]);
+
+        // glyphs are centered in their cell
+        let scaled = |c| font().glyph(c).scaled(style.scale());
+        let i = scaled('i').h_metrics().advance_width;
+        assert_eq!(layout.glyphs[0].position().x, (12.0 - i) / 2.0);
+        let w = scaled('W').h_metrics().advance_width;
+        assert_eq!(layout.glyphs[1].position().x, 12.0 + (12.0 - w) / 2.0);
+    }
+
+    #[test]
+    fn measurement_matches_the_layout() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(20);
+        let styles = [
+            builder().build(),
+            builder().notdef_metrics(NotdefMetrics::default()).build(),
+            builder().fixed_advance(9).letter_spacing(1).build(),
+            builder()
+                .tabular_digits()
+                .text_transform(TextTransform::Uppercase)
+                .build(),
+            builder().writing_mode(WritingMode::Vertical).build(),
+        ];
+        for style in styles.iter() {
+            for text in ["jAV 10", "x\u{301}\u{10FFFF}\tß", "", " f\u{200B}"] {
+                assert_eq!(style.line_extent(text), style.layout(text).extent());
+            }
+        }
+    }
+
+    #[test]
+    fn word_spacing_widens_spaces() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(20);
+        let normal = builder().build();
+        let spaced = builder().word_spacing(4).build();
+        let x = |style: &FontTextStyle<Rgb888>, text| {
+            style.layout(text).glyphs.last().unwrap().position().x
+        };
+
+        assert_eq!(x(&spaced, "ab"), x(&normal, "ab"));
+        assert_eq!(x(&spaced, "a b"), x(&normal, "a b") + 4.0);
+        assert_eq!(x(&spaced, "a \u{A0}b"), x(&normal, "a \u{A0}b") + 8.0);
+    }
+
+    #[test]
+    fn fractional_sizes_are_laid_out_on_whole_pixels() {
+        let style = |size| {
+            FontTextStyleBuilder::<Rgb888>::new(font())
+                .fractional_font_size(size)
+                .build()
+        };
+        let measure = |size| {
+            style(size)
+                .measure_string("mmmm", Point::zero(), Baseline::Top)
+                .bounding_box
+                .size
+        };
+
+        let (small, middle, large) = (measure(13.0), measure(13.5), measure(14.0));
+        assert_eq!(middle.height, 14);
+        assert!(small.width < middle.width && middle.width < large.width);
+        assert_eq!(
+            style(13.5).v_metrics().ascent,
+            style(27.0).v_metrics().ascent / 2.0
+        );
+    }
+
+    #[test]
+    fn condensed_text_is_narrower() {
+        let measure = |builder: FontTextStyleBuilder<Rgb888>| {
+            builder
+                .build()
+                .measure_string("mmmm", Point::zero(), Baseline::Top)
+                .bounding_box
+                .size
+        };
+
+        let normal = measure(FontTextStyleBuilder::new(font()).font_size(20));
+        let condensed = measure(FontTextStyleBuilder::new(font()).font_scale(16.0, 20.0));
+        assert_eq!(condensed.height, normal.height);
+        let expected = normal.width as f32 * 0.8;
+        assert!((condensed.width as f32 - expected).abs() <= 1.0);
+
+        let draw = |x| {
+            let mut display = display();
+            let style = FontTextStyleBuilder::new(font())
+                .font_scale(x, 20.0)
+                .text_color(Rgb888::WHITE)
+                .build();
+            Text::with_baseline("m", Point::zero(), style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            display.affected_area().size
+        };
+        assert!(draw(30.0).width > draw(20.0).width);
+        assert_eq!(draw(30.0).height, draw(20.0).height);
+    }
+
+    #[test]
+    fn measured_box_contains_the_ink() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(40)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let position = Point::new(10, 0);
+
+        for text in ["j", "jaj", "a ", "V/"] {
+            let metrics = style.measure_string(text, position, Baseline::Top);
+            let mut display = display();
+            let next = style
+                .draw_string(text, position, Baseline::Top, &mut display)
+                .unwrap();
+            assert_eq!(next, metrics.next_position, "{:?}", text);
+            let ink = display.affected_area();
+            assert_eq!(
+                ink.intersection(&metrics.bounding_box),
+                ink,
+                "{:?} clipped",
+                text
+            );
+        }
+
+        // the j hangs left of the position, a trailing space is part of the advance
+        let j = style.measure_string("j", position, Baseline::Top);
+        assert!(j.bounding_box.top_left.x < position.x);
+        let a = style.measure_string("a", position, Baseline::Top);
+        let space = style.measure_string("a ", position, Baseline::Top);
+        assert!(space.next_position.x > a.next_position.x);
+    }
+
+    #[test]
+    fn line_height_follows_the_metrics() {
+        let builder = || FontTextStyleBuilder::<Rgb888>::new(font()).font_size(20);
+
+        // Roboto has no line gap, the line is as tall as the font size
+        assert_eq!(builder().build().line_height(), 20);
+        assert_eq!(builder().line_gap_override(0.25).build().line_height(), 25);
+        assert_eq!(builder().descent_override(0.5).build().line_height(), 26);
+        assert_eq!(builder().line_height(18).build().line_height(), 18);
+        assert_eq!(builder().line_spacing(1.5).build().line_height(), 30);
+        let fixed = builder().line_height(18).line_spacing(1.5).build();
+        assert_eq!(fixed.line_height(), 18);
+    }
+
+    #[test]
+    fn blended_colors_use_the_palette() {
+        let palette = [Rgb888::BLACK, Rgb888::new(128, 128, 128), Rgb888::WHITE];
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .palette(Palette::new(palette))
+            .build();
+
+        let mut display = display();
+        Text::new("Hello", Point::new(0, 20), style)
+            .draw(&mut display)
+            .unwrap();
+        let area = display.affected_area();
+        assert!(area.points().all(|p| match display.get_pixel(p) {
+            Some(color) => palette.contains(&color),
+            None => true,
+        }));
+    }
+
+    #[test]
+    fn text_is_drawn_in_any_color_type() {
+        use embedded_graphics::pixelcolor::{BinaryColor, Gray4};
+
+        let mut binary = MockDisplay::new();
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(16)
+            .text_color(BinaryColor::On)
+            .background_color(BinaryColor::Off)
+            .build();
+        Text::new("Hi", Point::new(0, 16), style)
+            .draw(&mut binary)
+            .unwrap();
+        assert!(binary
+            .affected_area()
+            .points()
+            .any(|p| binary.get_pixel(p) == Some(BinaryColor::On)));
+
+        // partially covered pixels get intermediate levels
+        let mut gray = MockDisplay::new();
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(16)
+            .text_color(Gray4::WHITE)
+            .background_color(Gray4::BLACK)
+            .build();
+        Text::new("Hi", Point::new(0, 16), style)
+            .draw(&mut gray)
+            .unwrap();
+        assert!(gray.affected_area().points().any(|p| gray
+            .get_pixel(p)
+            .is_some_and(|c| c.luma() > 0 && c.luma() < 15)));
+    }
+
+    #[test]
+    fn custom_blending_chooses_the_colors() {
+        let draw = |builder: FontTextStyleBuilder<Rgb888>| {
+            let mut display = display();
+            Text::new("Hello", Point::new(0, 20), builder.font_size(20).build())
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+        let custom = draw(
+            FontTextStyleBuilder::new(font())
+                .text_color(Rgb888::WHITE)
+                .anti_aliasing_custom(|coverage, _| match coverage {
+                    0 => None,
+                    255 => Some(Rgb888::WHITE),
+                    _ => Some(Rgb888::RED),
+                }),
+        );
+        let blended = draw(
+            FontTextStyleBuilder::new(font())
+                .text_color(Rgb888::WHITE)
+                .anti_aliasing_color(Rgb888::BLACK),
+        );
+        // the function sees the same coverage as the blending
+        for point in blended.affected_area().points() {
+            let expected = match blended.get_pixel(point) {
+                Some(Rgb888::BLACK) | None => None,
+                Some(Rgb888::WHITE) => Some(Rgb888::WHITE),
+                Some(_) => Some(Rgb888::RED),
+            };
+            assert_eq!(custom.get_pixel(point), expected);
+        }
+    }
 
-    //TODO
+    #[test]
+    fn glyph_colors_come_from_the_function() {
+        let draw = |color: fn(Point, usize) -> Rgb888| {
+            let style = FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color_fn(color)
+                .build();
+            let mut display = display();
+            Text::new("HH", Point::new(0, 20), style)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+        let columns = |display: &MockDisplay<Rgb888>, color| {
+            let area = display.affected_area();
+            let xs = area
+                .points()
+                .filter(|p| display.get_pixel(*p) == Some(color));
+            xs.map(|p| p.x)
+                .fold((i32::MAX, i32::MIN), |(a, b), x| (a.min(x), b.max(x)))
+        };
+
+        let by_glyph = draw(|_, glyph| match glyph {
+            0 => Rgb888::RED,
+            _ => Rgb888::BLUE,
+        });
+        let (red, blue) = (
+            columns(&by_glyph, Rgb888::RED),
+            columns(&by_glyph, Rgb888::BLUE),
+        );
+        assert!(red.0 <= red.1 && red.1 < blue.0 && blue.0 <= blue.1);
+
+        let by_pixel = draw(|point, _| match point.x % 2 {
+            0 => Rgb888::RED,
+            _ => Rgb888::GREEN,
+        });
+        let area = by_pixel.affected_area();
+        assert!(area.points().all(|p| match by_pixel.get_pixel(p) {
+            Some(Rgb888::RED) => p.x % 2 == 0,
+            Some(Rgb888::GREEN) => p.x % 2 == 1,
+            other => other.is_none(),
+        }));
+    }
+
+    #[test]
+    fn opacity_fades_glyphs_and_decorations() {
+        let draw = |opacity| {
+            let style = FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+                .underline()
+                .opacity(opacity)
+                .build();
+            let mut display = display();
+            Text::new("Hello", Point::new(0, 20), style)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+        let opaque = draw(255);
+        let faded = draw(128);
+        let area = opaque.affected_area();
+        assert_eq!(faded.affected_area(), area);
+        for point in area.points() {
+            let (opaque, faded) = (opaque.get_pixel(point), faded.get_pixel(point));
+            let expected = opaque.map(|color| color.blend(Rgb888::BLACK, 128));
+            assert_eq!(faded.map(|c| c.r()), expected.map(|c| c.r()), "{:?}", point);
+        }
+        assert!(faded
+            .affected_area()
+            .points()
+            .any(|p| faded.get_pixel(p) == Some(Rgb888::new(128, 128, 128))));
+        draw(0).assert_eq(&draw(255).map(|_| Rgb888::BLACK));
+    }
+
+    #[test]
+    fn dithered_edges_use_the_text_color() {
+        let draw = |anti_aliasing| {
+            let mut style = FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .build();
+            style.anti_aliasing = anti_aliasing;
+            let mut display = display();
+            Text::new("Hello", Point::new(0, 20), style)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+
+        let dithered = draw(AntiAliasing::Dithering);
+        let cutoff = draw(AntiAliasing::None);
+        let area = dithered.affected_area();
+        assert!(area.points().all(|p| match dithered.get_pixel(p) {
+            Some(color) => color == Rgb888::WHITE,
+            None => true,
+        }));
+        assert!(area
+            .points()
+            .any(|p| dithered.get_pixel(p) != cutoff.get_pixel(p)));
+    }
+
+    #[test]
+    fn coverage_is_quantized() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .coverage_levels(4)
+            .build();
+        let mut display = display();
+        Text::new("Hello", Point::new(0, 20), style)
+            .draw(&mut display)
+            .unwrap();
+
+        let area = display.affected_area();
+        let mut levels = [false; 4];
+        for color in area.points().filter_map(|p| display.get_pixel(p)) {
+            let level = [0, 85, 170, 255].iter().position(|l| *l == color.r());
+            levels[level.unwrap()] = true;
+        }
+        assert_eq!(levels, [true; 4]);
+    }
+
+    #[cfg(feature = "stats")]
+    #[test]
+    fn drawing_is_counted() {
+        let stats = SharedRenderStats::new(RenderStats::new());
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .glyph_cache(SharedGlyphCache::new(GlyphCache::new(64 * 1024)))
+            .stats(stats.clone())
+            .build();
+
+        Text::new("aa", Point::new(0, 0), style)
+            .draw(&mut display())
+            .unwrap();
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.draw_calls, 1);
+        assert_eq!(snapshot.cache_hits + snapshot.cache_misses, 2);
+        assert_eq!(snapshot.glyphs_rasterized, snapshot.cache_misses);
+        assert!(snapshot.pixels_emitted > 0);
+        assert!(global_stats().snapshot().draw_calls >= 1);
+
+        stats.reset();
+        assert_eq!(stats.snapshot(), StatsSnapshot::default());
+    }
+
+    #[test]
+    fn positioned_glyphs_match_text() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .anti_aliasing_color(Rgb888::BLACK)
+            .build();
+
+        let mut text = display();
+        Text::with_baseline("Hi!", Point::new(3, 4), style.clone(), Baseline::Top)
+            .draw(&mut text)
+            .unwrap();
+
+        let ascent = style.v_metrics().ascent;
+        let glyphs: Vec<_> = style
+            .font()
+            .layout(
+                "Hi!",
+                rusttype::Scale::uniform(20.0),
+                rusttype::point(0.0, ascent),
+            )
+            .collect();
+        let mut positioned = display();
+        style
+            .draw_positioned_glyphs(&glyphs, Point::new(3, 4), &mut positioned)
+            .unwrap();
+        positioned.assert_eq(&text);
+    }
+
+    #[test]
+    fn line_is_flushed_at_once() {
+        /// Counts the operations drawn on a mock display.
+        struct Operations(MockDisplay<Rgb888>, u32);
+
+        impl OriginDimensions for Operations {
+            fn size(&self) -> Size {
+                self.0.size()
+            }
+        }
+
+        impl DrawTarget for Operations {
+            type Color = Rgb888;
+            type Error = core::convert::Infallible;
+
+            fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Pixel<Rgb888>>,
+            {
+                self.1 += 1;
+                self.0.draw_iter(pixels)
+            }
+
+            fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Rgb888>,
+            {
+                self.1 += 1;
+                self.0.fill_contiguous(area, colors)
+            }
+
+            fn fill_solid(&mut self, area: &Rectangle, color: Rgb888) -> Result<(), Self::Error> {
+                self.1 += 1;
+                self.0.fill_solid(area, color)
+            }
+        }
+
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLUE)
+            .underline()
+            .strikethrough()
+            .build();
+        let mut flushed = Operations(display(), 0);
+        Text::with_baseline("Hi", Point::zero(), style.clone(), Baseline::Top)
+            .draw(&mut flushed)
+            .unwrap();
+        assert_eq!(flushed.1, 1);
+
+        // same pixels as drawing background, glyphs and decorations one after the other
+        let mut layered = display();
+        let status = style.draw_string_cancellable(
+            "Hi",
+            Point::zero(),
+            &AtomicBool::new(false),
+            &mut layered,
+        );
+        assert!(matches!(status, Ok(DrawStatus::Complete(_))));
+        flushed.0.assert_eq(&layered);
+    }
+
+    #[test]
+    fn rows_are_sent_as_spans() {
+        /// Records the areas filled on a mock display, and counts the other operations.
+        struct Spans(MockDisplay<Rgb888>, Vec<Rectangle>, u32);
+
+        impl OriginDimensions for Spans {
+            fn size(&self) -> Size {
+                self.0.size()
+            }
+        }
+
+        impl DrawTarget for Spans {
+            type Color = Rgb888;
+            type Error = core::convert::Infallible;
+
+            fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Pixel<Rgb888>>,
+            {
+                self.2 += 1;
+                self.0.draw_iter(pixels)
+            }
+
+            fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+            where
+                I: IntoIterator<Item = Rgb888>,
+            {
+                self.1.push(*area);
+                self.0.fill_contiguous(area, colors)
+            }
+        }
+
+        let builder = || {
+            FontTextStyleBuilder::new(font())
+                .font_size(16)
+                .text_color(Rgb888::WHITE)
+                .anti_aliasing_color(Rgb888::BLACK)
+                .shadow(Shadow::new(Rgb888::RED, Point::new(1, 1)))
+                .underline()
+        };
+        let mut expected = display();
+        Text::new("Jig", Point::new(2, 14), builder().build())
+            .draw(&mut expected)
+            .unwrap();
+        let mut spans = Spans(MockDisplay::new(), Vec::new(), 0);
+        Text::new(
+            "Jig",
+            Point::new(2, 14),
+            builder().contiguous_rows().build(),
+        )
+        .draw(&mut spans)
+        .unwrap();
+
+        // each pixel is sent once, the last one drawn
+        spans.0.assert_eq(&expected);
+        assert_eq!(spans.2, 0);
+        assert!(spans.1.iter().all(|area| area.size.height == 1));
+        let pixels = expected.affected_area().points();
+        assert!(spans.1.len() < pixels.filter(|p| expected.get_pixel(*p).is_some()).count() / 2);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn decomposed_text_is_normalized() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let draw = |text| {
+            let mut display = display();
+            Text::new(text, Point::new(0, 20), style.clone())
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+        draw("e\u{301}").assert_eq(&draw("\u{e9}"));
+    }
+
+    #[test]
+    fn soft_hyphens_are_invisible() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let draw = |text| {
+            let mut display = display();
+            let next = Text::new(text, Point::new(0, 20), style.clone())
+                .draw(&mut display)
+                .unwrap();
+            (display, next)
+        };
+        let (hyphenated, next) = draw("co\u{AD}op");
+        let (plain, plain_next) = draw("coop");
+        hyphenated.assert_eq(&plain);
+        assert_eq!(next, plain_next);
+    }
+
+    #[test]
+    fn special_spaces_have_their_width() {
+        let style = FontTextStyleBuilder::<Rgb888>::new(font())
+            .font_size(40)
+            .notdef_metrics(NotdefMetrics::default())
+            .build();
+        let width = |text: &str| {
+            style
+                .measure_string(text, Point::zero(), Baseline::Top)
+                .bounding_box
+                .size
+                .width as i32
+        };
+        let spaced = |space| width(&["1", space, "1"].concat()) - width("11");
+
+        // the font has en, em and thin spaces, but no narrow no-break or ideographic space
+        let em = spaced("\u{2003}");
+        assert!(em > spaced(" "));
+        assert!((spaced("\u{2002}") * 2 - em).abs() <= 1);
+        assert!((spaced("\u{202F}") - spaced("\u{2009}")).abs() <= 1);
+        assert!((spaced("\u{3000}") - em).abs() <= 1);
+        assert_eq!(spaced("\u{200B}"), 0);
+    }
+
+    #[test]
+    fn scrolled_text_is_clipped_to_the_viewport() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let viewport = Rectangle::new(Point::new(10, 5), Size::new(30, 20));
+
+        let mut scrolled = display();
+        let next = style
+            .draw_string_scrolled("HHHHHH", &viewport, 25, &mut scrolled)
+            .unwrap();
+        let area = scrolled.affected_area();
+        assert_eq!(area.intersection(&viewport), area);
+        // the left edge is clipped in the middle of a glyph
+        assert_eq!(area.top_left.x, viewport.top_left.x);
+
+        let mut text = display();
+        let end = Text::with_baseline("HHHHHH", Point::new(-15, 5), style, Baseline::Top)
+            .draw(&mut text)
+            .unwrap();
+        assert_eq!(next, end);
+        for point in area.points() {
+            assert_eq!(scrolled.get_pixel(point), text.get_pixel(point));
+        }
+    }
+
+    #[test]
+    fn blurred_shadow_is_larger() {
+        let draw = |shadow: Shadow<Rgb888>| {
+            let style = FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .background_color(Rgb888::BLACK)
+                .anti_aliasing_color(Rgb888::BLACK)
+                .shadow(shadow)
+                .build();
+            let mut display = display();
+            Text::with_baseline("x", Point::new(10, 10), style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            display
+                .affected_area()
+                .points()
+                .filter(|p| !matches!(display.get_pixel(*p), None | Some(Rgb888::BLACK)))
+                .count()
+        };
+
+        let hard = draw(Shadow::new(Rgb888::WHITE, Point::new(2, 2)));
+        let soft = draw(Shadow::new(Rgb888::WHITE, Point::new(2, 2)).blur(2));
+        assert!(hard > 0);
+        assert!(soft > hard);
+    }
+
+    #[test]
+    fn drop_shadow_is_a_hard_shadow() {
+        let builder = || {
+            FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+        };
+        let draw = |style: FontTextStyle<Rgb888>| {
+            let mut display = display();
+            Text::with_baseline("x", Point::new(10, 10), style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+
+        let shadowed = draw(builder().drop_shadow(Point::new(2, 2), Rgb888::RED).build());
+        let expected = draw(
+            builder()
+                .shadow(Shadow::new(Rgb888::RED, Point::new(2, 2)))
+                .build(),
+        );
+        shadowed.assert_eq(&expected);
+        let plain = draw(builder().build());
+        assert!(plain
+            .affected_area()
+            .points()
+            .any(|p| shadowed.get_pixel(p) != plain.get_pixel(p)));
+    }
+
+    #[test]
+    fn outline_surrounds_glyphs() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .outline(Outline::new(Rgb888::RED, 1))
+            .build();
+        let mut display = display();
+        Text::with_baseline("l", Point::new(10, 10), style, Baseline::Top)
+            .draw(&mut display)
+            .unwrap();
+
+        // the glyph is surrounded by red on its left
+        let area = display.affected_area();
+        let row = area.center().y;
+        let first = (area.top_left.x..area.top_left.x + area.size.width as i32)
+            .map(|x| display.get_pixel(Point::new(x, row)))
+            .find(|p| !matches!(p, None | Some(Rgb888::BLACK)));
+        let first = first.flatten().unwrap();
+        assert!(first.r() > first.g());
+
+        let stroked = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .stroke(Rgb888::RED, 1)
+            .build();
+        let mut stroked_display = self::display();
+        Text::with_baseline("l", Point::new(10, 10), stroked, Baseline::Top)
+            .draw(&mut stroked_display)
+            .unwrap();
+        stroked_display.assert_eq(&display);
+    }
+
+    #[test]
+    fn glow_fades_around_glyphs() {
+        let style = FontTextStyleBuilder::new(font())
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .glow(Glow::new(Rgb888::GREEN, 4))
+            .build();
+        let mut display = display();
+        Text::with_baseline("l", Point::new(10, 10), style.clone(), Baseline::Top)
+            .draw(&mut display)
+            .unwrap();
+
+        // the halo fades towards the glow radius, left of the glyph
+        let row = display.affected_area().center().y;
+        let pixel = |x| {
+            display
+                .get_pixel(Point::new(x, row))
+                .unwrap_or(Rgb888::BLACK)
+        };
+        let stem = (0..64).find(|x| pixel(*x).r() > 0).unwrap();
+        let halo = (0..64).find(|x| pixel(*x) != Rgb888::BLACK).unwrap();
+        assert_eq!(halo, stem - 4);
+        assert!(pixel(halo).g() < pixel(stem - 2).g());
+        assert!(pixel(stem - 2).g() <= pixel(stem - 1).g());
+        assert_eq!(pixel(stem - 1).r(), 0);
+
+        let ink = style.ink_bounding_box("l", Point::new(10, 10));
+        assert!(ink.contains(Point::new(halo, row)));
+    }
+
+    #[test]
+    fn inverse_video_swaps_colors() {
+        let builder = || {
+            FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+        };
+        let draw = |style| {
+            let mut display = display();
+            Text::with_baseline("Hi", Point::zero(), style, Baseline::Top)
+                .draw(&mut display)
+                .unwrap();
+            display
+        };
+
+        let normal = draw(builder().build());
+        let inverse = draw(builder().inverse().build());
+        let swapped = draw(
+            FontTextStyleBuilder::new(font())
+                .font_size(20)
+                .text_color(Rgb888::BLACK)
+                .background_color(Rgb888::WHITE)
+                .build(),
+        );
+        inverse.assert_eq(&swapped);
+        assert_ne!(inverse, normal);
+    }
 }
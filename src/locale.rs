@@ -0,0 +1,163 @@
+//! Locale sensitive text processing.
+//!
+//! The locale of a style changes how case transformations are applied (turkish dotted and dotless
+//! i for example) and, with the `opentype-layout` feature, enables the font's localized glyph
+//! forms (`locl` feature, like serbian and bulgarian cyrillic forms, see [`crate::gsub`]).
+
+use core::char::{ToLowercase, ToUppercase};
+
+#[cfg(feature = "opentype-layout")]
+use owned_ttf_parser::Tag;
+
+/// Language of the text, used for locale sensitive processing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    language: [u8; 3],
+}
+
+impl Locale {
+    /// Create a locale from a BCP 47 language tag like `"tr"` or `"sr-Latn"`.
+    ///
+    /// Only the language subtag is used, returns `None` if it is not a 2 or 3 letter code.
+    pub fn new(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next()?;
+        if !(2..=3).contains(&language.len()) || !language.bytes().all(|b| b.is_ascii_alphabetic())
+        {
+            return None;
+        }
+        let mut bytes = [0; 3];
+        for (b, l) in bytes.iter_mut().zip(language.bytes()) {
+            *b = l.to_ascii_lowercase();
+        }
+        Some(Locale { language: bytes })
+    }
+
+    /// The lowercase language subtag.
+    pub fn language(&self) -> &str {
+        let len = if self.language[2] == 0 { 2 } else { 3 };
+        // only ascii letters are stored
+        core::str::from_utf8(&self.language[..len]).unwrap_or_default()
+    }
+
+    /// Turkic languages have a dotted and a dotless i.
+    fn has_dotless_i(&self) -> bool {
+        matches!(self.language(), "tr" | "az" | "crh" | "tt" | "ba")
+    }
+
+    /// OpenType language system tag of this locale.
+    #[cfg(feature = "opentype-layout")]
+    pub(crate) fn opentype_tag(&self) -> Option<Tag> {
+        const TAGS: &[(&str, &[u8; 4])] = &[
+            ("ar", b"ARA "),
+            ("az", b"AZE "),
+            ("ba", b"BSH "),
+            ("bg", b"BGR "),
+            ("ca", b"CAT "),
+            ("crh", b"CRT "),
+            ("de", b"DEU "),
+            ("el", b"ELL "),
+            ("en", b"ENG "),
+            ("es", b"ESP "),
+            ("fr", b"FRA "),
+            ("he", b"IWR "),
+            ("hi", b"HIN "),
+            ("it", b"ITA "),
+            ("ja", b"JAN "),
+            ("kk", b"KAZ "),
+            ("ko", b"KOR "),
+            ("mk", b"MKD "),
+            ("mn", b"MNG "),
+            ("nl", b"NLD "),
+            ("pl", b"PLK "),
+            ("pt", b"PTG "),
+            ("ro", b"ROM "),
+            ("ru", b"RUS "),
+            ("sr", b"SRB "),
+            ("sv", b"SVE "),
+            ("th", b"THA "),
+            ("tr", b"TRK "),
+            ("tt", b"TAT "),
+            ("uk", b"UKR "),
+            ("vi", b"VIT "),
+            ("zh", b"ZHS "),
+        ];
+        TAGS.iter()
+            .find(|(language, _)| *language == self.language())
+            .map(|(_, tag)| Tag::from_bytes(tag))
+    }
+}
+
+/// Case transformation applied to the text before layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextTransform {
+    /// Render text as is (default).
+    #[default]
+    None,
+    /// Render text in upper case.
+    Uppercase,
+    /// Render text in lower case.
+    Lowercase,
+}
+
+impl TextTransform {
+    /// Transform a character, taking the locale into account.
+    pub(crate) fn apply(self, c: char, locale: Option<Locale>) -> CaseChars {
+        let dotless_i = locale.map(|l| l.has_dotless_i()).unwrap_or(false);
+        match self {
+            TextTransform::None => CaseChars::One(Some(c)),
+            TextTransform::Uppercase if dotless_i && c == 'i' => CaseChars::One(Some('\u{130}')),
+            TextTransform::Uppercase => CaseChars::Upper(c.to_uppercase()),
+            TextTransform::Lowercase if dotless_i && c == 'I' => CaseChars::One(Some('\u{131}')),
+            TextTransform::Lowercase if dotless_i && c == '\u{130}' => CaseChars::One(Some('i')),
+            TextTransform::Lowercase => CaseChars::Lower(c.to_lowercase()),
+        }
+    }
+}
+
+/// Characters resulting from a case transformation.
+pub(crate) enum CaseChars {
+    One(Option<char>),
+    Upper(ToUppercase),
+    Lower(ToLowercase),
+}
+
+impl Iterator for CaseChars {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            CaseChars::One(c) => c.take(),
+            CaseChars::Upper(chars) => chars.next(),
+            CaseChars::Lower(chars) => chars.next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transformed(text: &str, transform: TextTransform, locale: &str, expected: &str) -> bool {
+        text.chars()
+            .flat_map(|c| transform.apply(c, Locale::new(locale)))
+            .eq(expected.chars())
+    }
+
+    #[test]
+    fn locale_parsing() {
+        assert_eq!(Locale::new("sr-Latn").unwrap().language(), "sr");
+        assert_eq!(Locale::new("TR").unwrap().language(), "tr");
+        assert_eq!(Locale::new("crh").unwrap().language(), "crh");
+        assert_eq!(Locale::new("x"), None);
+        assert_eq!(Locale::new("1a"), None);
+    }
+
+    #[test]
+    fn turkish_case_mapping() {
+        use TextTransform::*;
+        assert!(transformed("istanbul", Uppercase, "tr", "İSTANBUL"));
+        assert!(transformed("istanbul", Uppercase, "en", "ISTANBUL"));
+        assert!(transformed("DİYARBAKIR", Lowercase, "tr", "diyarbakır"));
+        assert!(transformed("straße", Uppercase, "de", "STRASSE"));
+    }
+}
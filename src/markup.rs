@@ -0,0 +1,221 @@
+//! Inline markup.
+//!
+//! [`MarkupText`] draws a string carrying its own emphasis, so that translated strings can change
+//! it without code changes:
+//! * `*bold*` uses the bold style of the text, or the regular style if there is none,
+//! * `_underline_` underlines the text with the text color,
+//! * `{#FF0000}red{}` changes the text color until `{}`,
+//! * `\` escapes the next character, as in `\*`.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use embedded_graphics::{
+    pixelcolor::Rgb888,
+    prelude::*,
+    text::{
+        renderer::{CharacterStyle, TextRenderer},
+        Baseline, DecorationColor,
+    },
+};
+
+/// A run of text sharing the same markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Span<'a> {
+    pub(crate) text: &'a str,
+    pub(crate) bold: bool,
+    pub(crate) underline: bool,
+    pub(crate) color: Option<Rgb888>,
+}
+
+/// Iterator over the spans of a marked up string.
+pub(crate) struct Spans<'a> {
+    text: &'a str,
+    bold: bool,
+    underline: bool,
+    color: Option<Rgb888>,
+}
+
+impl<'a> Spans<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        Spans {
+            text,
+            bold: false,
+            underline: false,
+            color: None,
+        }
+    }
+
+    /// Parse a `{#RRGGBB}` or `{}` color tag at the start of the text, returns its length.
+    fn color_tag(&mut self) -> Option<usize> {
+        let end = self.text.find('}')?;
+        let tag = &self.text[1..end];
+        if tag.is_empty() {
+            self.color = None;
+        } else {
+            let hex = tag.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+            let rgb = u32::from_str_radix(hex, 16).ok()?;
+            self.color = Some(Rgb888::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8));
+        }
+        Some(end + 1)
+    }
+}
+
+impl<'a> Iterator for Spans<'a> {
+    type Item = Span<'a>;
+
+    fn next(&mut self) -> Option<Span<'a>> {
+        loop {
+            // apply the markup at the start of the text
+            let skip = match self.text.chars().next()? {
+                '*' => {
+                    self.bold = !self.bold;
+                    1
+                }
+                '_' => {
+                    self.underline = !self.underline;
+                    1
+                }
+                // an invalid tag is drawn as text
+                '{' => match self.color_tag() {
+                    Some(len) => len,
+                    None => break,
+                },
+                _ => break,
+            };
+            self.text = &self.text[skip..];
+        }
+
+        // the span starts with an escaped character or a '{' which is not a tag
+        let (start, search_from) = match self.text.strip_prefix('\\') {
+            Some(rest) => (1, 1 + rest.chars().next().map_or(0, char::len_utf8)),
+            None if self.text.starts_with('{') => (0, 1),
+            None => (0, 0),
+        };
+        let end = self.text[search_from..]
+            .find(['*', '_', '{', '\\'])
+            .map_or(self.text.len(), |i| search_from + i);
+        let span = Span {
+            text: &self.text[start..end],
+            bold: self.bold,
+            underline: self.underline,
+            color: self.color,
+        };
+        self.text = &self.text[end..];
+        Some(span)
+    }
+}
+
+/// Text with inline markup, drawn in a single call.
+///
+/// Lines are separated by `\n`, `position` is the top left corner of the first line.
+#[derive(Debug, Clone)]
+pub struct MarkupText<'a, C> {
+    /// The marked up text.
+    pub text: &'a str,
+
+    /// Position of the top left corner of the text.
+    pub position: Point,
+
+    /// Style of regular text.
+    pub style: FontTextStyle<C>,
+
+    /// Style of bold text, usually with a bold font.
+    pub bold_style: Option<FontTextStyle<C>>,
+}
+
+impl<'a, C> MarkupText<'a, C> {
+    /// Create a marked up text.
+    pub fn new(text: &'a str, position: Point, style: FontTextStyle<C>) -> Self {
+        MarkupText {
+            text,
+            position,
+            style,
+            bold_style: None,
+        }
+    }
+
+    /// Use `bold_style` for bold text.
+    pub fn bold_style(mut self, bold_style: FontTextStyle<C>) -> Self {
+        self.bold_style = Some(bold_style);
+        self
+    }
+}
+
+impl<C> Drawable for MarkupText<'_, C>
+where
+    C: ColorBlend + From<Rgb888> + Debug,
+{
+    type Color = C;
+    type Output = Point;
+
+    /// Draw the text, returns the position following the last character.
+    fn draw<D>(&self, target: &mut D) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let mut position = self.position;
+        for span in Spans::new(self.text) {
+            let base = match (&self.bold_style, span.bold) {
+                (Some(bold), true) => bold,
+                _ => &self.style,
+            };
+            let mut style = base.clone();
+            if span.underline {
+                style.set_underline_color(DecorationColor::TextColor);
+            }
+            if let Some(color) = span.color {
+                style.set_text_color(Some(color.into()));
+            }
+
+            let mut lines = span.text.split('\n');
+            if let Some(line) = lines.next() {
+                position = style.draw_string(line, position, Baseline::Top, target)?;
+            }
+            for line in lines {
+                position = Point::new(
+                    self.position.x,
+                    position.y + self.style.line_height() as i32,
+                );
+                position = style.draw_string(line, position, Baseline::Top, target)?;
+            }
+        }
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::vec::Vec;
+
+    #[test]
+    fn markup_is_parsed() {
+        let spans: Vec<_> = Spans::new("a *b _c_* {#FF0000}d{} \\*e\\").collect();
+        let red = Some(Rgb888::new(255, 0, 0));
+        let span = |text, bold, underline, color| Span {
+            text,
+            bold,
+            underline,
+            color,
+        };
+        assert_eq!(
+            spans,
+            [
+                span("a ", false, false, None),
+                span("b ", true, false, None),
+                span("c", true, true, None),
+                span(" ", false, false, None),
+                span("d", false, false, red),
+                span(" ", false, false, None),
+                span("*e", false, false, None),
+                span("", false, false, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_tags_are_text() {
+        let spans: Vec<_> = Spans::new("{x} {").map(|span| span.text).collect();
+        assert_eq!(spans.concat(), "{x} {");
+    }
+}
@@ -0,0 +1,174 @@
+//! Vertical font metrics.
+//!
+//! Fonts store their ascent, descent and line gap in two places which often disagree: the `hhea`
+//! table (used by macOS) and the typographic metrics of the `OS/2` table (used by Windows when the
+//! font sets its `USE_TYPO_METRICS` flag). [`MetricsSource`] selects which one drives the
+//! baseline position and line spacing, and [`MetricsOverride`] replaces broken values.
+//! [`FontMetrics`] gives the resulting values at the size of a style.
+
+use crate::{layout::face, FontTextStyle};
+
+use embedded_graphics::pixelcolor::PixelColor;
+use rusttype::{Font, VMetrics};
+
+/// Font table providing the ascent, descent and line gap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetricsSource {
+    /// `OS/2` typographic metrics if the font sets the `USE_TYPO_METRICS` flag, `hhea` otherwise
+    /// (default).
+    #[default]
+    Auto,
+    /// Metrics of the `hhea` table.
+    Hhea,
+    /// Typographic metrics of the `OS/2` table, `hhea` is used if the font has no `OS/2` table.
+    Typographic,
+    /// Windows metrics of the `OS/2` table (`usWinAscent` and `usWinDescent`), without line gap.
+    Windows,
+}
+
+/// Replacement values for the vertical metrics of a font.
+///
+/// Like CSS `ascent-override` and friends, values are fractions of the font size, `None` keeps
+/// the font value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct MetricsOverride {
+    /// Distance from the baseline to the top of the line.
+    pub ascent: Option<f32>,
+    /// Distance from the baseline to the bottom of the line, positive below the baseline.
+    pub descent: Option<f32>,
+    /// Gap between the bottom of a line and the top of the next one.
+    pub line_gap: Option<f32>,
+}
+
+/// Vertical metrics of a style, in pixels.
+///
+/// Distances are measured from the baseline, upwards for the ascent and heights and downwards
+/// for the descent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FontMetrics {
+    /// Distance from the baseline to the top of the line.
+    pub ascent: f32,
+    /// Distance from the baseline to the bottom of the line.
+    pub descent: f32,
+    /// Gap between the bottom of a line and the top of the next one.
+    pub line_gap: f32,
+    /// Height of lowercase letters like `x`.
+    pub x_height: f32,
+    /// Height of capital letters like `H`.
+    pub cap_height: f32,
+}
+
+impl MetricsSource {
+    /// Unscaled metrics of a font, in font units.
+    pub(crate) fn unscaled(self, font: &Font<'_>) -> VMetrics {
+        let face = face(font);
+        let hhea = face.tables().hhea;
+        let os2 = face.tables().os2;
+        let (ascent, descent, line_gap) = match (self, os2) {
+            (MetricsSource::Auto, _) => (face.ascender(), face.descender(), face.line_gap()),
+            (MetricsSource::Typographic, Some(os2)) => (
+                os2.typographic_ascender(),
+                os2.typographic_descender(),
+                os2.typographic_line_gap(),
+            ),
+            (MetricsSource::Windows, Some(os2)) => {
+                (os2.windows_ascender(), os2.windows_descender(), 0)
+            }
+            _ => (hhea.ascender, hhea.descender, hhea.line_gap),
+        };
+        VMetrics {
+            ascent: ascent as f32,
+            descent: descent as f32,
+            line_gap: line_gap as f32,
+        }
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Vertical metrics of the main font in pixels, at the style font size.
+    ///
+    /// The font size is the height from descent to ascent of the font's default metrics, as in
+    /// rusttype, whatever the metrics source.
+    pub(crate) fn v_metrics(&self) -> VMetrics {
+        let size = self.font_size;
+        let scale = self.font.scale_for_pixel_height(size);
+        let metrics = self.metrics_source.unscaled(&self.font) * scale;
+        let overrides = &self.metrics_override;
+        VMetrics {
+            ascent: overrides.ascent.map_or(metrics.ascent, |a| a * size),
+            descent: overrides.descent.map_or(metrics.descent, |d| -d * size),
+            line_gap: overrides.line_gap.map_or(metrics.line_gap, |g| g * size),
+        }
+    }
+}
+
+impl<C: PixelColor> FontTextStyle<C> {
+    /// Metrics of the main font at the style size, with the metrics source and overrides
+    /// applied.
+    ///
+    /// The x-height and cap height come from the `OS/2` table, or from the outlines of `x` and
+    /// `H` when the font doesn't have them.
+    pub fn metrics(&self) -> FontMetrics {
+        let v_metrics = self.v_metrics();
+        let scale = self.font.scale_for_pixel_height(self.font_size);
+        let face = face(&self.font);
+        let height = |table: Option<i16>, c: char| {
+            let height = table.filter(|h| *h > 0).map(f32::from).or_else(|| {
+                let id = owned_ttf_parser::GlyphId(self.font.glyph(c).id().0);
+                face.glyph_bounding_box(id)
+                    .map(|bounding_box| f32::from(bounding_box.y_max))
+            });
+            height.unwrap_or(0.0) * scale
+        };
+        FontMetrics {
+            ascent: v_metrics.ascent,
+            descent: -v_metrics.descent,
+            line_gap: v_metrics.line_gap,
+            x_height: height(face.x_height(), 'x'),
+            cap_height: height(face.capital_height(), 'H'),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_sources() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        // rusttype uses the default selection
+        assert_eq!(
+            MetricsSource::Auto.unscaled(&font),
+            font.v_metrics_unscaled()
+        );
+        // Roboto has consistent tables
+        let typographic = MetricsSource::Typographic.unscaled(&font);
+        assert_eq!(MetricsSource::Hhea.unscaled(&font), typographic);
+        let windows = MetricsSource::Windows.unscaled(&font);
+        assert_eq!((windows.ascent, windows.descent), (2146.0, -555.0));
+    }
+
+    #[test]
+    fn style_metrics() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = crate::FontTextStyleBuilder::<embedded_graphics::pixelcolor::Rgb888>::new(font)
+            .font_size(24)
+            .build();
+        let metrics = style.metrics();
+
+        // the font size spans from descent to ascent
+        assert!((metrics.ascent + metrics.descent - 24.0).abs() < 0.01);
+        assert!(metrics.descent > 0.0);
+        assert!(0.0 < metrics.x_height && metrics.x_height < metrics.cap_height);
+        assert!(metrics.cap_height < metrics.ascent);
+
+        // heights match the outlines
+        let top = |c| {
+            let glyph = style.font().glyph(c).scaled(rusttype::Scale::uniform(24.0));
+            -glyph.exact_bounding_box().unwrap().min.y
+        };
+        assert!((metrics.x_height - top('x')).abs() < 0.5);
+        assert!((metrics.cap_height - top('H')).abs() < 0.5);
+    }
+}
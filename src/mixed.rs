@@ -0,0 +1,122 @@
+//! Segments of text in several styles on a shared baseline.
+
+use crate::{envelope, stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::Rectangle,
+    text::{
+        renderer::{TextMetrics, TextRenderer},
+        Baseline,
+    },
+};
+
+/// Segments of text drawn one after the other on the same alphabetic baseline.
+///
+/// Each segment has its own style, a value in a large font followed by its unit in a small font
+/// sits on the same baseline without computing offsets.
+#[derive(Debug, Clone)]
+pub struct MixedText<'a, C> {
+    /// Text and style of each segment.
+    pub segments: &'a [(&'a str, &'a FontTextStyle<C>)],
+
+    /// Start of the baseline.
+    pub position: Point,
+}
+
+impl<'a, C> MixedText<'a, C> {
+    /// Create segments of text starting on the baseline at `position`.
+    pub fn new(segments: &'a [(&'a str, &'a FontTextStyle<C>)], position: Point) -> Self {
+        MixedText { segments, position }
+    }
+}
+
+impl<C> MixedText<'_, C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Metrics of the whole text: the bounding box of all segments and the position following
+    /// the last one.
+    pub fn measure(&self) -> TextMetrics {
+        let mut bounding_box: Option<Rectangle> = None;
+        let mut position = self.position;
+        for (text, style) in self.segments {
+            let metrics = style.measure_string(text, position, Baseline::Alphabetic);
+            if !metrics.bounding_box.is_zero_sized() {
+                bounding_box = Some(match bounding_box {
+                    Some(bounding_box) => envelope(&bounding_box, &metrics.bounding_box),
+                    None => metrics.bounding_box,
+                });
+            }
+            position = metrics.next_position;
+        }
+        TextMetrics {
+            bounding_box: bounding_box
+                .unwrap_or_else(|| Rectangle::new(self.position, Size::zero())),
+            next_position: position,
+        }
+    }
+}
+
+impl<C> Drawable for MixedText<'_, C>
+where
+    C: ColorBlend + Debug,
+{
+    type Color = C;
+    type Output = TextMetrics;
+
+    /// Draw the segments, returns the metrics of the whole text.
+    fn draw<D>(&self, target: &mut D) -> Result<TextMetrics, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let mut position = self.position;
+        for (text, style) in self.segments {
+            position = style.draw_string(text, position, Baseline::Alphabetic, target)?;
+        }
+        Ok(self.measure())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+    use rusttype::Font;
+
+    #[test]
+    fn segments_share_the_baseline() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let large = FontTextStyleBuilder::new(font.clone())
+            .font_size(32)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let small = FontTextStyleBuilder::new(font)
+            .font_size(12)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let position = Point::new(0, 30);
+        let segments = [("42", &large), ("km", &small)];
+        let text = MixedText::new(&segments, position);
+
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        let metrics = text.draw(&mut display).unwrap();
+
+        // digits and lowercase letters without descender end on the baseline
+        assert_eq!(
+            display.affected_area().bottom_right().unwrap().y,
+            position.y - 1
+        );
+        let large_end = large
+            .measure_string("42", position, Baseline::Alphabetic)
+            .next_position;
+        let small_end = small.measure_string("km", large_end, Baseline::Alphabetic);
+        assert_eq!(metrics.next_position, small_end.next_position);
+        assert_eq!(metrics.next_position.y, position.y);
+        assert_eq!(metrics, text.measure());
+        let top = large.measure_string("42", position, Baseline::Alphabetic);
+        assert_eq!(metrics.bounding_box.top_left, top.bounding_box.top_left);
+    }
+}
@@ -0,0 +1,126 @@
+//! Progress and cancellation of long draws.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use core::sync::atomic::AtomicBool;
+use embedded_graphics::prelude::*;
+
+/// Result of a cancellable draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawStatus {
+    /// The whole text was drawn, with the position following the last character.
+    Complete(Point),
+    /// Drawing was cancelled after the given number of glyphs, decorations are not drawn.
+    Cancelled {
+        /// Number of glyphs drawn.
+        glyphs: usize,
+    },
+}
+
+/// Callback called every `every` glyphs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Progress {
+    every: u32,
+    callback: fn(u32),
+}
+
+impl Progress {
+    pub(crate) fn new(every: u32, callback: fn(u32)) -> Self {
+        Progress {
+            every: every.max(1),
+            callback,
+        }
+    }
+}
+
+impl<C> FontTextStyle<C> {
+    /// Report that `glyphs` glyphs of the current draw are done.
+    pub(crate) fn report_progress(&self, glyphs: u32) {
+        if let Some(progress) = &self.progress {
+            if glyphs.is_multiple_of(progress.every) {
+                (progress.callback)(glyphs);
+            }
+        }
+    }
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw a line of text like `draw_string`, stopping as soon as `cancel` is set.
+    ///
+    /// The flag is checked before each glyph, so that a render preempted by an interrupt or
+    /// another task stops within one glyph. The background, shadow and outline are drawn before
+    /// the first glyph, even if the draw is cancelled.
+    pub fn draw_string_cancellable<D>(
+        &self,
+        text: &str,
+        position: Point,
+        cancel: &AtomicBool,
+        target: &mut D,
+    ) -> Result<DrawStatus, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        self.draw_line(text, position, Some(cancel), target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use core::sync::atomic::{AtomicU32, Ordering};
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888, text::Text};
+    use rusttype::Font;
+
+    static REPORTS: AtomicU32 = AtomicU32::new(0);
+    static LAST: AtomicU32 = AtomicU32::new(0);
+
+    #[test]
+    fn progress_is_reported() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(10)
+            .text_color(Rgb888::WHITE)
+            .progress(2, |glyphs| {
+                REPORTS.fetch_add(1, Ordering::Relaxed);
+                LAST.store(glyphs, Ordering::Relaxed);
+            })
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        display.set_allow_overdraw(true);
+        Text::new("abcde", Point::new(0, 10), style)
+            .draw(&mut display)
+            .unwrap();
+
+        assert_eq!(REPORTS.load(Ordering::Relaxed), 2);
+        assert_eq!(LAST.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn drawing_is_cancelled() {
+        static CANCEL: AtomicBool = AtomicBool::new(false);
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(10)
+            .text_color(Rgb888::WHITE)
+            .progress(3, |_| CANCEL.store(true, Ordering::Relaxed))
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+
+        let status = style.draw_string_cancellable("abcde", Point::zero(), &CANCEL, &mut display);
+        assert_eq!(status, Ok(DrawStatus::Cancelled { glyphs: 3 }));
+
+        CANCEL.store(false, Ordering::Relaxed);
+        let style = FontTextStyle {
+            progress: None,
+            ..style
+        };
+        let status = style.draw_string_cancellable("abcde", Point::zero(), &CANCEL, &mut display);
+        assert!(matches!(status, Ok(DrawStatus::Complete(_))));
+    }
+}
@@ -0,0 +1,132 @@
+//! Antialiasing over the pixels already drawn.
+//!
+//! `DrawTarget` is write only, so antialiasing normally blends with a color guessed in advance.
+//! Targets keeping their pixels in memory can implement [`ReadPixel`], text drawn with
+//! [`FontTextStyle::draw_string_read_back`] then blends with what is actually under each glyph.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use embedded_graphics::{
+    framebuffer::Framebuffer, image::GetPixel, mock_display::MockDisplay, prelude::*,
+    text::Baseline,
+};
+
+/// Draw target whose pixels can be read back.
+pub trait ReadPixel: DrawTarget {
+    /// Color of the pixel at `point`, `None` if it is unknown or outside the target.
+    fn pixel(&self, point: Point) -> Option<Self::Color>;
+}
+
+impl<C: PixelColor> ReadPixel for MockDisplay<C> {
+    fn pixel(&self, point: Point) -> Option<C> {
+        self.get_pixel(point)
+    }
+}
+
+impl<C, R, BO, const WIDTH: usize, const HEIGHT: usize, const N: usize> ReadPixel
+    for Framebuffer<C, R, BO, WIDTH, HEIGHT, N>
+where
+    C: PixelColor,
+    Self: DrawTarget<Color = C> + GetPixel<Color = C>,
+{
+    fn pixel(&self, point: Point) -> Option<C> {
+        GetPixel::pixel(self, point)
+    }
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Draw `text` like `draw_string`, blending glyph edges with the pixels of the target.
+    ///
+    /// With [`AntiAliasing::ReadBack`](crate::AntiAliasing::ReadBack), the pixels under each
+    /// line are read before it is drawn, other antialiasing modes draw like `draw_string`.
+    ///
+    /// Returns the position following the last character.
+    pub fn draw_string_read_back<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: ReadPixel<Color = C>,
+    {
+        let lines = self
+            .lines(text)
+            .map(|(line, offset)| (line, self.layout(line), offset));
+        self.draw_lines(
+            lines,
+            position,
+            baseline,
+            |target: &D, point| target.pixel(point),
+            target,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{pixelcolor::Gray8, text::renderer::TextRenderer};
+    use rusttype::Font;
+
+    #[test]
+    fn edges_blend_with_the_target() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Gray8::WHITE)
+            .anti_aliasing_read_back()
+            .build();
+        // dark on the left, light on the right
+        let backdrop = || {
+            let mut display = MockDisplay::new();
+            display.set_allow_overdraw(true);
+            let area = display.bounding_box();
+            display
+                .draw_iter(
+                    area.points()
+                        .map(|p| Pixel(p, Gray8::new(if p.x < 32 { 0 } else { 200 }))),
+                )
+                .unwrap();
+            display
+        };
+
+        let mut display = backdrop();
+        let next = style
+            .draw_string_read_back("OO", Point::new(20, 4), Baseline::Top, &mut display)
+            .unwrap();
+        let mut expected = MockDisplay::new();
+        assert_eq!(
+            next,
+            style
+                .draw_string("OO", Point::new(20, 4), Baseline::Top, &mut expected)
+                .unwrap()
+        );
+
+        let (before, mut edges) = (backdrop(), [false; 2]);
+        for point in display.bounding_box().points() {
+            let (old, new) = (before.pixel(point).unwrap(), display.pixel(point).unwrap());
+            if new != old && new != Gray8::WHITE {
+                // partially covered pixels lie between the text and what was under them
+                assert!(new.luma() > old.luma(), "{:?}", point);
+                edges[(old.luma() > 0) as usize] = true;
+            }
+        }
+        assert_eq!(edges, [true, true]);
+
+        // without a backdrop, the edges are cut off
+        let mut plain = backdrop();
+        style
+            .draw_string("OO", Point::new(20, 4), Baseline::Top, &mut plain)
+            .unwrap();
+        for point in plain.bounding_box().points() {
+            let (old, new) = (before.pixel(point).unwrap(), plain.pixel(point).unwrap());
+            assert!(new == old || new == Gray8::WHITE);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! Reloading of font files.
+//!
+//! A [`FontWatcher`] reloads a font when its file is modified, so that a style running in a
+//! simulator picks up a font being tuned without restarting.
+
+use crate::FontTextStyle;
+
+use embedded_graphics::prelude::PixelColor;
+use rusttype::Font;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Font file reloaded when it changes.
+#[derive(Debug, Clone)]
+pub struct FontWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl FontWatcher {
+    /// Watch the font file at `path`, the first poll loads it.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FontWatcher {
+            path: path.into(),
+            modified: None,
+        }
+    }
+
+    /// Path of the watched file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the font, whether it changed or not.
+    pub fn load(&mut self) -> io::Result<Font<'static>> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+        let font = Font::try_from_vec(fs::read(&self.path)?).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a valid font", self.path.display()),
+            )
+        })?;
+        self.modified = Some(modified);
+        debug!("loaded font {}", self.path.display());
+        Ok(font)
+    }
+
+    /// Load the font if the file was modified since the last load.
+    ///
+    /// A file being written may not be a valid font yet, the error is returned and the next poll
+    /// tries again.
+    pub fn poll(&mut self) -> io::Result<Option<Font<'static>>> {
+        let modified = fs::metadata(&self.path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(None);
+        }
+        self.load().map(Some)
+    }
+
+    /// Replace the main font of `style` if the file was modified, returns whether it was.
+    pub fn reload<C: PixelColor>(&mut self, style: &mut FontTextStyle<C>) -> io::Result<bool> {
+        match self.poll()? {
+            Some(font) => {
+                style.set_font(font);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::pixelcolor::Rgb888;
+    use std::time::Duration;
+
+    #[test]
+    fn modified_fonts_are_reloaded() {
+        let path = std::env::temp_dir().join(format!("embedded-ttf-{}.ttf", std::process::id()));
+        let roboto = include_bytes!("../assets/Roboto-Regular.ttf");
+        fs::write(&path, roboto).unwrap();
+
+        let mut watcher = FontWatcher::new(&path);
+        let mut style = FontTextStyleBuilder::<Rgb888>::new(watcher.load().unwrap()).build();
+        assert!(!watcher.reload(&mut style).unwrap());
+
+        // an invalid file is reported and retried
+        fs::write(&path, b"not a font").unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+        assert!(watcher.reload(&mut style).is_err());
+
+        fs::write(&path, roboto).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(2))
+            .unwrap();
+        assert!(watcher.reload(&mut style).unwrap());
+        assert!(!watcher.reload(&mut style).unwrap());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,419 @@
+//! Text rotated by quarter turns.
+//!
+//! Rotated text is drawn upright into a [`RotatedTarget`], which turns every pixel around the
+//! text position before passing it to the real target. Backgrounds, effects and decorations
+//! rotate with the glyphs, and quarter turns keep pixels on the grid, so the result is exactly
+//! the upright text turned.
+//!
+//! The same target discards the pixels outside the clip area of the style, so that text crossing
+//! the edges of the display is cut cleanly, whatever its position.
+
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+/// Clockwise rotation of the text around its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// Upright text (default).
+    #[default]
+    Deg0,
+    /// Quarter turn clockwise, the text reads from top to bottom.
+    Deg90,
+    /// Half turn, the text is upside down.
+    Deg180,
+    /// Quarter turn counterclockwise, the text reads from bottom to top.
+    Deg270,
+}
+
+impl Rotation {
+    /// Rotated offset, saturating at the limits of the coordinates.
+    pub(crate) fn offset(self, offset: Point) -> Point {
+        let (x, y) = (offset.x, offset.y);
+        match self {
+            Rotation::Deg0 => offset,
+            Rotation::Deg90 => Point::new(y.saturating_neg(), x),
+            Rotation::Deg180 => Point::new(x.saturating_neg(), y.saturating_neg()),
+            Rotation::Deg270 => Point::new(y, x.saturating_neg()),
+        }
+    }
+
+    /// Rotation undoing this one.
+    fn inverse(self) -> Self {
+        match self {
+            Rotation::Deg90 => Rotation::Deg270,
+            Rotation::Deg270 => Rotation::Deg90,
+            rotation => rotation,
+        }
+    }
+
+    /// Pixel covering the rotated area of the pixel at `offset` from the center of rotation,
+    /// `None` if the result overflows.
+    fn pixel(self, offset: Point) -> Option<Point> {
+        let (x, y) = (offset.x, offset.y);
+        let (x, y) = match self {
+            Rotation::Deg0 => (x, y),
+            Rotation::Deg90 => (y.checked_neg()?.checked_sub(1)?, x),
+            Rotation::Deg180 => (
+                x.checked_neg()?.checked_sub(1)?,
+                y.checked_neg()?.checked_sub(1)?,
+            ),
+            Rotation::Deg270 => (y, x.checked_neg()?.checked_sub(1)?),
+        };
+        Some(Point::new(x, y))
+    }
+
+    /// Rectangle covering `area` rotated around `center`, cut to the coordinates that can be
+    /// represented.
+    pub(crate) fn rectangle(self, area: &Rectangle, center: Point) -> Rectangle {
+        let (left, top) = (area.top_left.x as i64, area.top_left.y as i64);
+        let (right, bottom) = (left + area.size.width as i64, top + area.size.height as i64);
+        let (cx, cy) = (center.x as i64, center.y as i64);
+        // rotate the edges, the exclusive end of the area becomes its start when turned
+        let (x0, y0, x1, y1) = match self {
+            Rotation::Deg0 => (left, top, right, bottom),
+            Rotation::Deg90 => (
+                cx + cy - bottom,
+                cy - cx + left,
+                cx + cy - top,
+                cy - cx + right,
+            ),
+            Rotation::Deg180 => (2 * cx - right, 2 * cy - bottom, 2 * cx - left, 2 * cy - top),
+            Rotation::Deg270 => (
+                cx - cy + top,
+                cy + cx - right,
+                cx - cy + bottom,
+                cy + cx - left,
+            ),
+        };
+        representable(x0, y0, x1, y1)
+    }
+}
+
+/// Rectangle from `(x0, y0)` included to `(x1, y1)` excluded, cut to the coordinates that can
+/// be represented, so that its end can be computed without overflowing.
+pub(crate) fn representable(x0: i64, y0: i64, x1: i64, y1: i64) -> Rectangle {
+    let clamp = |v: i64| v.clamp(i32::MIN as i64, i32::MAX as i64);
+    let (x0, y0) = (clamp(x0), clamp(y0));
+    let (x1, y1) = (clamp(x1).max(x0), clamp(y1).max(y0));
+    Rectangle::new(
+        Point::new(x0 as i32, y0 as i32),
+        Size::new((x1 - x0) as u32, (y1 - y0) as u32),
+    )
+}
+
+/// Pixel covering the area of the pixel at `point` turned around `center`, `None` if the result
+/// overflows.
+fn turned_point(point: Point, center: Point, rotation: Rotation) -> Option<Point> {
+    let offset = Point::new(
+        point.x.checked_sub(center.x)?,
+        point.y.checked_sub(center.y)?,
+    );
+    let pixel = rotation.pixel(offset)?;
+    Some(Point::new(
+        pixel.x.checked_add(center.x)?,
+        pixel.y.checked_add(center.y)?,
+    ))
+}
+
+/// `area` moved by `offset`, cut to the coordinates that can be represented.
+pub(crate) fn translate(area: &Rectangle, offset: Point) -> Rectangle {
+    let x = area.top_left.x as i64 + offset.x as i64;
+    let y = area.top_left.y as i64 + offset.y as i64;
+    representable(
+        x,
+        y,
+        x + area.size.width as i64,
+        y + area.size.height as i64,
+    )
+}
+
+/// `point` moved by `offset`, saturating at the limits of the coordinates.
+pub(crate) fn offset(point: Point, offset: Point) -> Point {
+    Point::new(
+        point.x.saturating_add(offset.x),
+        point.y.saturating_add(offset.y),
+    )
+}
+
+/// Draw target turning everything drawn around `center`, then dropping what falls outside
+/// `clip`, in the coordinates of the target.
+pub(crate) struct RotatedTarget<'a, D> {
+    target: &'a mut D,
+    center: Point,
+    rotation: Rotation,
+    clip: Option<Rectangle>,
+}
+
+impl<'a, D> RotatedTarget<'a, D> {
+    pub(crate) fn new(
+        target: &'a mut D,
+        center: Point,
+        rotation: Rotation,
+        clip: Option<Rectangle>,
+    ) -> Self {
+        RotatedTarget {
+            target,
+            center,
+            rotation,
+            clip,
+        }
+    }
+
+    /// The target turned.
+    pub(crate) fn target(&self) -> &D {
+        self.target
+    }
+
+    /// Pixel of the target `point` is drawn to, `None` if it is too far away to be addressed.
+    pub(crate) fn target_point(&self, point: Point) -> Option<Point> {
+        turned_point(point, self.center, self.rotation)
+    }
+}
+
+impl<D: DrawTarget> Dimensions for RotatedTarget<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        self.rotation
+            .inverse()
+            .rectangle(&self.target.bounding_box(), self.center)
+    }
+}
+
+impl<D: DrawTarget> DrawTarget for RotatedTarget<'_, D> {
+    type Color = D::Color;
+    type Error = D::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (center, rotation, clip) = (self.center, self.rotation, self.clip);
+        // pixels too far away to be addressed are outside any target
+        let target_point = move |point: Point| {
+            let point = turned_point(point, center, rotation)?;
+            match clip {
+                Some(clip) => clip.contains(point).then_some(point),
+                None => Some(point),
+            }
+        };
+        self.target.draw_iter(
+            pixels.into_iter().filter_map(|Pixel(point, color)| {
+                target_point(point).map(|point| Pixel(point, color))
+            }),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let inside = match self.clip {
+            Some(clip) => clip.intersection(area) == *area,
+            None => true,
+        };
+        match self.rotation {
+            Rotation::Deg0 if inside => self.target.fill_contiguous(area, colors),
+            // rows of the area become columns, the colors are not contiguous anymore, and the
+            // colors of clipped pixels must be skipped
+            _ => self.draw_iter(
+                area.points()
+                    .zip(colors)
+                    .map(|(point, color)| Pixel(point, color)),
+            ),
+        }
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let mut area = self.rotation.rectangle(area, self.center);
+        if let Some(clip) = self.clip {
+            area = area.intersection(&clip);
+        }
+        match area.is_zero_sized() {
+            true => Ok(()),
+            false => self.target.fill_solid(&area, color),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FontTextStyle, FontTextStyleBuilder};
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn rectangles_turn_with_their_pixels() {
+        let area = Rectangle::new(Point::new(12, 11), Size::new(3, 2));
+        let center = Point::new(10, 10);
+        for rotation in [
+            Rotation::Deg0,
+            Rotation::Deg90,
+            Rotation::Deg180,
+            Rotation::Deg270,
+        ] {
+            let rotated = rotation.rectangle(&area, center);
+            for point in area.points() {
+                assert!(rotated.contains(rotation.pixel(point - center).unwrap() + center));
+            }
+            assert_eq!(rotated.size.width * rotated.size.height, 6);
+            assert_eq!(rotation.inverse().rectangle(&rotated, center), area);
+        }
+    }
+
+    #[test]
+    fn rotated_text_is_turned() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let builder = || {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(12)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+                .underline()
+        };
+        let upright = builder().build();
+        let turned = builder().rotation(Rotation::Deg90).build();
+        let position = Point::new(20, 5);
+
+        let mut expected = MockDisplay::new();
+        let next = upright
+            .draw_string("Hi", Point::zero(), Baseline::Top, &mut expected)
+            .unwrap();
+        let mut display = MockDisplay::new();
+        let turned_next = turned
+            .draw_string("Hi", position, Baseline::Top, &mut display)
+            .unwrap();
+        assert_eq!(turned_next, position + Point::new(0, next.x));
+        for point in expected.affected_area().points() {
+            let rotated = Rotation::Deg90.pixel(point).unwrap() + position;
+            assert_eq!(display.get_pixel(rotated), expected.get_pixel(point));
+        }
+
+        let metrics = turned.measure_string("Hi", position, Baseline::Top);
+        let upright_box = upright
+            .measure_string("Hi", Point::zero(), Baseline::Top)
+            .bounding_box;
+        assert_eq!(
+            metrics.bounding_box,
+            Rotation::Deg90
+                .rectangle(&upright_box, Point::zero())
+                .translate(position)
+        );
+        assert_eq!(metrics.next_position, turned_next);
+    }
+
+    #[test]
+    fn text_is_clipped() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let builder = |rotation| {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(16)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+                .underline()
+                .rotation(rotation)
+        };
+        let draw = |style: &FontTextStyle<Rgb888>, position, display: &mut MockDisplay<_>| {
+            style
+                .draw_string("Hello", position, Baseline::Top, display)
+                .unwrap();
+        };
+
+        for (rotation, position) in [
+            (Rotation::Deg0, Point::new(-5, -3)),
+            (Rotation::Deg90, Point::new(8, 50)),
+            (Rotation::Deg180, Point::new(20, 5)),
+            (Rotation::Deg270, Point::new(-4, 30)),
+        ] {
+            // only the pixels inside the display, without drawing outside of it
+            let mut expected = MockDisplay::new();
+            expected.set_allow_out_of_bounds_drawing(true);
+            draw(&builder(rotation).build(), position, &mut expected);
+            let mut display = MockDisplay::new();
+            let area = display.bounding_box();
+            draw(
+                &builder(rotation).clip(area).build(),
+                position,
+                &mut display,
+            );
+            display.assert_eq(&expected);
+            assert!(!display.affected_area().is_zero_sized());
+
+            // a clip area cutting through the text
+            let clip = Rectangle::new(Point::new(3, 4), Size::new(9, 7));
+            let mut display = MockDisplay::new();
+            draw(
+                &builder(rotation).clip(clip).build(),
+                position,
+                &mut display,
+            );
+            for point in area.points() {
+                let inside = expected.get_pixel(point).filter(|_| clip.contains(point));
+                assert_eq!(display.get_pixel(point), inside, "{:?}", point);
+            }
+        }
+    }
+
+    /// Target accepting and discarding any pixel.
+    struct NullTarget;
+
+    impl Dimensions for NullTarget {
+        fn bounding_box(&self) -> Rectangle {
+            Rectangle::new(Point::zero(), Size::new(64, 64))
+        }
+    }
+
+    impl DrawTarget for NullTarget {
+        type Color = Rgb888;
+        type Error = core::convert::Infallible;
+
+        fn draw_iter<I>(&mut self, _pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn text_at_extreme_coordinates_is_dropped() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let clip = Rectangle::new(Point::zero(), Size::new(64, 64));
+        let (min, max) = (i32::MIN + 5, i32::MAX - 5);
+        for rotation in [
+            Rotation::Deg0,
+            Rotation::Deg90,
+            Rotation::Deg180,
+            Rotation::Deg270,
+        ] {
+            for decorated in [false, true] {
+                let mut builder = FontTextStyleBuilder::new(font.clone())
+                    .font_size(16)
+                    .text_color(Rgb888::WHITE)
+                    .rotation(rotation)
+                    .clip(clip);
+                if decorated {
+                    builder = builder.background_color(Rgb888::BLACK).underline();
+                }
+                let style = builder.build();
+                for position in [
+                    Point::new(0, max),
+                    Point::new(0, min),
+                    Point::new(max, 0),
+                    Point::new(min, 0),
+                    Point::new(max, max),
+                    Point::new(min, min),
+                ] {
+                    for baseline in [Baseline::Top, Baseline::Bottom] {
+                        style
+                            .draw_string("Hi", position, baseline, &mut NullTarget)
+                            .unwrap();
+                        style.measure_string("Hi", position, baseline);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Run length encoded output.
+
+use crate::{
+    stdlib::{fmt::Debug, vec::Vec},
+    ColorBlend, FontTextStyle,
+};
+
+use core::convert::Infallible;
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+/// Horizontal run of pixels of the same color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run<C> {
+    /// Leftmost pixel of the run.
+    pub start: Point,
+    /// Number of pixels of the run.
+    pub length: u32,
+    /// Color of the run.
+    pub color: C,
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Render a line of text to horizontal runs of pixels, instead of individual pixels.
+    ///
+    /// Runs are sorted from top to bottom and left to right and don't overlap, they contain the
+    /// background, effects, glyphs and decorations drawn by `draw_string` at `position`. Solid
+    /// areas like backgrounds and glyph stems become a few runs, which suits displays and
+    /// blitters accepting line or rectangle commands.
+    pub fn string_runs(&self, text: &str, position: Point) -> Vec<Run<C>> {
+        let mut target = RunTarget { pixels: Vec::new() };
+        self.draw_line(text, position, None, &mut target)
+            .unwrap_or_else(|never| match never {});
+        target.runs()
+    }
+}
+
+/// Target collecting the pixels drawn, in drawing order.
+struct RunTarget<C: PixelColor> {
+    pixels: Vec<Pixel<C>>,
+}
+
+impl<C: PixelColor> RunTarget<C> {
+    /// Merge the pixels into runs, the last pixel drawn at a point wins.
+    fn runs(mut self) -> Vec<Run<C>> {
+        // the sort is stable, pixels drawn later stay after
+        self.pixels
+            .sort_by_key(|Pixel(point, _)| (point.y, point.x));
+        let mut runs: Vec<Run<C>> = Vec::new();
+        let mut pixels = self.pixels.into_iter().peekable();
+        while let Some(Pixel(point, color)) = pixels.next() {
+            if pixels.peek().is_some_and(|Pixel(next, _)| *next == point) {
+                continue;
+            }
+            match runs.last_mut() {
+                Some(run)
+                    if run.color == color
+                        && run.start + Point::new(run.length as i32, 0) == point =>
+                {
+                    run.length += 1
+                }
+                _ => runs.push(Run {
+                    start: point,
+                    length: 1,
+                    color,
+                }),
+            }
+        }
+        runs
+    }
+}
+
+impl<C: PixelColor> Dimensions for RunTarget<C> {
+    fn bounding_box(&self) -> Rectangle {
+        let half = i32::MAX / 2;
+        Rectangle::new(
+            Point::new(-half, -half),
+            Size::new(i32::MAX as u32, i32::MAX as u32),
+        )
+    }
+}
+
+impl<C: PixelColor> DrawTarget for RunTarget<C> {
+    type Color = C;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<C>>,
+    {
+        self.pixels.extend(pixels);
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = C>,
+    {
+        self.draw_iter(
+            area.points()
+                .zip(colors)
+                .map(|(point, color)| Pixel(point, color)),
+        )
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: C) -> Result<(), Self::Error> {
+        self.draw_iter(area.points().map(|point| Pixel(point, color)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        text::{Baseline, Text},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn runs_match_pixels() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLUE)
+            .underline()
+            .build();
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        Text::with_baseline("Hi", Point::new(2, 3), style.clone(), Baseline::Top)
+            .draw(&mut display)
+            .unwrap();
+
+        let runs = style.string_runs("Hi", Point::new(2, 3));
+        let mut decoded = MockDisplay::new();
+        for run in &runs {
+            let area = Rectangle::new(run.start, Size::new(run.length, 1));
+            decoded.fill_solid(&area, run.color).unwrap();
+        }
+        decoded.assert_eq(&display);
+        assert!(runs.len() < display.affected_area().size.height as usize * 8);
+    }
+
+    #[test]
+    fn overdrawn_pixels_are_replaced() {
+        let red = Rgb888::RED;
+        let blue = Rgb888::BLUE;
+        let mut target = RunTarget { pixels: Vec::new() };
+        let row = |x: i32, color| Pixel(Point::new(x, 0), color);
+        target
+            .draw_iter([row(0, red), row(1, red), row(2, red), row(1, blue)])
+            .unwrap();
+        assert_eq!(
+            target.runs(),
+            [
+                Run {
+                    start: Point::new(0, 0),
+                    length: 1,
+                    color: red,
+                },
+                Run {
+                    start: Point::new(1, 0),
+                    length: 1,
+                    color: blue,
+                },
+                Run {
+                    start: Point::new(2, 0),
+                    length: 1,
+                    color: red,
+                },
+            ]
+        );
+    }
+}
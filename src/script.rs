@@ -0,0 +1,218 @@
+//! Script itemization.
+//!
+//! Text mixing several scripts (latin, greek, CJK ...) is split into runs of a single script
+//! before layout, so that each run is laid out with the first font able to render it.
+//!
+//! The script table is a coarse approximation of the Unicode `Scripts.txt` data covering the main
+//! blocks of each script, it is small enough for embedded targets.
+
+use core::str::CharIndices;
+
+/// Unicode script of a character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Script {
+    /// Characters shared by all scripts (digits, punctuation, spaces ...)
+    Common,
+    /// Combining marks, they take the script of the preceding character
+    Inherited,
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+    /// Any other script not known by this crate.
+    Unknown,
+}
+
+/// Sorted list of `(first, last, script)` ranges.
+const SCRIPT_RANGES: &[(u32, u32, Script)] = &[
+    (0x0041, 0x005A, Script::Latin),
+    (0x0061, 0x007A, Script::Latin),
+    (0x00AA, 0x00AA, Script::Latin),
+    (0x00BA, 0x00BA, Script::Latin),
+    (0x00C0, 0x00D6, Script::Latin),
+    (0x00D8, 0x00F6, Script::Latin),
+    (0x00F8, 0x024F, Script::Latin),
+    (0x0250, 0x02AF, Script::Latin),
+    (0x0300, 0x036F, Script::Inherited),
+    (0x0370, 0x03FF, Script::Greek),
+    (0x0400, 0x052F, Script::Cyrillic),
+    (0x0531, 0x058F, Script::Armenian),
+    (0x0591, 0x05FF, Script::Hebrew),
+    (0x0600, 0x06FF, Script::Arabic),
+    (0x0750, 0x077F, Script::Arabic),
+    (0x08A0, 0x08FF, Script::Arabic),
+    (0x0900, 0x097F, Script::Devanagari),
+    (0x0E00, 0x0E7F, Script::Thai),
+    (0x1100, 0x11FF, Script::Hangul),
+    (0x1AB0, 0x1AFF, Script::Inherited),
+    (0x1C80, 0x1C8F, Script::Cyrillic),
+    (0x1DC0, 0x1DFF, Script::Inherited),
+    (0x1E00, 0x1EFF, Script::Latin),
+    (0x1F00, 0x1FFF, Script::Greek),
+    (0x200C, 0x200D, Script::Inherited),
+    (0x20D0, 0x20FF, Script::Inherited),
+    (0x2C60, 0x2C7F, Script::Latin),
+    (0x2DE0, 0x2DFF, Script::Cyrillic),
+    (0x2E80, 0x2FDF, Script::Han),
+    (0x3005, 0x3005, Script::Han),
+    (0x3007, 0x3007, Script::Han),
+    (0x3021, 0x3029, Script::Han),
+    (0x3038, 0x303B, Script::Han),
+    (0x3041, 0x309F, Script::Hiragana),
+    (0x30A0, 0x30FF, Script::Katakana),
+    (0x3130, 0x318F, Script::Hangul),
+    (0x31F0, 0x31FF, Script::Katakana),
+    (0x3400, 0x4DBF, Script::Han),
+    (0x4E00, 0x9FFF, Script::Han),
+    (0xA640, 0xA69F, Script::Cyrillic),
+    (0xA720, 0xA7FF, Script::Latin),
+    (0xAC00, 0xD7AF, Script::Hangul),
+    (0xF900, 0xFAFF, Script::Han),
+    (0xFB1D, 0xFB4F, Script::Hebrew),
+    (0xFB50, 0xFDFF, Script::Arabic),
+    (0xFE00, 0xFE0F, Script::Inherited),
+    (0xFE20, 0xFE2F, Script::Inherited),
+    (0xFE70, 0xFEFF, Script::Arabic),
+    (0xFF21, 0xFF3A, Script::Latin),
+    (0xFF41, 0xFF5A, Script::Latin),
+    (0xFF66, 0xFF9F, Script::Katakana),
+    (0x20000, 0x3134F, Script::Han),
+    (0xE0100, 0xE01EF, Script::Inherited),
+];
+
+/// First code point of the blocks not covered by the table which are known to be letters.
+const FIRST_UNKNOWN_LETTER: u32 = 0x0980;
+
+impl Script {
+    /// Script of a character.
+    pub(crate) fn of(c: char) -> Script {
+        let c = c as u32;
+        match SCRIPT_RANGES.binary_search_by(|&(first, last, _)| {
+            if last < c {
+                core::cmp::Ordering::Less
+            } else if first > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        }) {
+            Ok(i) => SCRIPT_RANGES[i].2,
+            // letters of scripts we don't know still must not be merged with known scripts
+            Err(_) if (FIRST_UNKNOWN_LETTER..0x2000).contains(&c) => Script::Unknown,
+            Err(_) if (0xA000..0xD800).contains(&c) => Script::Unknown,
+            Err(_) => Script::Common,
+        }
+    }
+
+    /// OpenType script tag, `None` for neutral and unknown scripts.
+    #[cfg(feature = "opentype-layout")]
+    pub(crate) fn opentype_tag(self) -> Option<owned_ttf_parser::Tag> {
+        let tag = match self {
+            Script::Latin => b"latn",
+            Script::Greek => b"grek",
+            Script::Cyrillic => b"cyrl",
+            Script::Armenian => b"armn",
+            Script::Hebrew => b"hebr",
+            Script::Arabic => b"arab",
+            Script::Devanagari => b"dev2",
+            Script::Thai => b"thai",
+            Script::Hangul => b"hang",
+            Script::Hiragana | Script::Katakana => b"kana",
+            Script::Han => b"hani",
+            Script::Common | Script::Inherited | Script::Unknown => return None,
+        };
+        Some(owned_ttf_parser::Tag::from_bytes(tag))
+    }
+
+    /// Whether this script takes the script of the surrounding text.
+    fn is_neutral(self) -> bool {
+        matches!(self, Script::Common | Script::Inherited)
+    }
+}
+
+/// Iterator over `(start, end, script)` runs of a string, in byte indices.
+///
+/// Common and inherited characters are merged with the preceding run, or with the following run
+/// when they start the text.
+pub(crate) struct ScriptRuns<'a> {
+    text: &'a str,
+    chars: CharIndices<'a>,
+    start: usize,
+    script: Script,
+}
+
+impl<'a> ScriptRuns<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        ScriptRuns {
+            text,
+            chars: text.char_indices(),
+            start: 0,
+            script: Script::Common,
+        }
+    }
+}
+
+impl Iterator for ScriptRuns<'_> {
+    type Item = (usize, usize, Script);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.start >= self.text.len() {
+            return None;
+        }
+        for (i, c) in self.chars.by_ref() {
+            let script = Script::of(c);
+            if script.is_neutral() || script == self.script {
+                continue;
+            }
+            if self.script.is_neutral() {
+                // leading neutral characters join the first real script
+                self.script = script;
+                continue;
+            }
+            let run = (self.start, i, self.script);
+            self.start = i;
+            self.script = script;
+            return Some(run);
+        }
+        let run = (self.start, self.text.len(), self.script);
+        self.start = self.text.len();
+        Some(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::vec::Vec;
+
+    #[test]
+    fn mixed_scripts_are_split() {
+        let text = "Hello αβγ 世界!";
+        let runs: Vec<_> = ScriptRuns::new(text)
+            .map(|(start, end, script)| (&text[start..end], script))
+            .collect();
+        assert_eq!(
+            runs,
+            [
+                ("Hello ", Script::Latin),
+                ("αβγ ", Script::Greek),
+                ("世界!", Script::Han),
+            ]
+        );
+    }
+
+    #[test]
+    fn neutral_text_is_a_single_run() {
+        let runs: Vec<_> = ScriptRuns::new("12:34").collect();
+        assert_eq!(runs, [(0, 5, Script::Common)]);
+        assert_eq!(ScriptRuns::new("").next(), None);
+    }
+}
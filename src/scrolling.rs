@@ -0,0 +1,154 @@
+//! Text scrolling through a viewport, like a ticker.
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle};
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::Rectangle,
+    text::{renderer::TextRenderer, Baseline},
+};
+
+/// A line of text scrolling horizontally through a viewport, repeated after a gap.
+///
+/// The text is drawn at the top of the viewport, `offset` pixels left of its start, and a new
+/// copy follows each copy `gap` pixels after its end. Glyphs crossing the edges of the viewport
+/// are clipped to it. Increment the offset between frames to scroll the text, the layout is the
+/// same for all offsets so the text doesn't jitter.
+#[derive(Debug, Clone)]
+pub struct ScrollingText<'a, C> {
+    /// The text.
+    pub text: &'a str,
+
+    /// Area the text is drawn in.
+    pub viewport: Rectangle,
+
+    /// Character style.
+    pub style: FontTextStyle<C>,
+
+    /// Distance in pixels the text has scrolled to the left, wrapped around by the period.
+    pub offset: i32,
+
+    /// Space in pixels between the end of the text and its next copy.
+    pub gap: u32,
+}
+
+impl<'a, C> ScrollingText<'a, C> {
+    /// Create text scrolling through `viewport`.
+    ///
+    /// The gap is the width of the viewport, so that a copy leaves the viewport before the next
+    /// one enters it.
+    pub fn new(text: &'a str, viewport: Rectangle, style: FontTextStyle<C>) -> Self {
+        ScrollingText {
+            text,
+            viewport,
+            style,
+            offset: 0,
+            gap: viewport.size.width,
+        }
+    }
+
+    /// Set the distance the text has scrolled to the left.
+    pub fn offset(mut self, offset: i32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Set the space between the end of the text and its next copy.
+    pub fn gap(mut self, gap: u32) -> Self {
+        self.gap = gap;
+        self
+    }
+}
+
+impl<C> ScrollingText<'_, C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Distance between the starts of two copies of the text, offsets are the same modulo the
+    /// period.
+    pub fn period(&self) -> u32 {
+        let advance = self
+            .style
+            .measure_string(self.text, Point::zero(), Baseline::Top)
+            .next_position
+            .x;
+        advance.max(0) as u32 + self.gap
+    }
+}
+
+impl<C> Drawable for ScrollingText<'_, C>
+where
+    C: ColorBlend + Debug,
+{
+    type Color = C;
+    type Output = ();
+
+    /// Draw the copies of the text visible in the viewport.
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let period = self.period() as i32;
+        if period <= 0 {
+            return Ok(());
+        }
+        let mut scroll_x = self.offset.rem_euclid(period);
+        while scroll_x > -(self.viewport.size.width as i32) {
+            self.style
+                .draw_string_scrolled(self.text, &self.viewport, scroll_x, target)?;
+            scroll_x -= period;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+    use rusttype::Font;
+
+    #[test]
+    fn text_wraps_around() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(12)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let viewport = Rectangle::new(Point::new(2, 2), Size::new(30, 14));
+        let text = ScrollingText::new("Hello ticker", viewport, style.clone()).gap(6);
+        let draw = |text: &ScrollingText<'_, Rgb888>| {
+            let mut display = MockDisplay::new();
+            text.draw(&mut display).unwrap();
+            display
+        };
+        let period = text.period() as i32;
+        let advance = style
+            .measure_string("Hello ticker", Point::zero(), Baseline::Top)
+            .next_position
+            .x;
+        assert_eq!(period, advance + 6);
+
+        // offsets repeat with the period
+        let start = draw(&text);
+        draw(&text.clone().offset(period)).assert_eq(&start);
+        draw(&text.clone().offset(-period)).assert_eq(&start);
+        let mut expected = MockDisplay::new();
+        style
+            .draw_string_scrolled("Hello ticker", &viewport, 0, &mut expected)
+            .unwrap();
+        start.assert_eq(&expected);
+
+        // near the end of a copy, the next one enters on the right
+        let end = draw(&text.clone().offset(period - 10));
+        let mut expected = MockDisplay::new();
+        for scroll_x in [period - 10, -10] {
+            style
+                .draw_string_scrolled("Hello ticker", &viewport, scroll_x, &mut expected)
+                .unwrap();
+        }
+        end.assert_eq(&expected);
+        assert!(viewport.contains(end.affected_area().bottom_right().unwrap()));
+    }
+}
@@ -0,0 +1,126 @@
+//! Rendering statistics.
+//!
+//! With the `stats` feature, drawing updates the counters of the global [`RenderStats`] returned
+//! by [`global_stats`] and of the [`RenderStats`] attached to the style, if any. Without it,
+//! counting compiles to nothing.
+
+#[cfg(feature = "stats")]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::FontTextStyle;
+
+/// Event counted by the statistics.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Counter {
+    GlyphsRasterized,
+    CacheHits,
+    CacheMisses,
+    PixelsEmitted,
+    DrawCalls,
+}
+
+/// Rendering counters, updated by every style drawing with them.
+#[cfg(feature = "stats")]
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    glyphs_rasterized: AtomicU32,
+    cache_hits: AtomicU32,
+    cache_misses: AtomicU32,
+    pixels_emitted: AtomicU32,
+    draw_calls: AtomicU32,
+}
+
+/// Values of the [`RenderStats`] counters at some point in time.
+#[cfg(feature = "stats")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatsSnapshot {
+    /// Glyphs rasterized by rusttype, cache misses included.
+    pub glyphs_rasterized: u32,
+    /// Glyphs found in the glyph cache.
+    pub cache_hits: u32,
+    /// Glyphs not found in the glyph cache.
+    pub cache_misses: u32,
+    /// Glyph pixels sent to the draw target, backgrounds and decorations excluded.
+    pub pixels_emitted: u32,
+    /// Calls to `draw_string`.
+    pub draw_calls: u32,
+}
+
+/// Shared handle to [`RenderStats`], as stored in a [`FontTextStyle`].
+#[cfg(all(feature = "stats", feature = "std"))]
+pub type SharedRenderStats = std::sync::Arc<RenderStats>;
+
+/// Shared handle to [`RenderStats`], as stored in a [`FontTextStyle`].
+#[cfg(all(feature = "stats", not(feature = "std")))]
+pub type SharedRenderStats = alloc::rc::Rc<RenderStats>;
+
+#[cfg(feature = "stats")]
+static GLOBAL_STATS: RenderStats = RenderStats::new();
+
+/// Counters of all drawing done by this crate.
+#[cfg(feature = "stats")]
+pub fn global_stats() -> &'static RenderStats {
+    &GLOBAL_STATS
+}
+
+#[cfg(feature = "stats")]
+impl RenderStats {
+    /// Create counters starting at zero.
+    pub const fn new() -> Self {
+        RenderStats {
+            glyphs_rasterized: AtomicU32::new(0),
+            cache_hits: AtomicU32::new(0),
+            cache_misses: AtomicU32::new(0),
+            pixels_emitted: AtomicU32::new(0),
+            draw_calls: AtomicU32::new(0),
+        }
+    }
+
+    /// Current value of the counters.
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            glyphs_rasterized: self.glyphs_rasterized.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            pixels_emitted: self.pixels_emitted.load(Ordering::Relaxed),
+            draw_calls: self.draw_calls.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Set all counters back to zero.
+    pub fn reset(&self) {
+        for counter in self.counters() {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn counters(&self) -> [&AtomicU32; 5] {
+        [
+            &self.glyphs_rasterized,
+            &self.cache_hits,
+            &self.cache_misses,
+            &self.pixels_emitted,
+            &self.draw_calls,
+        ]
+    }
+
+    fn add(&self, counter: Counter, n: u32) {
+        self.counters()[counter as usize].fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+impl<C> FontTextStyle<C> {
+    /// Add `n` to a counter of the global and style statistics.
+    #[cfg(feature = "stats")]
+    pub(crate) fn count(&self, counter: Counter, n: u32) {
+        GLOBAL_STATS.add(counter, n);
+        if let Some(stats) = &self.stats {
+            stats.add(counter, n);
+        }
+    }
+
+    /// Add `n` to a counter of the global and style statistics.
+    #[cfg(not(feature = "stats"))]
+    #[inline(always)]
+    pub(crate) fn count(&self, _counter: Counter, _n: u32) {}
+}
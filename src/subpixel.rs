@@ -0,0 +1,132 @@
+//! Subpixel antialiasing for color LCD panels.
+//!
+//! The pixels of LCD panels are made of three colored stripes side by side. Glyphs rasterized at
+//! three times the horizontal resolution give each stripe its own coverage, which triples the
+//! horizontal precision of the edges. The coverage is filtered across neighboring stripes so that
+//! the edges don't show colored fringes.
+
+use crate::{
+    allocator, bold, stats::Counter, stdlib::fmt::Debug, transform::AffineTransform, ColorBlend,
+    FontTextStyle,
+};
+
+use rusttype::PositionedGlyph;
+
+/// Order of the color stripes of the pixels of a panel, from left to right.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SubpixelOrder {
+    /// Red, green, blue (default), the most common.
+    #[default]
+    Rgb,
+    /// Blue, green, red.
+    Bgr,
+}
+
+/// Weights of the filter spreading the coverage of a stripe over its neighbors, summing to 9.
+const FILTER: [u32; 5] = [1, 2, 3, 2, 1];
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Call `f(x, y, [red, green, blue])` for each pixel of a glyph, relative to the layout
+    /// origin, with the coverage of each color stripe.
+    pub(crate) fn subpixel_coverage(
+        &self,
+        glyph: &PositionedGlyph<'_>,
+        order: SubpixelOrder,
+        mut f: impl FnMut(i32, i32, [u8; 3]),
+    ) {
+        let bounds = match self.glyph_bounds(glyph) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        self.count(Counter::GlyphsRasterized, 1);
+
+        // a pixel and a stripe of margin around the glyph, for rounding and the filter
+        let (left, top) = (bounds.min.x - 1, bounds.min.y - 1);
+        let width = (bounds.max.x - bounds.min.x + 2) as usize;
+        let height = (bounds.max.y - bounds.min.y + 2) as usize;
+        let stripes = 3 * width;
+        let mut data = allocator::zeroed(stripes * height, self.allocator);
+
+        let transform = self
+            .outline_transform(glyph)
+            .then(&AffineTransform::scale(3.0, 1.0));
+        self.outline_coverage(glyph, &transform, |x, y, v| {
+            let (x, y) = ((x - 3 * left) as usize, (y - top) as usize);
+            if x < stripes && y < height {
+                data[y * stripes + x] = v;
+            }
+        });
+
+        for y in 0..height {
+            let row = &mut data[y * stripes..(y + 1) * stripes];
+            bold::embolden(row, 3 * self.faux_bold as usize);
+            let row = &*row;
+            for x in 0..width {
+                let mut coverage = [0u8; 3];
+                for (i, channel) in coverage.iter_mut().enumerate() {
+                    let center = (3 * x + i) as isize;
+                    let sum: u32 = FILTER
+                        .iter()
+                        .zip(center - 2..)
+                        .filter_map(|(w, s)| {
+                            row.get(usize::try_from(s).ok()?).map(|v| w * *v as u32)
+                        })
+                        .sum();
+                    *channel = (sum / 9) as u8;
+                }
+                if order == SubpixelOrder::Bgr {
+                    coverage.reverse();
+                }
+                if coverage != [0; 3] {
+                    f(left + x as i32, top + y as i32, coverage);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        prelude::*,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn stripes_have_their_own_coverage() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let draw = |order| {
+            let style = FontTextStyleBuilder::new(font.clone())
+                .font_size(12)
+                .text_color(Rgb888::WHITE)
+                .background_color(Rgb888::BLACK)
+                .anti_aliasing_subpixel(order)
+                .build();
+            let mut display = MockDisplay::new();
+            style
+                .draw_string("Hello", Point::new(2, 2), Baseline::Top, &mut display)
+                .unwrap();
+            display
+        };
+
+        let rgb = draw(SubpixelOrder::Rgb);
+        let fringes = rgb
+            .affected_area()
+            .points()
+            .filter_map(|p| rgb.get_pixel(p))
+            .filter(|c| c.r() != c.b())
+            .count();
+        assert!(fringes > 0);
+        // the same stripes, in the other order
+        let swapped = rgb.map(|c| Rgb888::new(c.b(), c.g(), c.r()));
+        draw(SubpixelOrder::Bgr).assert_eq(&swapped);
+    }
+}
@@ -0,0 +1,352 @@
+//! Multi-line text.
+//!
+//! A [`TextBox`] draws text made of several paragraphs, with paragraph spacing and indentation
+//! controlled by a [`TextBoxStyle`].
+
+use crate::{stdlib::fmt::Debug, ColorBlend, FontTextStyle, WrappedText};
+
+use embedded_graphics::{draw_target::DrawTarget, prelude::*};
+
+/// What separates two paragraphs of a text box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParagraphBreak {
+    /// Every line break starts a new paragraph (default).
+    #[default]
+    Newline,
+    /// Paragraphs are separated by one or more blank lines, a single line break starts a new
+    /// line in the same paragraph.
+    BlankLine,
+}
+
+/// What to do with the lines of a text box beyond its maximum line count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VerticalOverflow {
+    /// Don't draw the lines beyond the limit (default).
+    #[default]
+    Clip,
+    /// Don't draw the lines beyond the limit and replace the end of the last visible line with
+    /// an ellipsis, so that it still fits the width of the text.
+    Ellipsis,
+}
+
+/// Layout properties of a [`TextBox`] or [`WrappedText`](crate::WrappedText).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextBoxStyle {
+    /// What separates two paragraphs.
+    pub paragraph_break: ParagraphBreak,
+
+    /// Extra space between paragraphs, in pixels.
+    pub paragraph_spacing: u32,
+
+    /// Indentation of the first line of each paragraph, in pixels.
+    pub first_line_indent: u32,
+
+    /// Indentation of the other lines of each paragraph, in pixels.
+    pub hanging_indent: u32,
+
+    /// Maximum number of lines drawn, all lines are drawn if `None`.
+    pub max_lines: Option<u32>,
+
+    /// What to do with the lines beyond `max_lines`.
+    pub vertical_overflow: VerticalOverflow,
+}
+
+impl TextBoxStyle {
+    /// Indentation of a line, depending on whether it starts a paragraph.
+    pub(crate) fn line_indent(&self, starts_paragraph: bool) -> u32 {
+        match starts_paragraph {
+            true => self.first_line_indent,
+            false => self.hanging_indent,
+        }
+    }
+}
+
+/// Text box style builder.
+#[derive(Debug, Default)]
+pub struct TextBoxStyleBuilder {
+    style: TextBoxStyle,
+}
+
+impl TextBoxStyleBuilder {
+    /// Create a new text box style builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select what separates two paragraphs.
+    pub fn paragraph_break(mut self, paragraph_break: ParagraphBreak) -> Self {
+        self.style.paragraph_break = paragraph_break;
+        self
+    }
+
+    /// Set the extra space between paragraphs in pixels.
+    pub fn paragraph_spacing(mut self, paragraph_spacing: u32) -> Self {
+        self.style.paragraph_spacing = paragraph_spacing;
+        self
+    }
+
+    /// Indent the first line of each paragraph by `indent` pixels.
+    pub fn first_line_indent(mut self, indent: u32) -> Self {
+        self.style.first_line_indent = indent;
+        self
+    }
+
+    /// Indent the lines following the first one of each paragraph by `indent` pixels, to align
+    /// them after a bullet or a term.
+    pub fn hanging_indent(mut self, indent: u32) -> Self {
+        self.style.hanging_indent = indent;
+        self
+    }
+
+    /// Draw at most `max_lines` lines.
+    pub fn max_lines(mut self, max_lines: u32) -> Self {
+        self.style.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Select what to do with the lines beyond the maximum line count.
+    pub fn vertical_overflow(mut self, vertical_overflow: VerticalOverflow) -> Self {
+        self.style.vertical_overflow = vertical_overflow;
+        self
+    }
+
+    /// Build the text box style.
+    pub fn build(self) -> TextBoxStyle {
+        self.style
+    }
+}
+
+/// Multi-line text drawn with a [`FontTextStyle`].
+///
+/// Lines are stacked from the top left corner `position`, each line is `line_height()` pixels
+/// high. The box is laid out like a [`WrappedText`] as wide as its widest line, so that its
+/// lines are never wrapped.
+#[derive(Debug, Clone)]
+pub struct TextBox<'a, C> {
+    /// The text.
+    pub text: &'a str,
+
+    /// Position of the top left corner of the box.
+    pub position: Point,
+
+    /// Character style.
+    pub character_style: FontTextStyle<C>,
+
+    /// Layout style.
+    pub style: TextBoxStyle,
+
+    /// Background color of every other line, starting with the second one.
+    ///
+    /// When set, line backgrounds span the width of the box, the other lines use the background
+    /// color of the character style.
+    pub alternate_background_color: Option<C>,
+}
+
+impl<'a, C> TextBox<'a, C> {
+    /// Create a text box with the default layout style.
+    pub fn new(text: &'a str, position: Point, character_style: FontTextStyle<C>) -> Self {
+        Self::with_textbox_style(text, position, character_style, TextBoxStyle::default())
+    }
+
+    /// Create a text box with a layout style.
+    pub fn with_textbox_style(
+        text: &'a str,
+        position: Point,
+        character_style: FontTextStyle<C>,
+        style: TextBoxStyle,
+    ) -> Self {
+        TextBox {
+            text,
+            position,
+            character_style,
+            style,
+            alternate_background_color: None,
+        }
+    }
+
+    /// Fill the background of every other line with `color`, like a zebra striped table.
+    pub fn alternate_background_color(mut self, color: C) -> Self {
+        self.alternate_background_color = Some(color);
+        self
+    }
+}
+
+impl<'a, C> TextBox<'a, C>
+where
+    C: ColorBlend + Debug,
+{
+    /// The box as text wrapped to the width of its widest line.
+    fn wrapped(&self) -> WrappedText<'a, C> {
+        let mut text = WrappedText::with_textbox_style(
+            self.text,
+            self.position,
+            self.character_style.clone(),
+            u32::MAX,
+            self.style,
+        );
+        text.alternate_background_color = self.alternate_background_color;
+        // the ellipsis replaces the end of the last line instead of widening the box
+        text.textbox_style.vertical_overflow = VerticalOverflow::Clip;
+        text.width = text.size().width;
+        text.textbox_style.vertical_overflow = self.style.vertical_overflow;
+        text
+    }
+
+    /// Size of the drawn lines, from the top left corner of the box.
+    pub(crate) fn size(&self) -> Size {
+        self.wrapped().size()
+    }
+}
+
+impl<C> Drawable for TextBox<'_, C>
+where
+    C: ColorBlend + Debug,
+{
+    type Color = C;
+    type Output = Point;
+
+    /// Draw the text box, returns the top left corner of the line following the text.
+    fn draw<D>(&self, target: &mut D) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let metrics = self.wrapped().draw(target)?;
+        Ok(self.position + Point::new(0, metrics.height as i32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        primitives::Rectangle,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    fn draw(text: &str, style: TextBoxStyle) -> (MockDisplay<Rgb888>, Point) {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let character_style = FontTextStyleBuilder::new(font)
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        display.set_allow_overdraw(true);
+        let next = TextBox::with_textbox_style(text, Point::zero(), character_style, style)
+            .draw(&mut display)
+            .unwrap();
+        (display, next)
+    }
+
+    #[test]
+    fn alternate_lines_are_striped() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let character_style = FontTextStyleBuilder::new(font)
+            .font_size(10)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        TextBox::new("long line\nx\ny", Point::zero(), character_style)
+            .alternate_background_color(Rgb888::BLUE)
+            .draw(&mut display)
+            .unwrap();
+
+        // the short lines are filled up to the width of the longest one
+        assert_eq!(display.get_pixel(Point::new(25, 15)), Some(Rgb888::BLUE));
+        assert_eq!(display.get_pixel(Point::new(25, 25)), Some(Rgb888::BLACK));
+    }
+
+    #[test]
+    fn stripes_keep_the_antialiasing_mode() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let character_style = FontTextStyleBuilder::new(font)
+            .font_size(10)
+            .text_color(Rgb888::WHITE)
+            .anti_aliasing_dithering()
+            .build();
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        TextBox::new("o\no", Point::zero(), character_style.clone())
+            .alternate_background_color(Rgb888::BLUE)
+            .draw(&mut display)
+            .unwrap();
+
+        // the second line is dithered over the stripe, not blended with it
+        let mut striped = character_style.clone();
+        striped.background_color = Some(Rgb888::BLUE);
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        let line = Rectangle::new(Point::new(0, 10), Size::new(64, 10));
+        expected
+            .fill_solid(&line.intersection(&display.affected_area()), Rgb888::BLUE)
+            .unwrap();
+        striped
+            .draw_string("o", Point::new(0, 10), Baseline::Top, &mut expected)
+            .unwrap();
+        for point in line.points() {
+            assert_eq!(
+                display.get_pixel(point),
+                expected.get_pixel(point),
+                "{:?}",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn paragraphs_are_spaced() {
+        let spaced = TextBoxStyleBuilder::new().paragraph_spacing(5).build();
+        assert_eq!(draw("a\nb", TextBoxStyle::default()).1, Point::new(0, 40));
+        assert_eq!(draw("a\nb", spaced).1, Point::new(0, 45));
+
+        let blank_lines = TextBoxStyleBuilder::new()
+            .paragraph_break(ParagraphBreak::BlankLine)
+            .paragraph_spacing(5)
+            .build();
+        assert_eq!(draw("a\nb\n\n\nc", blank_lines).1, Point::new(0, 65));
+    }
+
+    #[test]
+    fn lines_are_limited() {
+        let clipped = TextBoxStyleBuilder::new().max_lines(2).build();
+        assert_eq!(draw("a\nb\nc", clipped).1, Point::new(0, 40));
+
+        let ellipsis = TextBoxStyleBuilder::new()
+            .max_lines(2)
+            .vertical_overflow(VerticalOverflow::Ellipsis)
+            .build();
+        let text = "abc\nabc\nabc";
+        let (plain, _) = draw(text, clipped);
+        let (ellipsized, next) = draw(text, ellipsis);
+        assert_eq!(next, Point::new(0, 40));
+        // the end of the last line is replaced, the box keeps its width
+        let (plain_area, area) = (plain.affected_area(), ellipsized.affected_area());
+        assert!(area.size.width <= plain_area.size.width);
+        let last_line = Rectangle::new(Point::new(0, 20), Size::new(64, 20));
+        assert!(last_line
+            .points()
+            .any(|p| ellipsized.get_pixel(p) != plain.get_pixel(p)));
+        // no ellipsis when all lines fit
+        assert_eq!(draw("a\nb", ellipsis).0, draw("a\nb", clipped).0);
+    }
+
+    #[test]
+    fn first_lines_are_indented() {
+        let indented = TextBoxStyleBuilder::new()
+            .paragraph_break(ParagraphBreak::BlankLine)
+            .first_line_indent(7)
+            .build();
+        let (plain, _) = draw("H\nH", TextBoxStyle::default());
+        let (display, _) = draw("H\nH", indented);
+        let plain = plain.affected_area();
+        let area = display.affected_area();
+        assert_eq!(area.top_left.x, plain.top_left.x);
+        assert_eq!(area.size.width, plain.size.width + 7);
+    }
+}
@@ -0,0 +1,108 @@
+//! Text laid out once and drawn many times.
+//!
+//! Drawing a string splits it into runs and clusters, picks fonts and positions every glyph
+//! before rasterizing anything. A [`TextLayout`] keeps the positioned glyphs of a static label so
+//! that each draw only rasterizes them, from a [`GlyphCache`](crate::GlyphCache) if the style
+//! has one.
+
+use crate::{
+    layout::LineLayout,
+    stdlib::{fmt::Debug, vec::Vec},
+    ColorBlend, FontTextStyle,
+};
+
+use embedded_graphics::{
+    prelude::*,
+    text::{renderer::TextMetrics, Baseline},
+};
+
+/// Text laid out with a style, ready to be drawn at any position.
+#[derive(Debug)]
+pub struct TextLayout<'a, C> {
+    style: &'a FontTextStyle<C>,
+    lines: Vec<(&'a str, LineLayout, Point)>,
+}
+
+impl<'a, C> TextLayout<'a, C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Lay out `text` with `style`.
+    pub fn new(text: &'a str, style: &'a FontTextStyle<C>) -> Self {
+        let lines = style
+            .lines(text)
+            .map(|(line, offset)| (line, style.layout(line), offset))
+            .collect();
+        TextLayout { style, lines }
+    }
+
+    /// Draw the text at `position`, like `draw_string` does.
+    ///
+    /// Returns the position following the last character.
+    pub fn draw<D>(
+        &self,
+        position: Point,
+        baseline: Baseline,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let lines = self
+            .lines
+            .iter()
+            .map(|(line, layout, offset)| (*line, layout, *offset));
+        self.style
+            .draw_lines(lines, position, baseline, |_, _| None, target)
+    }
+
+    /// Metrics of the text drawn at `position`, like `measure_string` returns.
+    pub fn measure(&self, position: Point, baseline: Baseline) -> TextMetrics {
+        let lines = self
+            .lines
+            .iter()
+            .map(|(_, layout, offset)| (layout.extent(), *offset));
+        self.style.measure_lines(lines, position, baseline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay, pixelcolor::Rgb888, text::renderer::TextRenderer,
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn layout_draws_like_the_string() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(16)
+            .text_color(Rgb888::WHITE)
+            .background_color(Rgb888::BLACK)
+            .multiline()
+            .build();
+        let text = "Label\nValue";
+        let layout = TextLayout::new(text, &style);
+
+        for (position, baseline) in [
+            (Point::new(2, 3), Baseline::Top),
+            (Point::new(10, 20), Baseline::Alphabetic),
+        ] {
+            let mut drawn = MockDisplay::new();
+            let next = layout.draw(position, baseline, &mut drawn).unwrap();
+            let mut expected = MockDisplay::new();
+            let expected_next = style
+                .draw_string(text, position, baseline, &mut expected)
+                .unwrap();
+            drawn.assert_eq(&expected);
+            assert_eq!(next, expected_next);
+            assert_eq!(
+                layout.measure(position, baseline),
+                style.measure_string(text, position, baseline)
+            );
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Rasterization of transformed glyphs.
+//!
+//! rusttype only rasterizes upright glyphs. Rotated, skewed or scaled glyphs are rasterized from
+//! their outline, transformed point by point, so that they are as smooth as upright ones.
+
+use crate::stdlib::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::float::Float;
+
+use embedded_graphics::geometry::Angle;
+
+use ab_glyph_rasterizer::{point, Point as RasterPoint, Rasterizer};
+use rusttype::{OutlineBuilder, ScaledGlyph};
+
+/// Affine transform of points: `(x, y)` becomes `(a x + c y + e, b x + d y + f)`.
+///
+/// Coordinates are in pixels, y pointing down, so positive rotations and skews are clockwise on
+/// screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AffineTransform {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    e: f32,
+    f: f32,
+}
+
+impl Default for AffineTransform {
+    fn default() -> Self {
+        AffineTransform::identity()
+    }
+}
+
+impl AffineTransform {
+    /// Transform from its coefficients, see [`AffineTransform`].
+    pub fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        AffineTransform { a, b, c, d, e, f }
+    }
+
+    /// Transform leaving points unchanged.
+    pub fn identity() -> Self {
+        AffineTransform::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    /// Rotation by `angle` around the origin.
+    pub fn rotation(angle: Angle) -> Self {
+        let angle = angle.to_radians();
+        let (sin, cos) = (angle.sin(), angle.cos());
+        AffineTransform::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// Translation by `(x, y)`.
+    pub fn translation(x: f32, y: f32) -> Self {
+        AffineTransform::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    /// Scaling by `x` horizontally and `y` vertically around the origin.
+    pub fn scale(x: f32, y: f32) -> Self {
+        AffineTransform::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    /// Skew of the vertical axis by `x` and of the horizontal axis by `y`.
+    ///
+    /// A negative `x` slants the tops of the glyphs to the right, like italics.
+    pub fn skew(x: Angle, y: Angle) -> Self {
+        let (x, y) = (x.to_radians().tan(), y.to_radians().tan());
+        AffineTransform::new(1.0, y, x, 1.0, 0.0, 0.0)
+    }
+
+    /// This transform followed by `next`.
+    pub fn then(&self, next: &AffineTransform) -> Self {
+        AffineTransform {
+            a: next.a * self.a + next.c * self.b,
+            b: next.b * self.a + next.d * self.b,
+            c: next.a * self.c + next.c * self.d,
+            d: next.b * self.c + next.d * self.d,
+            e: next.a * self.e + next.c * self.f + next.e,
+            f: next.b * self.e + next.d * self.f + next.f,
+        }
+    }
+
+    /// Transformed point.
+    pub(crate) fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+}
+
+/// Segment of a glyph outline.
+enum Segment {
+    Line(RasterPoint, RasterPoint),
+    Quad(RasterPoint, RasterPoint, RasterPoint),
+    Cubic(RasterPoint, RasterPoint, RasterPoint, RasterPoint),
+}
+
+/// Collects the transformed segments of an outline.
+struct Outline<F> {
+    transform: AffineTransform,
+    /// Fitting of heights before the transform.
+    fit: F,
+    segments: Vec<Segment>,
+    start: RasterPoint,
+    last: RasterPoint,
+}
+
+impl<F: Fn(f32) -> f32> Outline<F> {
+    fn point(&self, x: f32, y: f32) -> RasterPoint {
+        let (x, y) = self.transform.apply(x, (self.fit)(y));
+        point(x, y)
+    }
+}
+
+impl<F: Fn(f32) -> f32> OutlineBuilder for Outline<F> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = self.point(x, y);
+        self.last = self.start;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.segments.push(Segment::Line(self.last, to));
+        self.last = to;
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.segments
+            .push(Segment::Quad(self.last, self.point(x1, y1), to));
+        self.last = to;
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let to = self.point(x, y);
+        self.segments.push(Segment::Cubic(
+            self.last,
+            self.point(x1, y1),
+            self.point(x2, y2),
+            to,
+        ));
+        self.last = to;
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.segments.push(Segment::Line(self.last, self.start));
+        }
+        self.last = self.start;
+    }
+}
+
+/// Columns reached beyond the upright ink, to the left and to the right, by glyphs slanted
+/// `degrees` to the right that span from `ascent` above the baseline to `descent` (negative)
+/// below it.
+pub(crate) fn slant_overhang(degrees: f32, ascent: f32, descent: f32) -> (i32, i32) {
+    let slant = degrees.to_radians().tan();
+    let (top, bottom) = (ascent * slant, descent * slant);
+    (
+        top.min(bottom).min(0.0).floor() as i32,
+        top.max(bottom).max(0.0).ceil() as i32,
+    )
+}
+
+/// Call `f(x, y, coverage)` for each pixel of `glyph` transformed by `transform`.
+///
+/// The outline is relative to the glyph origin on the baseline, y pointing down.
+pub(crate) fn transformed_coverage(
+    glyph: &ScaledGlyph<'_>,
+    transform: &AffineTransform,
+    f: impl FnMut(i32, i32, u8),
+) {
+    fitted_coverage(glyph, |y| y, transform, f)
+}
+
+/// Like `transformed_coverage`, moving each point of the outline at height `y` to `fit(y)`
+/// before transforming it.
+pub(crate) fn fitted_coverage(
+    glyph: &ScaledGlyph<'_>,
+    fit: impl Fn(f32) -> f32,
+    transform: &AffineTransform,
+    mut f: impl FnMut(i32, i32, u8),
+) {
+    let mut outline = Outline {
+        transform: *transform,
+        fit,
+        segments: Vec::new(),
+        start: point(0.0, 0.0),
+        last: point(0.0, 0.0),
+    };
+    if !glyph.build_outline(&mut outline) {
+        return;
+    }
+
+    // control points bound the curves
+    let (mut min, mut max) = (point(f32::MAX, f32::MAX), point(f32::MIN, f32::MIN));
+    let mut extend = |p: &RasterPoint| {
+        min = point(min.x.min(p.x), min.y.min(p.y));
+        max = point(max.x.max(p.x), max.y.max(p.y));
+    };
+    for segment in outline.segments.iter() {
+        match segment {
+            Segment::Line(p0, p1) => [p0, p1].into_iter().for_each(&mut extend),
+            Segment::Quad(p0, p1, p2) => [p0, p1, p2].into_iter().for_each(&mut extend),
+            Segment::Cubic(p0, p1, p2, p3) => [p0, p1, p2, p3].into_iter().for_each(&mut extend),
+        }
+    }
+    if outline.segments.is_empty() {
+        return;
+    }
+    let (left, top) = (min.x.floor(), min.y.floor());
+    let width = (max.x.ceil() - left) as usize;
+    let height = (max.y.ceil() - top) as usize;
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let offset = |p: &RasterPoint| point(p.x - left, p.y - top);
+    let mut rasterizer = Rasterizer::new(width, height);
+    for segment in outline.segments.iter() {
+        match segment {
+            Segment::Line(p0, p1) => rasterizer.draw_line(offset(p0), offset(p1)),
+            Segment::Quad(p0, p1, p2) => rasterizer.draw_quad(offset(p0), offset(p1), offset(p2)),
+            Segment::Cubic(p0, p1, p2, p3) => {
+                rasterizer.draw_cubic(offset(p0), offset(p1), offset(p2), offset(p3))
+            }
+        }
+    }
+    rasterizer.for_each_pixel_2d(|x, y, coverage| {
+        f(
+            x as i32 + left as i32,
+            y as i32 + top as i32,
+            (coverage * 255.0).min(255.0) as u8,
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusttype::{Font, Scale};
+
+    #[test]
+    fn transforms_compose() {
+        let quarter = AffineTransform::rotation(Angle::from_degrees(90.0));
+        let (x, y) = quarter.apply(1.0, 0.0);
+        assert!(x.abs() < 1e-6 && (y - 1.0).abs() < 1e-6);
+        let moved = quarter.then(&AffineTransform::translation(2.0, 3.0));
+        let (x, y) = moved.apply(1.0, 0.0);
+        assert!((x - 2.0).abs() < 1e-6 && (y - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rotated_coverage_matches_the_upright_glyph() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let glyph = font.glyph('L').scaled(Scale::uniform(20.0));
+        let total = |transform: &AffineTransform| {
+            let mut total = 0u32;
+            transformed_coverage(&glyph, transform, |_, _, coverage| total += coverage as u32);
+            total
+        };
+        let mut upright = 0u32;
+        let positioned = glyph.clone().positioned(rusttype::point(0.0, 0.0));
+        positioned.draw(|_, _, v| upright += (v * 255.0).min(255.0) as u32);
+
+        assert_eq!(total(&AffineTransform::identity()), upright);
+        // a rotation keeps the area of the glyph
+        let rotated = total(&AffineTransform::rotation(Angle::from_radians(0.5)));
+        assert!(rotated.abs_diff(upright) < upright / 50);
+    }
+
+    #[test]
+    fn styles_transform_the_glyphs() {
+        use crate::FontTextStyleBuilder;
+        use embedded_graphics::{
+            mock_display::MockDisplay,
+            pixelcolor::Rgb888,
+            prelude::*,
+            text::{renderer::TextRenderer, Baseline},
+        };
+
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let builder = || {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+        };
+        let draw = |style: &crate::FontTextStyle<Rgb888>| {
+            let mut display = MockDisplay::new();
+            style
+                .draw_string("lI", Point::new(4, 4), Baseline::Top, &mut display)
+                .unwrap();
+            display
+        };
+
+        let identity = builder().transform(AffineTransform::identity()).build();
+        draw(&identity).assert_eq(&draw(&builder().build()));
+
+        // slanted stems cover more columns
+        let skewed = builder()
+            .transform(AffineTransform::skew(
+                Angle::from_degrees(-20.0),
+                Angle::zero(),
+            ))
+            .build();
+        let upright = draw(&builder().build()).affected_area();
+        let slanted = draw(&skewed).affected_area();
+        assert!(slanted.size.width > upright.size.width);
+        // the baseline stays in place, the tops lean right
+        assert!(slanted.size.height.abs_diff(upright.size.height) <= 1);
+        assert!((slanted.top_left.x - upright.top_left.x).abs() <= 1);
+        let ink = skewed.ink_bounding_box("lI", Point::new(4, 4));
+        assert_eq!(crate::envelope(&ink, &slanted), ink);
+
+        // synthetic italic is a skew, composed before other transforms
+        draw(&builder().faux_italic(20.0).build()).assert_eq(&draw(&skewed));
+        let scale = AffineTransform::scale(1.5, 1.0);
+        let italic = builder().transform(scale).faux_italic(20.0).build();
+        let expected =
+            AffineTransform::skew(Angle::from_degrees(-20.0), Angle::zero()).then(&scale);
+        assert_eq!(italic.transform, Some(expected));
+    }
+
+    #[test]
+    fn faux_italic_widens_measured_boxes() {
+        use crate::FontTextStyleBuilder;
+        use embedded_graphics::{
+            mock_display::MockDisplay,
+            pixelcolor::Rgb888,
+            prelude::*,
+            text::{renderer::TextRenderer, Baseline},
+        };
+
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let builder = || {
+            FontTextStyleBuilder::new(font.clone())
+                .font_size(20)
+                .text_color(Rgb888::WHITE)
+        };
+        let style = builder().faux_italic(20.0).build();
+        let position = Point::new(4, 4);
+        let upright = builder()
+            .build()
+            .measure_string("lI", position, Baseline::Top)
+            .bounding_box;
+        let measured = style
+            .measure_string("lI", position, Baseline::Top)
+            .bounding_box;
+        assert!(measured.size.width > upright.size.width);
+
+        // the tops of the last glyph stay in the measured box
+        let mut display = MockDisplay::new();
+        style
+            .draw_string("lI", position, Baseline::Top, &mut display)
+            .unwrap();
+        let ink = display.affected_area();
+        assert_eq!(crate::envelope(&measured, &ink), measured);
+    }
+}
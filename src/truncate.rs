@@ -0,0 +1,206 @@
+//! Truncation of text to a width.
+
+use crate::{cluster::Clusters, envelope, stdlib::vec::Vec, FontTextStyle};
+
+use embedded_graphics::{
+    prelude::*,
+    text::{renderer::TextRenderer, Baseline},
+};
+
+impl<C> FontTextStyle<C>
+where
+    Self: TextRenderer<Color = C>,
+{
+    /// Longest start of `text` at most `width` pixels wide.
+    ///
+    /// The text is only cut between clusters, an emoji sequence or a character and its combining
+    /// marks are kept or removed as a whole.
+    pub fn truncate<'t>(&self, text: &'t str, width: u32) -> &'t str {
+        let fits = |end: usize| {
+            let metrics = self.measure_string(&text[..end], Point::zero(), Baseline::Top);
+            metrics.bounding_box.size.width <= width
+        };
+        if fits(text.len()) {
+            return text;
+        }
+
+        let mut ends: Vec<usize> = Vec::new();
+        let mut end = 0;
+        for cluster in Clusters::new(text) {
+            end += cluster.len();
+            ends.push(end);
+        }
+        // ends[..fitting] fit, the rest don't
+        let fitting = ends.partition_point(|&end| fits(end));
+        match fitting {
+            0 => "",
+            n => &text[..ends[n - 1]],
+        }
+    }
+
+    /// Width of `text` followed by `suffix`, drawn at the position where `text` ends.
+    pub(crate) fn width_with_suffix(&self, text: &str, suffix: &str) -> u32 {
+        let metrics = self.measure_string(text, Point::zero(), Baseline::Top);
+        if suffix.is_empty() {
+            return metrics.bounding_box.size.width;
+        }
+        let suffix = self.measure_string(suffix, metrics.next_position, Baseline::Top);
+        envelope(&metrics.bounding_box, &suffix.bounding_box)
+            .size
+            .width
+    }
+
+    /// Start of `text` to draw followed by `ellipsis` to fit in `width` pixels.
+    ///
+    /// Returns the whole text and `false` if it fits. Otherwise returns the longest start of the
+    /// text, without trailing spaces, which fits with the ellipsis drawn after it, and `true`. If
+    /// not even the ellipsis fits, nothing should be drawn and the result is empty and `false`.
+    pub fn truncate_with_ellipsis<'t>(
+        &self,
+        text: &'t str,
+        width: u32,
+        ellipsis: &str,
+    ) -> (&'t str, bool) {
+        if self.width_with_suffix(text, "") <= width {
+            return (text, false);
+        }
+        match self.fit_with_ellipsis(text, width, ellipsis) {
+            Some(text) => (text, true),
+            None => ("", false),
+        }
+    }
+
+    /// Longest start of `text`, without trailing spaces, which fits in `width` pixels with
+    /// `ellipsis` drawn after it, or `None` if not even the ellipsis fits.
+    pub(crate) fn fit_with_ellipsis<'t>(
+        &self,
+        text: &'t str,
+        width: u32,
+        ellipsis: &str,
+    ) -> Option<&'t str> {
+        if self.width_with_suffix("", ellipsis) > width {
+            return None;
+        }
+
+        let mut ends: Vec<usize> = Vec::new();
+        let mut end = 0;
+        for cluster in Clusters::new(text) {
+            end += cluster.len();
+            ends.push(end);
+        }
+        let fitting = ends.partition_point(|&end| {
+            self.width_with_suffix(text[..end].trim_end(), ellipsis) <= width
+        });
+        match fitting {
+            0 => Some(""),
+            n => Some(text[..ends[n - 1]].trim_end()),
+        }
+    }
+
+    /// Ellipsis character if the font has it, three dots otherwise.
+    pub(crate) fn ellipsis(&self) -> &'static str {
+        if self.font.glyph('…').id().0 != 0 {
+            "…"
+        } else {
+            "..."
+        }
+    }
+
+    /// Draw `text`, cut and followed by `ellipsis` if it is wider than `width` pixels.
+    ///
+    /// See [`truncate_with_ellipsis`](Self::truncate_with_ellipsis), the drawn text is at most
+    /// `width` pixels wide. Returns the position following the last character.
+    pub fn draw_truncated<D>(
+        &self,
+        text: &str,
+        position: Point,
+        baseline: Baseline,
+        width: u32,
+        ellipsis: &str,
+        target: &mut D,
+    ) -> Result<Point, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let (text, ellipsis_follows) = self.truncate_with_ellipsis(text, width, ellipsis);
+        let end = self.draw_string(text, position, baseline, target)?;
+        if ellipsis_follows {
+            self.draw_string(ellipsis, end, baseline, target)
+        } else {
+            Ok(end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::FontTextStyleBuilder;
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        pixelcolor::Rgb888,
+        prelude::*,
+        text::{renderer::TextRenderer, Baseline},
+    };
+    use rusttype::Font;
+
+    #[test]
+    fn clusters_are_not_split() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::<Rgb888>::new(font)
+            .font_size(20)
+            .build();
+        let width = |text| {
+            style
+                .measure_string(text, Point::zero(), Baseline::Top)
+                .bounding_box
+                .size
+                .width
+        };
+
+        assert_eq!(style.truncate("abc", 100), "abc");
+        assert_eq!(style.truncate("abc", width("ab")), "ab");
+        assert_eq!(style.truncate("abc", 0), "");
+        let text = "e\u{301}e\u{301}";
+        assert_eq!(style.truncate(text, width("e\u{301}")), "e\u{301}");
+    }
+
+    #[test]
+    fn ellipsis_fits_the_width() {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        let style = FontTextStyleBuilder::new(font)
+            .font_size(20)
+            .text_color(Rgb888::WHITE)
+            .build();
+
+        assert_eq!(
+            style.truncate_with_ellipsis("abc", 100, "…"),
+            ("abc", false)
+        );
+        for width in 0..60 {
+            let (text, ellipsis) = style.truncate_with_ellipsis("ab cdef", width, "…");
+            let suffix = if ellipsis { "…" } else { "" };
+            assert!(style.width_with_suffix(text, suffix) <= width);
+            assert!(!text.ends_with(' '));
+
+            let mut display = MockDisplay::new();
+            display.set_allow_out_of_bounds_drawing(true);
+            style
+                .draw_truncated(
+                    "ab cdef",
+                    Point::zero(),
+                    Baseline::Top,
+                    width,
+                    "…",
+                    &mut display,
+                )
+                .unwrap();
+            assert!(display.affected_area().size.width <= width);
+        }
+        let width = style.width_with_suffix("ab", "...");
+        assert_eq!(
+            style.truncate_with_ellipsis("ab cdef", width, "..."),
+            ("ab", true)
+        );
+        assert_eq!(style.truncate_with_ellipsis("ab cdef", 1, "…"), ("", false));
+    }
+}
@@ -0,0 +1,655 @@
+//! Word wrapping.
+//!
+//! [`WrappedText`] breaks each line of a text into lines fitting a width:
+//! * lines are broken after spaces, except no-break spaces,
+//! * a soft hyphen is a break opportunity, drawn as a hyphen when the line is broken there,
+//! * a word wider than the whole width is broken between clusters.
+//!
+//! With the `unicode-linebreak` feature, break opportunities are the ones of UAX #14 instead:
+//! lines are also broken after CJK ideographs and hyphens, but never before closing punctuation.
+//!
+//! Paragraphs are laid out like in a [`TextBox`](crate::TextBox), with the spacing and
+//! indentation of a [`TextBoxStyle`]. The first line of each paragraph and the following ones can
+//! be indented separately, for bulleted lists and definitions. The number of lines can be
+//! limited, the last one ending with an ellipsis if text was left out.
+
+use crate::{
+    cluster::{Clusters, SOFT_HYPHEN},
+    stdlib::{fmt::Debug, vec::Vec},
+    ColorBlend, FontTextStyle, ParagraphBreak, TextBoxStyle, VerticalOverflow,
+};
+
+use embedded_graphics::{
+    prelude::*,
+    primitives::Rectangle,
+    text::{renderer::TextRenderer, Baseline},
+};
+
+/// Drawn at the end of a line broken at a soft hyphen.
+const HYPHEN: &str = "-";
+
+/// A line of wrapped text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct WrappedLine<'a> {
+    /// Text of the line, without the spaces where it was broken.
+    pub(crate) text: &'a str,
+    /// Whether the line was broken at a soft hyphen.
+    pub(crate) hyphen: bool,
+    /// Whether the line is followed by an ellipsis.
+    pub(crate) ellipsis: bool,
+    /// Indentation of the line, in pixels.
+    pub(crate) indent: u32,
+    /// Whether the line starts a paragraph.
+    pub(crate) starts_paragraph: bool,
+}
+
+/// Lines of a text, with whether each line starts a paragraph.
+///
+/// Blank lines separating paragraphs are not returned.
+pub(crate) struct Paragraphs<'a> {
+    lines: core::str::Lines<'a>,
+    paragraph_break: ParagraphBreak,
+    first: bool,
+}
+
+impl<'a> Paragraphs<'a> {
+    pub(crate) fn new(text: &'a str, paragraph_break: ParagraphBreak) -> Self {
+        Paragraphs {
+            lines: text.lines(),
+            paragraph_break,
+            first: true,
+        }
+    }
+}
+
+impl<'a> Iterator for Paragraphs<'a> {
+    type Item = (&'a str, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        let starts_paragraph = match self.paragraph_break {
+            ParagraphBreak::Newline => true,
+            ParagraphBreak::BlankLine => {
+                if line.trim().is_empty() {
+                    // skip the blank lines, the next line starts a paragraph
+                    let line = self.lines.find(|line| !line.trim().is_empty())?;
+                    self.first = false;
+                    return Some((line, true));
+                }
+                self.first
+            }
+        };
+        self.first = false;
+        Some((line, starts_paragraph))
+    }
+}
+
+/// Possible break in a line of text.
+#[derive(Debug, Clone, Copy)]
+struct Break {
+    /// End of the text before the break.
+    end: usize,
+    /// Start of the text after the break.
+    next: usize,
+    hyphen: bool,
+}
+
+/// Whether a line may be broken after `c`.
+fn is_break_space(c: char) -> bool {
+    c.is_whitespace() && !matches!(c, '\u{A0}' | '\u{2007}' | '\u{202F}')
+}
+
+/// Possible breaks of a line, in order.
+#[cfg(not(feature = "unicode-linebreak"))]
+fn breaks(line: &str) -> impl Iterator<Item = Break> + '_ {
+    let mut chars = line.char_indices().peekable();
+    core::iter::from_fn(move || loop {
+        let (i, c) = chars.next()?;
+        if c == SOFT_HYPHEN && i > 0 {
+            let next = i + c.len_utf8();
+            return Some(Break {
+                end: i,
+                next,
+                hyphen: true,
+            });
+        }
+        if is_break_space(c) {
+            let mut next = i + c.len_utf8();
+            while let Some((j, c)) = chars.next_if(|(_, c)| is_break_space(*c)) {
+                next = j + c.len_utf8();
+            }
+            // spaces starting the line are an indentation, not a break
+            if i > 0 && next < line.len() {
+                return Some(Break {
+                    end: i,
+                    next,
+                    hyphen: false,
+                });
+            }
+        }
+    })
+}
+
+/// Possible breaks of a line, in order, from the Unicode line breaking algorithm.
+#[cfg(feature = "unicode-linebreak")]
+fn breaks(line: &str) -> impl Iterator<Item = Break> + '_ {
+    unicode_linebreak::linebreaks(line).filter_map(move |(next, _)| {
+        // the end of the line is a break, and so are lines in the text, which is already split
+        if next >= line.len() {
+            return None;
+        }
+        let before = line[..next].trim_end_matches(is_break_space);
+        let (end, hyphen) = match before.strip_suffix(SOFT_HYPHEN) {
+            Some(text) => (text.len(), true),
+            None => (before.len(), false),
+        };
+        // spaces starting the line are an indentation, not a break
+        (end > 0).then_some(Break { end, next, hyphen })
+    })
+}
+
+impl<C> FontTextStyle<C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Whether `text`, followed by a hyphen if `hyphen` is set, is at most `width` pixels wide.
+    fn fits(&self, text: &str, hyphen: bool, width: u32) -> bool {
+        let suffix = if hyphen { HYPHEN } else { "" };
+        self.width_with_suffix(text, suffix) <= width
+    }
+
+    /// Break the lines of `text` into lines at most `width` pixels wide, indentation included,
+    /// with the paragraphs of `layout`.
+    pub(crate) fn wrap<'t>(
+        &self,
+        text: &'t str,
+        width: u32,
+        layout: &TextBoxStyle,
+    ) -> Vec<WrappedLine<'t>> {
+        let mut lines = Vec::new();
+        for (line, starts_paragraph) in Paragraphs::new(text, layout.paragraph_break) {
+            self.wrap_line(line, width, starts_paragraph, layout, &mut lines);
+        }
+        lines
+    }
+
+    fn wrap_line<'t>(
+        &self,
+        line: &'t str,
+        width: u32,
+        mut starts_paragraph: bool,
+        layout: &TextBoxStyle,
+        lines: &mut Vec<WrappedLine<'t>>,
+    ) {
+        let mut rest = line;
+        loop {
+            let line_indent = layout.line_indent(starts_paragraph);
+            let width = width.saturating_sub(line_indent);
+            let end = rest.trim_end_matches(is_break_space);
+            if self.fits(end, false, width) {
+                lines.push(WrappedLine {
+                    text: end,
+                    hyphen: false,
+                    ellipsis: false,
+                    indent: line_indent,
+                    starts_paragraph,
+                });
+                return;
+            }
+
+            let fitting = breaks(rest)
+                .take_while(|b| self.fits(&rest[..b.end], b.hyphen, width))
+                .last();
+            let next = match fitting {
+                Some(b) => {
+                    lines.push(WrappedLine {
+                        text: &rest[..b.end],
+                        hyphen: b.hyphen,
+                        ellipsis: false,
+                        indent: line_indent,
+                        starts_paragraph,
+                    });
+                    b.next
+                }
+                // the first word is too wide, it is broken between clusters
+                None => {
+                    let mut text = self.truncate(rest, width);
+                    if text.is_empty() {
+                        text = Clusters::new(rest).next().unwrap_or(rest);
+                    }
+                    lines.push(WrappedLine {
+                        text,
+                        hyphen: false,
+                        ellipsis: false,
+                        indent: line_indent,
+                        starts_paragraph,
+                    });
+                    text.len()
+                }
+            };
+            rest = &rest[next..];
+            if rest.is_empty() {
+                return;
+            }
+            starts_paragraph = false;
+        }
+    }
+}
+
+/// Lines and height of wrapped text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WrapMetrics {
+    /// Number of lines.
+    pub lines: u32,
+    /// Height of the lines, in pixels.
+    pub height: u32,
+}
+
+/// Text wrapped to a maximum width.
+///
+/// Lines are stacked from the top left corner `position`, `line_height()` pixels apart, and
+/// paragraphs are separated and indented as set by the [`TextBoxStyle`]. Indented lines are
+/// narrower, so that all lines end within `width` pixels of `position`.
+#[derive(Debug, Clone)]
+pub struct WrappedText<'a, C> {
+    /// The text.
+    pub text: &'a str,
+
+    /// Position of the top left corner of the text.
+    pub position: Point,
+
+    /// Character style.
+    pub style: FontTextStyle<C>,
+
+    /// Maximum width of a line, in pixels.
+    pub width: u32,
+
+    /// Layout of the paragraphs.
+    pub textbox_style: TextBoxStyle,
+
+    /// Background color of every other line, starting with the second one.
+    ///
+    /// When set, line backgrounds span the width of the text, the other lines use the background
+    /// color of the character style.
+    pub alternate_background_color: Option<C>,
+}
+
+impl<'a, C> WrappedText<'a, C> {
+    /// Create text wrapped to `width` pixels, with the default layout style.
+    pub fn new(text: &'a str, position: Point, style: FontTextStyle<C>, width: u32) -> Self {
+        Self::with_textbox_style(text, position, style, width, TextBoxStyle::default())
+    }
+
+    /// Create text wrapped to `width` pixels, with a layout style.
+    pub fn with_textbox_style(
+        text: &'a str,
+        position: Point,
+        style: FontTextStyle<C>,
+        width: u32,
+        textbox_style: TextBoxStyle,
+    ) -> Self {
+        WrappedText {
+            text,
+            position,
+            style,
+            width,
+            textbox_style,
+            alternate_background_color: None,
+        }
+    }
+
+    /// Fill the background of every other line with `color`, like a zebra striped table.
+    pub fn alternate_background_color(mut self, color: C) -> Self {
+        self.alternate_background_color = Some(color);
+        self
+    }
+}
+
+impl<'a, C> WrappedText<'a, C>
+where
+    C: ColorBlend + Debug,
+{
+    /// Wrapped lines, without the ones beyond the maximum line count of the layout style.
+    ///
+    /// With [`VerticalOverflow::Ellipsis`], the last line is cut to fit the width with the
+    /// ellipsis drawn after it.
+    fn lines(&self) -> Vec<WrappedLine<'a>> {
+        let mut lines = self.style.wrap(self.text, self.width, &self.textbox_style);
+        let max_lines = self
+            .textbox_style
+            .max_lines
+            .map_or(usize::MAX, |n| n as usize);
+        if lines.len() <= max_lines {
+            return lines;
+        }
+        lines.truncate(max_lines);
+        if self.textbox_style.vertical_overflow == VerticalOverflow::Ellipsis {
+            if let Some(last) = lines.last_mut() {
+                let width = self.width.saturating_sub(last.indent);
+                let ellipsis = self.style.ellipsis();
+                if let Some(text) = self.style.fit_with_ellipsis(last.text, width, ellipsis) {
+                    last.text = text;
+                    last.hyphen = false;
+                    last.ellipsis = true;
+                }
+            }
+        }
+        lines
+    }
+
+    /// Lines and height of the text, without drawing it.
+    pub fn measure(&self) -> WrapMetrics {
+        self.metrics(&self.lines())
+    }
+
+    /// Size of the drawn lines, from the top left corner of the text.
+    pub(crate) fn size(&self) -> Size {
+        let lines = self.lines();
+        let width = lines
+            .iter()
+            .map(|line| line.indent + self.style.width_with_suffix(line.text, self.suffix(line)))
+            .max()
+            .unwrap_or(0);
+        Size::new(width, self.metrics(&lines).height)
+    }
+
+    /// Text drawn after a line, a hyphen or an ellipsis.
+    fn suffix(&self, line: &WrappedLine<'_>) -> &'static str {
+        match (line.hyphen, line.ellipsis) {
+            (true, _) => HYPHEN,
+            (_, true) => self.style.ellipsis(),
+            _ => "",
+        }
+    }
+
+    /// Lines and height of wrapped lines, paragraph spacing included.
+    fn metrics(&self, lines: &[WrappedLine<'_>]) -> WrapMetrics {
+        let paragraphs = lines.iter().filter(|line| line.starts_paragraph).count() as u32;
+        let spacing = paragraphs.saturating_sub(1) * self.textbox_style.paragraph_spacing;
+        WrapMetrics {
+            lines: lines.len() as u32,
+            height: lines.len() as u32 * self.style.line_height() + spacing,
+        }
+    }
+}
+
+impl<C> Drawable for WrappedText<'_, C>
+where
+    C: ColorBlend + Debug,
+{
+    type Color = C;
+    type Output = WrapMetrics;
+
+    /// Draw the text, returns the number of lines and their height.
+    fn draw<D>(&self, target: &mut D) -> Result<WrapMetrics, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let line_height = self.style.line_height();
+        let lines = self.lines();
+
+        // striped lines need a style blending with the stripe color, in the antialiasing mode of
+        // the character style
+        let alternate_style = self.alternate_background_color.map(|color| {
+            let mut style = self.style.clone();
+            style.background_color = Some(color);
+            style
+        });
+
+        let mut position = self.position;
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_paragraph && i > 0 {
+                position.y += self.textbox_style.paragraph_spacing as i32;
+            }
+            let style = match &alternate_style {
+                Some(alternate_style) if i % 2 == 1 => alternate_style,
+                _ => &self.style,
+            };
+            if let (Some(_), Some(color)) = (&alternate_style, style.colors().1) {
+                let stripe = Rectangle::new(position, Size::new(self.width, line_height));
+                target.fill_solid(&stripe, color)?;
+            }
+            let start = position + Point::new(line.indent as i32, 0);
+            let end = style.draw_string(line.text, start, Baseline::Top, target)?;
+            let suffix = self.suffix(line);
+            if !suffix.is_empty() {
+                style.draw_string(suffix, end, Baseline::Top, target)?;
+            }
+            position.y += line_height as i32;
+        }
+        Ok(self.metrics(&lines))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FontTextStyleBuilder, TextBoxStyleBuilder};
+    use embedded_graphics::{mock_display::MockDisplay, pixelcolor::Rgb888};
+    use rusttype::Font;
+
+    fn style() -> FontTextStyle<Rgb888> {
+        let font = Font::try_from_bytes(include_bytes!("../assets/Roboto-Regular.ttf")).unwrap();
+        FontTextStyleBuilder::new(font)
+            .font_size(12)
+            .text_color(Rgb888::WHITE)
+            .build()
+    }
+
+    fn width(text: &str) -> u32 {
+        style()
+            .measure_string(text, Point::zero(), Baseline::Top)
+            .bounding_box
+            .size
+            .width
+    }
+
+    fn lines(text: &str, width: u32) -> Vec<(&str, bool)> {
+        style()
+            .wrap(text, width, &TextBoxStyle::default())
+            .into_iter()
+            .map(|line| (line.text, line.hyphen))
+            .collect()
+    }
+
+    #[test]
+    fn lines_are_broken_at_spaces() {
+        assert_eq!(
+            lines("one two three four", width("one two")),
+            [("one two", false), ("three", false), ("four", false)]
+        );
+        assert_eq!(
+            lines("one  two\nthree", 1000),
+            [("one  two", false), ("three", false)]
+        );
+        // no-break spaces keep words together
+        assert_eq!(
+            lines("one two\u{A0}three", width("one two\u{A0}thr")),
+            [("one", false), ("two\u{A0}three", false)]
+        );
+    }
+
+    #[test]
+    fn soft_hyphens_are_breaks() {
+        // the hyphen is drawn after the line, up to a pixel further than in a single string
+        let width = width("wrap-") + 1;
+        assert_eq!(
+            lines("wrap\u{AD}ping", width),
+            [("wrap", true), ("ping", false)]
+        );
+        assert_eq!(lines("wrap\u{AD}ping", 1000), [("wrap\u{AD}ping", false)]);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-linebreak")]
+    fn breaks_follow_uax14() {
+        let ends = |line| breaks(line).map(|b| (b.end, b.hyphen)).collect::<Vec<_>>();
+        // after each ideograph, but not before the full stop
+        assert_eq!(ends("日本語。"), [(3, false), (6, false)]);
+        assert_eq!(ends("well-known fact"), [(5, false), (10, false)]);
+        assert_eq!(ends("  wrap\u{AD}ping"), [(6, true)]);
+    }
+
+    #[test]
+    fn long_words_are_broken() {
+        let width = width("abc");
+        assert_eq!(
+            lines("abcdefg", width),
+            [("abc", false), ("def", false), ("g", false)]
+        );
+        // a single cluster wider than the line is kept whole
+        assert_eq!(lines("WW", 1), [("W", false), ("W", false)]);
+    }
+
+    #[test]
+    fn wrapped_text_is_drawn() {
+        let text = WrappedText::new("one two wrap\u{AD}ping", Point::zero(), style(), 30);
+        let mut display = MockDisplay::new();
+        display.set_allow_out_of_bounds_drawing(true);
+        let metrics = text.draw(&mut display).unwrap();
+        assert_eq!(metrics, text.measure());
+        assert_eq!(
+            metrics.lines,
+            style().wrap(text.text, 30, &TextBoxStyle::default()).len() as u32
+        );
+        assert_eq!(metrics.height, metrics.lines * 12);
+        assert!(display.affected_area().size.width <= 30);
+    }
+
+    #[test]
+    fn lines_are_indented() {
+        let layout = TextBoxStyleBuilder::new()
+            .first_line_indent(5)
+            .hanging_indent(width("- "))
+            .build();
+        let wrapped: Vec<_> = style()
+            .wrap("- one two three\n- four", width("- one two") + 5, &layout)
+            .into_iter()
+            .map(|line| (line.text, line.indent))
+            .collect();
+        assert_eq!(
+            wrapped,
+            [
+                ("- one two", 5),
+                ("three", layout.hanging_indent),
+                ("- four", 5)
+            ]
+        );
+
+        let layout = TextBoxStyleBuilder::new().hanging_indent(10).build();
+        let text =
+            WrappedText::with_textbox_style("- one two three", Point::zero(), style(), 40, layout);
+        let mut display = MockDisplay::new();
+        text.draw(&mut display).unwrap();
+        // the second line starts at the indentation
+        let second = Rectangle::new(Point::new(0, 12), Size::new(64, 12));
+        let mut start = 64;
+        for point in second.points() {
+            if display.get_pixel(point).is_some() {
+                start = start.min(point.x);
+            }
+        }
+        assert!((10..12).contains(&start), "{}", start);
+        assert!(display.affected_area().bottom_right().unwrap().x < 40);
+    }
+
+    #[test]
+    fn lines_are_limited() {
+        let text = "one two three four";
+        let wrapped = WrappedText::new(text, Point::zero(), style(), width("one two"));
+        assert_eq!(wrapped.measure().lines, 3);
+        let mut clipped = wrapped.clone();
+        clipped.textbox_style = TextBoxStyleBuilder::new().max_lines(2).build();
+        assert_eq!(clipped.measure().lines, 2);
+        assert_eq!(
+            clipped
+                .lines()
+                .last()
+                .map(|line| (line.text, line.ellipsis)),
+            Some(("three", false))
+        );
+
+        let mut ellipsized = clipped;
+        ellipsized.textbox_style.vertical_overflow = VerticalOverflow::Ellipsis;
+        let last = ellipsized.lines().pop().unwrap();
+        assert!(last.ellipsis);
+        assert!(style().width_with_suffix(last.text, "…") <= width("one two"));
+
+        let mut display = MockDisplay::new();
+        let metrics = ellipsized.draw(&mut display).unwrap();
+        assert_eq!(
+            metrics,
+            WrapMetrics {
+                lines: 2,
+                height: 24
+            }
+        );
+        let mut expected = MockDisplay::new();
+        style()
+            .draw_string("one two", Point::zero(), Baseline::Top, &mut expected)
+            .unwrap();
+        let end = style()
+            .draw_string(last.text, Point::new(0, 12), Baseline::Top, &mut expected)
+            .unwrap();
+        style()
+            .draw_string("…", end, Baseline::Top, &mut expected)
+            .unwrap();
+        display.assert_eq(&expected);
+    }
+
+    #[test]
+    fn wrapped_paragraphs_are_spaced() {
+        let textbox_style = TextBoxStyleBuilder::new()
+            .paragraph_break(ParagraphBreak::BlankLine)
+            .paragraph_spacing(5)
+            .first_line_indent(6)
+            .build();
+        let text = "one two three\nfour\n\nfive";
+        let wrapped = WrappedText::with_textbox_style(
+            text,
+            Point::zero(),
+            style(),
+            width("one two") + 6,
+            textbox_style,
+        );
+        let lines: Vec<_> = wrapped
+            .lines()
+            .into_iter()
+            .map(|line| (line.text, line.indent, line.starts_paragraph))
+            .collect();
+        // a single line break continues the paragraph, without indentation
+        assert_eq!(
+            lines,
+            [
+                ("one two", 6, true),
+                ("three", 0, false),
+                ("four", 0, false),
+                ("five", 6, true)
+            ]
+        );
+        assert_eq!(
+            wrapped.measure(),
+            WrapMetrics {
+                lines: 4,
+                height: 4 * 12 + 5
+            }
+        );
+
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        wrapped.draw(&mut display).unwrap();
+        let mut expected = MockDisplay::new();
+        expected.set_allow_overdraw(true);
+        for (text, position) in [
+            ("one two", Point::new(6, 0)),
+            ("three", Point::new(0, 12)),
+            ("four", Point::new(0, 24)),
+            ("five", Point::new(6, 41)),
+        ] {
+            style()
+                .draw_string(text, position, Baseline::Top, &mut expected)
+                .unwrap();
+        }
+        display.assert_eq(&expected);
+    }
+}